@@ -0,0 +1,32 @@
+CREATE TABLE IF NOT EXISTS celestial_region (
+  id INTEGER NOT NULL PRIMARY KEY,
+  name TEXT NOT NULL UNIQUE,
+  description TEXT,
+  created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+  updated_at INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS celestial_subregion (
+  id INTEGER NOT NULL PRIMARY KEY,
+  name TEXT NOT NULL UNIQUE,
+  description TEXT,
+  created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+  updated_at INTEGER
+);
+
+-- SQLite cannot `ALTER TABLE ... ADD COLUMN` with a non-constant default, so the
+-- body table is rebuilt with the region/subregion references and timestamps.
+CREATE TABLE celestial_body_new (
+  id INTEGER NOT NULL PRIMARY KEY,
+  name TEXT NOT NULL UNIQUE,
+  region INTEGER REFERENCES celestial_region (id),
+  subregion INTEGER REFERENCES celestial_subregion (id),
+  created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+  updated_at INTEGER
+);
+
+INSERT INTO celestial_body_new (id, name) SELECT id, name FROM celestial_body;
+
+DROP TABLE celestial_body;
+
+ALTER TABLE celestial_body_new RENAME TO celestial_body;
@@ -0,0 +1,4 @@
+-- Orbital extremes, both in km.
+ALTER TABLE celestial_body ADD COLUMN aphelion REAL NOT NULL DEFAULT 0;
+
+ALTER TABLE celestial_body ADD COLUMN perihelion REAL NOT NULL DEFAULT 0;
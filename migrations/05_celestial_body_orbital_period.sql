@@ -0,0 +1,2 @@
+-- Sidereal orbital period, in days.
+ALTER TABLE celestial_body ADD COLUMN orbital_period REAL NOT NULL DEFAULT 0;
@@ -0,0 +1,18 @@
+-- Rebuild the user table so that:
+-- 1. the name column is `username`, matching the model.
+-- 2. the password may be NULL, as it is set in a second step after the insert.
+-- 3. the salts are generated by the database on insert.
+CREATE TABLE user_new (
+  id INTEGER NOT NULL PRIMARY KEY,
+  username TEXT NOT NULL UNIQUE,
+  pwd TEXT,
+  pwd_salt TEXT NOT NULL DEFAULT (lower(hex(randomblob(16)))),
+  token_salt TEXT NOT NULL DEFAULT (lower(hex(randomblob(16))))
+);
+
+INSERT INTO user_new (id, username, pwd, pwd_salt, token_salt)
+SELECT id, name, pwd, pwd_salt, token_salt FROM user;
+
+DROP TABLE user;
+
+ALTER TABLE user_new RENAME TO user;
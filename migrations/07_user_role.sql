@@ -0,0 +1,2 @@
+-- One of 'viewer', 'editor' or 'admin'.
+ALTER TABLE user ADD COLUMN role TEXT NOT NULL DEFAULT 'viewer';
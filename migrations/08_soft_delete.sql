@@ -0,0 +1,8 @@
+-- Soft deletes: a row is "deleted" by stamping `deleted_at`, so references to it
+-- survive and the history is kept. Reads filter on `deleted_at IS NULL`.
+-- NOTE: deleted rows still hold their `name`, so the UNIQUE constraint applies to them.
+ALTER TABLE celestial_region ADD COLUMN deleted_at INTEGER;
+
+ALTER TABLE celestial_subregion ADD COLUMN deleted_at INTEGER;
+
+ALTER TABLE celestial_body ADD COLUMN deleted_at INTEGER;
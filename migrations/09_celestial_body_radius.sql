@@ -0,0 +1,2 @@
+-- Mean radius of the body, in km.
+ALTER TABLE celestial_body ADD COLUMN radius REAL NOT NULL DEFAULT 0;
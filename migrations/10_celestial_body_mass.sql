@@ -0,0 +1,2 @@
+-- Mass of the body, in kg.
+ALTER TABLE celestial_body ADD COLUMN mass REAL NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+-- The body this one orbits, *eg* a moon's planet. NULL means it orbits the Sun.
+ALTER TABLE celestial_body ADD COLUMN parent_id INTEGER REFERENCES celestial_body (id);
@@ -0,0 +1,3 @@
+-- The region this subregion is part of. NULL only for subregions created before
+-- the link existed; new subregions must name one.
+ALTER TABLE celestial_subregion ADD COLUMN region_id INTEGER REFERENCES celestial_region (id);
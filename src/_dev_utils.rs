@@ -1,70 +1,145 @@
 //! Utilities for local development.
 //!
 //! TODO: document
-use sqlx::sqlite::{Sqlite, SqliteConnectOptions, SqlitePoolOptions};
+use crate::data_access::configured_pool_options;
+use sqlx::migrate::Migrator;
+use sqlx::sqlite::{Sqlite, SqliteConnectOptions};
 use sqlx::Pool;
-use std::fs;
-use std::path::PathBuf;
-use std::time::Duration;
 use tokio::sync::OnceCell;
 
 type Db = Pool<Sqlite>;
 
 // Hardcoded config values to ensure no overlap with the real config.
 const DATABASE_URL: &str = ":memory:";
-// Hardcoded path to the SQL files used for testing.
-const SQL_DIRECTORY: &str = "migrations";
+// Set this to a file path to run the development database on disk instead, so it
+// can be inspected with the `sqlite3` CLI. Tests always use `DATABASE_URL`.
+const DATABASE_FILE_OVERRIDE: &str = "DEV_DATABASE_FILE";
+
+/// The files in `migrations/`, embedded at compile time. Applied versions (and their
+/// checksums) are recorded in `_sqlx_migrations`, so re-running is a no-op and an
+/// edited migration is reported as an error rather than silently skipped.
+static MIGRATOR: Migrator = sqlx::migrate!();
 
 async fn create_development_database_pool(db_connection_url: &str) -> Result<Db, sqlx::Error> {
     let connection_options = SqliteConnectOptions::new()
         .filename(db_connection_url)
         .create_if_missing(true);
 
-    let connection_pool = SqlitePoolOptions::new()
+    // NOTE: each connection to `:memory:` gets its own, empty, database, so the
+    //       pool is capped at one connection whatever the config says.
+    let connection_pool = configured_pool_options()
         .max_connections(1)
-        .acquire_timeout(Duration::from_millis(500))
+        .min_connections(0)
         .connect_with(connection_options)
         .await;
 
     connection_pool
 }
 
-/// Manually execute the SQL files in the `sql/dev` folder.
-/// This is fiddly, but absolutely ensures control over the initialisation of the database.
-async fn execute_sql_statements_from_file(db: &Db, file: &str) -> Result<(), sqlx::Error> {
-    let sql_file_content = fs::read_to_string(file)?;
-    let sql_statements: Vec<&str> = sql_file_content.split(';').collect();
+/// Initialise the development/test database, bringing it up to the latest migration.
+async fn initialise_development_database(
+    db_connection_url: &str,
+) -> Result<Db, Box<dyn std::error::Error>> {
+    let db_pool = create_development_database_pool(db_connection_url).await?;
+    MIGRATOR.run(&db_pool).await?;
+
+    Ok(db_pool)
+}
+
+// -----------------------------------------------------------------------------
+// Seed data
+// -----------------------------------------------------------------------------
 
-    for statement in sql_statements {
-        sqlx::query(statement).execute(db).await?;
+/// The canonical regions, as `(name, description)`.
+#[rustfmt::skip]
+const SEED_REGIONS: [(&str, &str); 4] = [
+    ("Inner Solar System", "The terrestrial planets and the asteroid belt."),
+    ("Outer Solar System", "The giant planets and the centaurs."),
+    ("Trans-Neptunian", "Everything orbiting beyond Neptune."),
+    ("Farthest Regions", "The detached objects and the Oort cloud."),
+];
+
+/// One subregion per `CelestialSubregion` variant, as `(name, region, description)`.
+#[rustfmt::skip]
+const SEED_SUBREGIONS: [(&str, &str, &str); 7] = [
+    ("Inner Planets",    "Inner Solar System", "Mercury, Venus, Earth and Mars."),
+    ("Asteroid Belt",    "Inner Solar System", "Between the orbits of Mars and Jupiter."),
+    ("Outer Planets",    "Outer Solar System", "Jupiter, Saturn, Uranus and Neptune."),
+    ("Centaurs",         "Outer Solar System", "Small bodies orbiting between Jupiter and Neptune."),
+    ("Kuiper Belt",      "Trans-Neptunian",    "The disc of icy bodies beyond Neptune."),
+    ("Scattered Disc",   "Trans-Neptunian",    "Icy bodies on eccentric orbits beyond the Kuiper belt."),
+    ("Detached Objects", "Farthest Regions",   "Bodies whose perihelia lie well beyond Neptune."),
+];
+
+/// The planets, as `(name, region, subregion, aphelion, perihelion, orbital_period, radius, mass)`.
+/// Distances/radii are in km, periods in days, masses in kg.
+#[rustfmt::skip]
+const SEED_PLANETS: [(&str, &str, &str, f64, f64, f64, f64, f64); 8] = [
+    ("Mercury", "Inner Solar System", "Inner Planets",    69_816_900.0,    46_001_200.0,     87.969,  2_439.7, 3.301e23),
+    ("Venus",   "Inner Solar System", "Inner Planets",   108_939_000.0,   107_477_000.0,    224.701,  6_051.8, 4.867e24),
+    ("Earth",   "Inner Solar System", "Inner Planets",   152_100_000.0,   147_095_000.0,    365.256,  6_371.0, 5.972e24),
+    ("Mars",    "Inner Solar System", "Inner Planets",   249_200_000.0,   206_700_000.0,    686.980,  3_389.5, 6.417e23),
+    ("Jupiter", "Outer Solar System", "Outer Planets",   816_363_000.0,   740_595_000.0,  4_332.59,  69_911.0, 1.898e27),
+    ("Saturn",  "Outer Solar System", "Outer Planets", 1_514_500_000.0, 1_352_550_000.0, 10_759.22,  58_232.0, 5.683e26),
+    ("Uranus",  "Outer Solar System", "Outer Planets", 3_006_390_000.0, 2_735_560_000.0, 30_688.5,   25_362.0, 8.681e25),
+    ("Neptune", "Outer Solar System", "Outer Planets", 4_540_000_000.0, 4_460_000_000.0, 60_195.0,   24_622.0, 1.024e26),
+];
+
+/// Populate the regions, subregions and planets. Every insert is `OR IGNORE` on
+/// the unique `name`, so this can safely be run against an already-seeded database.
+async fn insert_seed_data(db: &Db) -> Result<(), sqlx::Error> {
+    for (name, description) in SEED_REGIONS {
+        sqlx::query("INSERT OR IGNORE INTO celestial_region (name, description) VALUES (?1, ?2)")
+            .bind(name)
+            .bind(description)
+            .execute(db)
+            .await?;
     }
 
-    Ok(())
-}
+    for (name, region, description) in SEED_SUBREGIONS {
+        sqlx::query(
+            "INSERT OR IGNORE INTO celestial_subregion (name, region_id, description) \
+             VALUES (?1, (SELECT id FROM celestial_region WHERE name = ?2), ?3)",
+        )
+        .bind(name)
+        .bind(region)
+        .bind(description)
+        .execute(db)
+        .await?;
+    }
 
-/// Initialise the test database
-async fn initialise_development_database() -> Result<Db, Box<dyn std::error::Error>> {
-    let mut sql_filepaths: Vec<PathBuf> = fs::read_dir(SQL_DIRECTORY)?
-        .filter_map(|path_buf| path_buf.ok().map(|pbuf| pbuf.path()))
-        .collect();
-    // NOTE: the SQL files are prefixed sequentially - 00_, 01_, 02_, etc.
-    //       So the `sort` method will ensure they are executed in the correct order.
-    sql_filepaths.sort();
-
-    let db_pool = create_development_database_pool(DATABASE_URL).await?;
-
-    for sql_filepath in sql_filepaths {
-        if let Some(sql_filepath) = sql_filepath.to_str() {
-            // NOTE: fix for Windows paths.
-            let sql_filepath = sql_filepath.replace("\\", "/");
-            execute_sql_statements_from_file(&db_pool, &sql_filepath).await?;
-        }
+    for (name, region, subregion, aphelion, perihelion, orbital_period, radius, mass) in
+        SEED_PLANETS
+    {
+        sqlx::query(
+            "INSERT OR IGNORE INTO celestial_body \
+             (name, region, subregion, aphelion, perihelion, orbital_period, radius, mass) \
+             VALUES (?1, \
+             (SELECT id FROM celestial_region WHERE name = ?2), \
+             (SELECT id FROM celestial_subregion WHERE name = ?3), \
+             ?4, ?5, ?6, ?7, ?8)",
+        )
+        .bind(name)
+        .bind(region)
+        .bind(subregion)
+        .bind(aphelion)
+        .bind(perihelion)
+        .bind(orbital_period)
+        .bind(radius)
+        .bind(mass)
+        .execute(db)
+        .await?;
     }
 
-    Ok(db_pool)
+    Ok(())
 }
 
+// -----------------------------------------------------------------------------
+// Environments
+// -----------------------------------------------------------------------------
+
 /// Initialise environment from local development.
+/// Set `SEED=1` to also populate the database with the solar system's planets.
 pub async fn initialise_development_environment() {
     // NOTE: Tokio's `OnceCell` is used rather than `OnceLock` from Rust's stdlib.
     //       `OnceCell` is designed for async contexts.
@@ -73,12 +148,17 @@ pub async fn initialise_development_environment() {
     INIT.get_or_init(|| async {
         // NOTE: `unwrap` used here as want eveything to explode as soon as possible if there's an issue;
         //       this would be an unrecoverable error, so no point in trying to handle it.
-        let _ = initialise_development_database().await.unwrap();
+        let db_url =
+            std::env::var(DATABASE_FILE_OVERRIDE).unwrap_or_else(|_| DATABASE_URL.to_string());
+        let db_pool = initialise_development_database(&db_url).await.unwrap();
+        if std::env::var("SEED").as_deref() == Ok("1") {
+            insert_seed_data(&db_pool).await.unwrap();
+        }
     })
     .await;
 }
 
-#[cfg(test)]
+#[cfg(any(test, feature = "test-util"))]
 pub async fn initialise_test_environment() -> crate::data_access::DataAccessManager {
     // NOTE: Exact same setup pattern as the development environment setup, using the OnceCell,
     //       but this time the `DataAccessManager` is returned.
@@ -86,7 +166,7 @@ pub async fn initialise_test_environment() -> crate::data_access::DataAccessMana
 
     let dam = INIT
         .get_or_init(|| async {
-            let db_pool = initialise_development_database().await.unwrap();
+            let db_pool = initialise_development_database(DATABASE_URL).await.unwrap();
             // NOTE: unwrap again here, fail fast and fail early for tests.
             crate::data_access::DataAccessManager::new_from_existing_resources(db_pool)
                 .await
@@ -97,3 +177,219 @@ pub async fn initialise_test_environment() -> crate::data_access::DataAccessMana
     // The data access manager is *designed* to be cloned, so following is fine.
     dam.clone()
 }
+
+/// A running server, for end-to-end tests of the HTTP API: requests go through
+/// the real router, middleware and a real socket, rather than being handed to
+/// the router directly as with `TestClient`.
+#[cfg(any(test, feature = "test-util"))]
+pub struct TestApp {
+    pub addr: std::net::SocketAddr,
+    pub client: reqwest::Client,
+    /// The manager the server was built over, for setting up and checking data
+    /// directly.
+    pub dam: crate::data_access::DataAccessManager,
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl TestApp {
+    /// The absolute URL for `path`, *eg* `/api/v1/regions`.
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{path}", self.addr)
+    }
+}
+
+/// Serve the full application (see `server::serve`) over the test database, on
+/// an ephemeral port. The server runs until the test's runtime shuts down.
+#[cfg(any(test, feature = "test-util"))]
+pub async fn spawn_test_app() -> TestApp {
+    let dam = initialise_test_environment().await;
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let state = crate::web::AppState::new(dam.clone());
+    tokio::spawn(crate::server::serve(
+        listener,
+        state,
+        std::future::pending(),
+    ));
+
+    TestApp {
+        addr,
+        client: reqwest::Client::new(),
+        dam,
+    }
+}
+
+/// Every application table, ordered so that referencing tables come first.
+#[cfg(test)]
+const RESETTABLE_TABLES: [&str; 4] = [
+    "celestial_body",
+    "celestial_subregion",
+    "celestial_region",
+    "user",
+];
+
+/// Empty every application table in the shared test database.
+///
+/// The database behind `initialise_test_environment` lives for the whole test run,
+/// so tests that mutate data (and then assert on totals or listings) must call this
+/// at the top to avoid depending on which tests happened to run before them.
+#[cfg(test)]
+pub async fn reset_database(
+    dam: &crate::data_access::DataAccessManager,
+) -> crate::data_access::Result<()> {
+    dam.truncate(&RESETTABLE_TABLES).await
+}
+
+/// Create a user with the given role and return an `Authorization` header value
+/// carrying a valid token for them, for tests that exercise authenticated routes.
+#[cfg(test)]
+pub async fn create_test_user_authorization(
+    dam: &crate::data_access::DataAccessManager,
+    username: &str,
+    role: crate::Role,
+) -> String {
+    use crate::data_access::model::user::{Server, UserAuth};
+
+    let ctx = crate::RequestContext::root_context();
+    let id = Server::create_for_test(&ctx, dam, username, "welcome", role)
+        .await
+        .unwrap();
+    let user: UserAuth = Server::read(&ctx, dam, id).await.unwrap();
+    let token = crate::security::generate_web_token(&user.username, &user.token_salt).unwrap();
+
+    format!("Bearer {token}")
+}
+
+/// A celestial body to create in tests: `name`, with no placement, parent or orbit.
+///
+/// Tests set the fields they care about with struct update syntax:
+/// `CelestialBodyCreate { aphelion: 1.0, ..body_create("name") }`.
+#[cfg(test)]
+pub fn body_create(name: &str) -> crate::data_access::model::celestial_body::CelestialBodyCreate {
+    use crate::data_access::model::celestial_body::CelestialBodyCreate;
+
+    CelestialBodyCreate {
+        name: name.to_string(),
+        region: None,
+        subregion: None,
+        parent_id: None,
+        aphelion: 0.0,
+        perihelion: 0.0,
+        orbital_period: 0.0,
+        radius: 0.0,
+        mass: 0.0,
+    }
+}
+
+/// A `tracing` writer that collects everything logged into a shared buffer, for
+/// tests that assert on log output.
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub struct CapturedLogs(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+#[cfg(test)]
+impl CapturedLogs {
+    /// Everything logged so far.
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+    }
+}
+
+#[cfg(test)]
+impl std::io::Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_migrations_rerun_is_a_no_op() -> anyhow::Result<()> {
+        let db = create_development_database_pool(DATABASE_URL).await?;
+
+        MIGRATOR.run(&db).await?;
+        let applied: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM _sqlx_migrations")
+            .fetch_one(&db)
+            .await?;
+        assert_eq!(applied, MIGRATOR.iter().count() as i64);
+
+        // A second run finds everything applied (with matching checksums) and does nothing.
+        MIGRATOR.run(&db).await?;
+        let reapplied: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM _sqlx_migrations")
+            .fetch_one(&db)
+            .await?;
+        assert_eq!(reapplied, applied);
+
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_reset_database_empties_tables() -> anyhow::Result<()> {
+        use crate::data_access::model::{celestial_body, celestial_region};
+
+        let dam = initialise_test_environment().await;
+        let ctx = crate::RequestContext::root_context();
+        reset_database(&dam).await?;
+        let region = celestial_region::RegionCreate {
+            name: "test_reset_database".to_string(),
+            description: None,
+        };
+        celestial_region::Client::create(&ctx, &dam, region).await?;
+        create_test_user_authorization(&dam, "test_reset_database", crate::Role::Viewer).await;
+        assert_eq!(
+            celestial_region::Client::read_page(&ctx, &dam, 10, 0)
+                .await?
+                .len(),
+            1
+        );
+
+        reset_database(&dam).await?;
+
+        assert!(celestial_region::Client::read_page(&ctx, &dam, 10, 0)
+            .await?
+            .is_empty());
+        assert_eq!(celestial_body::Client::count(&ctx, &dam).await?, 0);
+        // The username is free again once the user table has been emptied.
+        create_test_user_authorization(&dam, "test_reset_database", crate::Role::Viewer).await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_seed_data_is_idempotent() -> anyhow::Result<()> {
+        // NOTE: a fresh in-memory database, so the shared test database is untouched.
+        let db = initialise_development_database(DATABASE_URL).await.unwrap();
+
+        // Seeding twice must neither fail nor duplicate any rows.
+        insert_seed_data(&db).await?;
+        insert_seed_data(&db).await?;
+
+        let (count, radius, has_region): (i64, f64, bool) = sqlx::query_as(
+            "SELECT COUNT(*), radius, region IS NOT NULL FROM celestial_body WHERE name = 'Earth'",
+        )
+        .fetch_one(&db)
+        .await?;
+        assert_eq!(count, 1);
+        assert_eq!(radius, 6_371.0);
+        assert!(has_region);
+
+        let planets: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM celestial_body")
+            .fetch_one(&db)
+            .await?;
+        assert_eq!(planets, 8);
+
+        Ok(())
+    }
+}
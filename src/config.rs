@@ -6,7 +6,12 @@
 //! NOTE: these are *only* used when running cargo commands. They are not used when running
 //! the application directly, nor are they available to other runtimes during development.
 //!
+//! To cover those cases, `Config::init` will also load a `.env` file if one is present.
+//! Precedence is: real environment variables, then `.env` entries, then field defaults.
+//! Production deployments can skip the file entirely by setting `SKIP_DOTENV`.
+//!
 use envconfig::Envconfig;
+use std::str::FromStr;
 use std::sync::OnceLock;
 
 /// The config need only be loaded once, hence definition as a `static`.
@@ -19,36 +24,269 @@ pub fn get_config() -> &'static Config {
         // Note that this explicity panics if the config cannot be loaded: this
         // is the desired behaviour in this instance - the application cannot
         // run without a config.
-        Config::init_from_env().unwrap_or_else(|exception| {
+        Config::init().unwrap_or_else(|exception| {
             panic!("Failed to load config from environment: {exception:?}");
         })
     })
 }
 
 // NOTE: the config keys use SCREAMING_SNAKE_CASE to match the environment variables.
+//       `Debug` is implemented by hand, below, so that the secrets are redacted.
 #[allow(non_snake_case)]
-#[derive(Debug, Envconfig)]
+#[derive(Envconfig)]
 pub struct Config {
     /// The URL of the database to connect to.
     pub DATABASE_URL: String,
-    /// Adjust pool connections based on usage.
+    /// Adjust pool connections based on usage. Must be within `1..=100`.
     #[envconfig(default = "5")]
     pub DATABASE_POOL_MAX_CONNECTIONS: u32,
-    /// Db setup should fail if the timeout is exceeded.
+    /// Connections the pool keeps open even when idle. At most the maximum.
+    #[envconfig(default = "0")]
+    pub DATABASE_POOL_MIN_CONNECTIONS: u32,
+    /// Db setup should fail if the timeout is exceeded. Must be within `1..=60000`.
     #[envconfig(default = "500")]
     pub DATABASE_POOL_CONNECTION_TIMEOUT_MS: u64,
+    /// Idle connections (beyond the minimum) are closed after this long. Must be non-zero.
+    #[envconfig(default = "600")]
+    pub DATABASE_POOL_IDLE_TIMEOUT_SECONDS: u64,
+    /// How many times a query tries to get a connection from a busy pool, backing
+    /// off between attempts, before failing. Must be within `1..=10`.
+    #[envconfig(default = "3")]
+    pub DATABASE_POOL_ACQUIRE_MAX_ATTEMPTS: u32,
+    /// Queries taking longer than this are logged at `warn` rather than `debug`.
+    #[envconfig(default = "100")]
+    pub SLOW_QUERY_THRESHOLD_MS: u64,
     /// The log level to use for the application.
-    pub RUST_LOG: String,
+    pub RUST_LOG: LogLevel,
     /// The port to listen on for HTTP requests.
     pub SERVER_PORT: u16,
+    /// If `SERVER_PORT` is taken, try the next few ports rather than failing.
+    #[envconfig(default = "false")]
+    pub SERVER_PORT_FALLBACK: bool,
+    /// After a shutdown signal, how long `/ready` reports 503 before new connections
+    /// are refused, giving load balancers time to stop sending traffic.
+    #[envconfig(default = "0")]
+    pub SHUTDOWN_DRAIN_SECONDS: u64,
     /// The path to the folder containing the static files to serve.
     pub ASSETS_FOLDER: String,
+    /// Comma-separated origins allowed to call the API from a browser. Empty means
+    /// same-origin only: no CORS headers are sent.
+    #[envconfig(default = "")]
+    pub ALLOWED_ORIGINS: String,
     /// TODO: document
     pub PASSWORD_KEY: String,
     /// TODO: document
     pub TOKEN_KEY: String,
     /// TODO: document
     pub TOKEN_DURATION_IN_SECONDS: f64,
+    /// How long after expiry a token may still be exchanged for a fresh one.
+    #[envconfig(default = "300")]
+    pub TOKEN_REFRESH_GRACE_SECONDS: f64,
+    /// Reads (`GET`, `HEAD`, `OPTIONS`) allowed per user, or per IP if anonymous.
+    #[envconfig(default = "600")]
+    pub RATE_LIMIT_READS_PER_MINUTE: u32,
+    /// Writes (every other method) allowed per user, or per IP if anonymous.
+    #[envconfig(default = "60")]
+    pub RATE_LIMIT_WRITES_PER_MINUTE: u32,
+    /// How far (as a fraction) a supplied orbital period may deviate from the
+    /// period predicted by Kepler's third law before it is rejected.
+    #[envconfig(default = "0.05")]
+    pub KEPLER_TOLERANCE: f64,
+    /// How long cached region/subregion reads are served for. Zero disables the cache.
+    #[envconfig(default = "60")]
+    pub CACHE_TTL_SECONDS: u64,
+    /// The largest request body accepted by the write routes, in bytes (1 MiB).
+    #[envconfig(default = "1048576")]
+    pub MAX_REQUEST_BODY_BYTES: usize,
+    /// As `MAX_REQUEST_BODY_BYTES`, for file imports (10 MiB).
+    #[envconfig(default = "10485760")]
+    pub MAX_IMPORT_BODY_BYTES: usize,
+    /// How often a WebSocket client is pinged to keep the connection alive. A send
+    /// to the client taking longer than this drops it.
+    #[envconfig(default = "30")]
+    pub WS_PING_SECONDS: u64,
+    /// The `Retry-After` sent with writes refused during maintenance.
+    #[envconfig(default = "60")]
+    pub MAINTENANCE_RETRY_AFTER_SECONDS: u64,
+}
+
+/// The levels accepted for `RUST_LOG`. Parsing is case-insensitive; anything else
+/// fails config loading at startup rather than being silently ignored by `tracing`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    /// The directive to pass to `tracing_subscriber::EnvFilter`.
+    pub fn to_env_filter_directive(&self) -> &'static str {
+        match self {
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        }
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "error" => Ok(LogLevel::Error),
+            "warn" => Ok(LogLevel::Warn),
+            "info" => Ok(LogLevel::Info),
+            "debug" => Ok(LogLevel::Debug),
+            "trace" => Ok(LogLevel::Trace),
+            _ => Err(format!("unknown log level: {s}")),
+        }
+    }
+}
+
+/// If this environment variable is set (to anything), no `.env` file is loaded.
+const SKIP_DOTENV: &str = "SKIP_DOTENV";
+
+impl Config {
+    /// Populate the environment from a `.env` file, if there is one, then load
+    /// the config from the environment. Variables that are already set are *not*
+    /// overridden by the file.
+    pub fn init() -> Result<Self, envconfig::Error> {
+        if std::env::var_os(SKIP_DOTENV).is_none() {
+            // NOTE: a missing `.env` file is not an error, so the result is ignored.
+            dotenvy::dotenv().ok();
+        }
+
+        Self::init_from_env()?.validated()
+    }
+
+    /// Reject values that parse but make no sense, so a misconfigured deployment
+    /// fails at startup rather than at its first database query.
+    fn validated(self) -> Result<Self, envconfig::Error> {
+        let invalid = |name: &'static str| Err(envconfig::Error::ParseError { name });
+
+        if !(1..=100).contains(&self.DATABASE_POOL_MAX_CONNECTIONS) {
+            return invalid("DATABASE_POOL_MAX_CONNECTIONS");
+        }
+        if self.DATABASE_POOL_MIN_CONNECTIONS > self.DATABASE_POOL_MAX_CONNECTIONS {
+            return invalid("DATABASE_POOL_MIN_CONNECTIONS");
+        }
+        if !(1..=60_000).contains(&self.DATABASE_POOL_CONNECTION_TIMEOUT_MS) {
+            return invalid("DATABASE_POOL_CONNECTION_TIMEOUT_MS");
+        }
+        if self.DATABASE_POOL_IDLE_TIMEOUT_SECONDS == 0 {
+            return invalid("DATABASE_POOL_IDLE_TIMEOUT_SECONDS");
+        }
+        if !(1..=10).contains(&self.DATABASE_POOL_ACQUIRE_MAX_ATTEMPTS) {
+            return invalid("DATABASE_POOL_ACQUIRE_MAX_ATTEMPTS");
+        }
+
+        Ok(self)
+    }
+
+    /// Log the loaded config in one `info` event, so there is a record of what
+    /// the process is actually running with. `PASSWORD_KEY` and `TOKEN_KEY` are
+    /// redacted (see `redacted`).
+    pub fn log_effective(&self) {
+        tracing::info!(
+            DATABASE_URL = %self.DATABASE_URL,
+            DATABASE_POOL_MAX_CONNECTIONS = self.DATABASE_POOL_MAX_CONNECTIONS,
+            DATABASE_POOL_MIN_CONNECTIONS = self.DATABASE_POOL_MIN_CONNECTIONS,
+            DATABASE_POOL_CONNECTION_TIMEOUT_MS = self.DATABASE_POOL_CONNECTION_TIMEOUT_MS,
+            DATABASE_POOL_IDLE_TIMEOUT_SECONDS = self.DATABASE_POOL_IDLE_TIMEOUT_SECONDS,
+            DATABASE_POOL_ACQUIRE_MAX_ATTEMPTS = self.DATABASE_POOL_ACQUIRE_MAX_ATTEMPTS,
+            SLOW_QUERY_THRESHOLD_MS = self.SLOW_QUERY_THRESHOLD_MS,
+            RUST_LOG = self.RUST_LOG.to_env_filter_directive(),
+            SERVER_PORT = self.SERVER_PORT,
+            SERVER_PORT_FALLBACK = self.SERVER_PORT_FALLBACK,
+            SHUTDOWN_DRAIN_SECONDS = self.SHUTDOWN_DRAIN_SECONDS,
+            ASSETS_FOLDER = %self.ASSETS_FOLDER,
+            ALLOWED_ORIGINS = %self.ALLOWED_ORIGINS,
+            PASSWORD_KEY = %redacted(&self.PASSWORD_KEY),
+            TOKEN_KEY = %redacted(&self.TOKEN_KEY),
+            TOKEN_DURATION_IN_SECONDS = self.TOKEN_DURATION_IN_SECONDS,
+            TOKEN_REFRESH_GRACE_SECONDS = self.TOKEN_REFRESH_GRACE_SECONDS,
+            RATE_LIMIT_READS_PER_MINUTE = self.RATE_LIMIT_READS_PER_MINUTE,
+            RATE_LIMIT_WRITES_PER_MINUTE = self.RATE_LIMIT_WRITES_PER_MINUTE,
+            KEPLER_TOLERANCE = self.KEPLER_TOLERANCE,
+            CACHE_TTL_SECONDS = self.CACHE_TTL_SECONDS,
+            MAX_REQUEST_BODY_BYTES = self.MAX_REQUEST_BODY_BYTES,
+            MAX_IMPORT_BODY_BYTES = self.MAX_IMPORT_BODY_BYTES,
+            WS_PING_SECONDS = self.WS_PING_SECONDS,
+            MAINTENANCE_RETRY_AFTER_SECONDS = self.MAINTENANCE_RETRY_AFTER_SECONDS,
+            "effective config"
+        );
+    }
+}
+
+/// A stand-in for a secret in logs: only its length, so a missing or truncated
+/// key can still be spotted.
+fn redacted(secret: &str) -> String {
+    format!("<redacted, {} chars>", secret.chars().count())
+}
+
+impl core::fmt::Debug for Config {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        fmt.debug_struct("Config")
+            .field("DATABASE_URL", &self.DATABASE_URL)
+            .field(
+                "DATABASE_POOL_MAX_CONNECTIONS",
+                &self.DATABASE_POOL_MAX_CONNECTIONS,
+            )
+            .field(
+                "DATABASE_POOL_MIN_CONNECTIONS",
+                &self.DATABASE_POOL_MIN_CONNECTIONS,
+            )
+            .field(
+                "DATABASE_POOL_CONNECTION_TIMEOUT_MS",
+                &self.DATABASE_POOL_CONNECTION_TIMEOUT_MS,
+            )
+            .field(
+                "DATABASE_POOL_IDLE_TIMEOUT_SECONDS",
+                &self.DATABASE_POOL_IDLE_TIMEOUT_SECONDS,
+            )
+            .field(
+                "DATABASE_POOL_ACQUIRE_MAX_ATTEMPTS",
+                &self.DATABASE_POOL_ACQUIRE_MAX_ATTEMPTS,
+            )
+            .field("SLOW_QUERY_THRESHOLD_MS", &self.SLOW_QUERY_THRESHOLD_MS)
+            .field("RUST_LOG", &self.RUST_LOG)
+            .field("SERVER_PORT", &self.SERVER_PORT)
+            .field("SERVER_PORT_FALLBACK", &self.SERVER_PORT_FALLBACK)
+            .field("SHUTDOWN_DRAIN_SECONDS", &self.SHUTDOWN_DRAIN_SECONDS)
+            .field("ASSETS_FOLDER", &self.ASSETS_FOLDER)
+            .field("ALLOWED_ORIGINS", &self.ALLOWED_ORIGINS)
+            .field("PASSWORD_KEY", &redacted(&self.PASSWORD_KEY))
+            .field("TOKEN_KEY", &redacted(&self.TOKEN_KEY))
+            .field("TOKEN_DURATION_IN_SECONDS", &self.TOKEN_DURATION_IN_SECONDS)
+            .field(
+                "TOKEN_REFRESH_GRACE_SECONDS",
+                &self.TOKEN_REFRESH_GRACE_SECONDS,
+            )
+            .field(
+                "RATE_LIMIT_READS_PER_MINUTE",
+                &self.RATE_LIMIT_READS_PER_MINUTE,
+            )
+            .field(
+                "RATE_LIMIT_WRITES_PER_MINUTE",
+                &self.RATE_LIMIT_WRITES_PER_MINUTE,
+            )
+            .field("KEPLER_TOLERANCE", &self.KEPLER_TOLERANCE)
+            .field("CACHE_TTL_SECONDS", &self.CACHE_TTL_SECONDS)
+            .field("MAX_REQUEST_BODY_BYTES", &self.MAX_REQUEST_BODY_BYTES)
+            .field("MAX_IMPORT_BODY_BYTES", &self.MAX_IMPORT_BODY_BYTES)
+            .field("WS_PING_SECONDS", &self.WS_PING_SECONDS)
+            .field(
+                "MAINTENANCE_RETRY_AFTER_SECONDS",
+                &self.MAINTENANCE_RETRY_AFTER_SECONDS,
+            )
+            .finish()
+    }
 }
 
 #[cfg(test)]
@@ -77,14 +315,150 @@ mod tests {
         // Create a HashMap that looks like the required environment.
         let mock_env = create_config_map(required_configs);
         // Initialise the config from HashMap, avoiding race conditions.
-        let config = Config::init_from_hashmap(&mock_env).unwrap();
+        let config = Config::init_from_hashmap(&mock_env)
+            .and_then(Config::validated)
+            .unwrap();
 
         assert_eq!(config.DATABASE_URL, ":memory:");
-        assert_eq!(config.RUST_LOG, "debug");
+        assert_eq!(config.RUST_LOG, LogLevel::Debug);
         assert_eq!(config.SERVER_PORT, 12345u16);
         assert_eq!(config.ASSETS_FOLDER, "assets");
         assert_eq!(config.PASSWORD_KEY, "password");
         assert_eq!(config.TOKEN_KEY, "token");
         assert_eq!(config.TOKEN_DURATION_IN_SECONDS, 3600f64);
+        // Defaults
+        assert_eq!(config.DATABASE_POOL_MAX_CONNECTIONS, 5u32);
+        assert_eq!(config.DATABASE_POOL_MIN_CONNECTIONS, 0u32);
+        assert_eq!(config.DATABASE_POOL_CONNECTION_TIMEOUT_MS, 500u64);
+        assert_eq!(config.DATABASE_POOL_IDLE_TIMEOUT_SECONDS, 600u64);
+        assert_eq!(config.SLOW_QUERY_THRESHOLD_MS, 100u64);
+        assert_eq!(config.KEPLER_TOLERANCE, 0.05f64);
+        assert!(!config.SERVER_PORT_FALLBACK);
+        assert_eq!(config.SHUTDOWN_DRAIN_SECONDS, 0u64);
+        assert_eq!(config.CACHE_TTL_SECONDS, 60u64);
+        assert_eq!(config.MAX_REQUEST_BODY_BYTES, 1_048_576usize);
+        assert_eq!(config.MAX_IMPORT_BODY_BYTES, 10_485_760usize);
+        assert_eq!(config.WS_PING_SECONDS, 30u64);
+        assert_eq!(config.MAINTENANCE_RETRY_AFTER_SECONDS, 60u64);
+        assert_eq!(config.ALLOWED_ORIGINS, "");
+        assert_eq!(config.TOKEN_REFRESH_GRACE_SECONDS, 300f64);
+        assert_eq!(config.RATE_LIMIT_READS_PER_MINUTE, 600u32);
+        assert_eq!(config.RATE_LIMIT_WRITES_PER_MINUTE, 60u32);
+    }
+
+    #[test]
+    fn test_invalid_log_level_fails_to_load() {
+        let mock_env = create_config_map(vec![
+            ("DATABASE_URL", ":memory:"),
+            ("RUST_LOG", "debg"),
+            ("SERVER_PORT", "12345"),
+            ("ASSETS_FOLDER", "assets"),
+            ("PASSWORD_KEY", "password"),
+            ("TOKEN_KEY", "token"),
+            ("TOKEN_DURATION_IN_SECONDS", "3600"),
+        ]);
+
+        assert!(Config::init_from_hashmap(&mock_env).is_err());
+    }
+
+    #[test]
+    fn test_out_of_range_pool_settings_fail_to_load() {
+        let required_configs = vec![
+            ("DATABASE_URL", ":memory:"),
+            ("RUST_LOG", "debug"),
+            ("SERVER_PORT", "12345"),
+            ("ASSETS_FOLDER", "assets"),
+            ("PASSWORD_KEY", "password"),
+            ("TOKEN_KEY", "token"),
+            ("TOKEN_DURATION_IN_SECONDS", "3600"),
+        ];
+        let load_with = |key: &str, value: &str| {
+            let mut mock_env = create_config_map(required_configs.clone());
+            mock_env.insert(key.to_string(), value.to_string());
+            Config::init_from_hashmap(&mock_env).and_then(Config::validated)
+        };
+
+        let config = load_with("DATABASE_POOL_MAX_CONNECTIONS", "100").unwrap();
+        assert_eq!(config.DATABASE_POOL_MAX_CONNECTIONS, 100u32);
+        assert!(load_with("DATABASE_POOL_MAX_CONNECTIONS", "0").is_err());
+        assert!(load_with("DATABASE_POOL_MAX_CONNECTIONS", "101").is_err());
+        assert!(load_with("DATABASE_POOL_MIN_CONNECTIONS", "6").is_err());
+        assert!(load_with("DATABASE_POOL_CONNECTION_TIMEOUT_MS", "0").is_err());
+        assert!(load_with("DATABASE_POOL_CONNECTION_TIMEOUT_MS", "60001").is_err());
+        assert!(load_with("DATABASE_POOL_IDLE_TIMEOUT_SECONDS", "0").is_err());
+        assert!(load_with("DATABASE_POOL_ACQUIRE_MAX_ATTEMPTS", "0").is_err());
+        assert!(load_with("DATABASE_POOL_ACQUIRE_MAX_ATTEMPTS", "11").is_err());
+    }
+
+    #[test]
+    fn test_log_effective_redacts_the_secrets() {
+        use crate::_dev_utils::CapturedLogs;
+
+        let mock_env = create_config_map(vec![
+            ("DATABASE_URL", "sqlite://test-log-effective.db"),
+            ("RUST_LOG", "info"),
+            ("SERVER_PORT", "12345"),
+            ("ASSETS_FOLDER", "assets"),
+            ("PASSWORD_KEY", "password-key-never-logged"),
+            ("TOKEN_KEY", "token-key-never-logged"),
+            ("TOKEN_DURATION_IN_SECONDS", "3600"),
+        ]);
+        let config = Config::init_from_hashmap(&mock_env).unwrap();
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::TRACE)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+
+        tracing::subscriber::with_default(subscriber, || config.log_effective());
+
+        let output = logs.contents();
+        assert!(output.contains("effective config"), "{output}");
+        assert!(
+            output.contains("sqlite://test-log-effective.db"),
+            "{output}"
+        );
+        assert!(output.contains("<redacted, 25 chars>"), "{output}");
+        assert!(!output.contains("password-key-never-logged"), "{output}");
+        assert!(!output.contains("token-key-never-logged"), "{output}");
+
+        // Nor can they leak through `Debug`.
+        let debug = format!("{config:?}");
+        assert!(debug.contains("sqlite://test-log-effective.db"));
+        assert!(!debug.contains("never-logged"), "{debug}");
+    }
+
+    #[test]
+    fn test_log_level_parsing() {
+        assert_eq!("TRACE".parse::<LogLevel>(), Ok(LogLevel::Trace));
+        assert_eq!("warn".parse::<LogLevel>(), Ok(LogLevel::Warn));
+        assert_eq!(LogLevel::Info.to_env_filter_directive(), "info");
+        assert!("verbose".parse::<LogLevel>().is_err());
+    }
+
+    #[serial_test::serial]
+    #[test]
+    fn test_dotenv_does_not_override_existing_variables() {
+        // NOTE: `ASSETS_FOLDER` is always set for cargo commands (see `.cargo/config.toml`),
+        //       while the pool size is normally left to its default.
+        let existing = std::env::var("ASSETS_FOLDER").unwrap();
+        std::env::remove_var("DATABASE_POOL_MAX_CONNECTIONS");
+
+        let path = std::env::temp_dir().join(format!("orrery-test-{}.env", std::process::id()));
+        std::fs::write(
+            &path,
+            "ASSETS_FOLDER=from_dotenv\nDATABASE_POOL_MAX_CONNECTIONS=7\n",
+        )
+        .unwrap();
+        dotenvy::from_path(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let config = Config::init_from_env().unwrap();
+        assert_eq!(config.ASSETS_FOLDER, existing);
+        assert_eq!(config.DATABASE_POOL_MAX_CONNECTIONS, 7u32);
+
+        std::env::remove_var("DATABASE_POOL_MAX_CONNECTIONS");
     }
 }
@@ -0,0 +1,88 @@
+//! A small in-memory cache for rows that are read often but rarely change.
+//!
+//! Entries expire after a fixed TTL, and the owning client invalidates them on
+//! every write, so a stale read is only possible if the row is changed behind
+//! the data access layer's back (*eg* by another process).
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+/// Rows of type `V`, keyed on their id. Shared between the clones of a
+/// `DataAccessManager` by wrapping it in an `Arc`.
+pub struct TtlCache<V> {
+    ttl: Duration,
+    entries: RwLock<HashMap<i64, (Instant, V)>>,
+}
+
+impl<V: Clone> TtlCache<V> {
+    /// A TTL of zero disables the cache: every entry has expired by the time it is read.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, id: i64) -> Option<V> {
+        // NOTE: a poisoned lock only means a panic elsewhere mid-access; the map
+        //       itself is still consistent, so it is used regardless.
+        let entries = self.entries.read().unwrap_or_else(|err| err.into_inner());
+
+        entries
+            .get(&id)
+            .filter(|(inserted, _)| inserted.elapsed() < self.ttl)
+            .map(|(_, value)| value.clone())
+    }
+
+    pub fn insert(&self, id: i64, value: V) {
+        let mut entries = self.entries.write().unwrap_or_else(|err| err.into_inner());
+        // Expired entries are only ever dropped here, so the map stays bounded by
+        // the number of rows read within one TTL.
+        entries.retain(|_, (inserted, _)| inserted.elapsed() < self.ttl);
+        entries.insert(id, (Instant::now(), value));
+    }
+
+    pub fn invalidate(&self, id: i64) {
+        let mut entries = self.entries.write().unwrap_or_else(|err| err.into_inner());
+        entries.remove(&id);
+    }
+
+    pub fn clear(&self) {
+        let mut entries = self.entries.write().unwrap_or_else(|err| err.into_inner());
+        entries.clear();
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_insert_invalidate() {
+        let cache = TtlCache::new(Duration::from_secs(60));
+        assert_eq!(cache.get(1), None);
+
+        cache.insert(1, "Inner Solar System");
+        cache.insert(2, "Outer Solar System");
+        assert_eq!(cache.get(1), Some("Inner Solar System"));
+
+        cache.invalidate(1);
+        assert_eq!(cache.get(1), None);
+        assert_eq!(cache.get(2), Some("Outer Solar System"));
+
+        cache.clear();
+        assert_eq!(cache.get(2), None);
+    }
+
+    #[test]
+    fn test_zero_ttl_disables_the_cache() {
+        let cache = TtlCache::new(Duration::ZERO);
+        cache.insert(1, "Inner Solar System");
+
+        assert_eq!(cache.get(1), None);
+    }
+}
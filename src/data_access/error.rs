@@ -12,9 +12,50 @@ pub type Result<T> = core::result::Result<T, Error>;
 #[serde_as]
 #[derive(Debug, Serialize)]
 pub enum Error {
-    EntityNotFound { entity: &'static str, id: i64 },
+    EntityNotFound {
+        entity: &'static str,
+        id: i64,
+    },
+    /// The entity was modified since the caller last read it.
+    StaleWrite {
+        entity: &'static str,
+        id: i64,
+    },
+    /// A batch operation failed on the item at `index`; the whole batch was rolled back.
+    BatchItemFailed {
+        index: usize,
+        cause: Box<Error>,
+    },
+    /// More than `limit` distinct ids were asked for at once.
+    TooManyIds {
+        limit: usize,
+    },
+    // Validation errors
+    KeplerianInconsistency {
+        expected_days: f64,
+        supplied_days: f64,
+    },
+    UnboundOrbit {
+        eccentricity: f64,
+    },
+    /// Setting `parent_id` as the parent of `id` would make `id` its own ancestor.
+    ParentCycle {
+        id: i64,
+        parent_id: i64,
+    },
     // Db-related errors
     FailedToCreatePool(String),
+    UniqueViolation(String),
+    /// `DbCrudAction::create_with_id` was given an id that is already taken.
+    DuplicateId {
+        entity: &'static str,
+        id: i64,
+    },
+    /// A write would leave a reference dangling, or removes a row still referenced.
+    ForeignKeyViolation(String),
+    UnsupportedOperator(String),
+    /// A `*_where` condition named a column the entity does not have.
+    UnknownColumn(String),
     Sqlx(#[serde_as(as = "DisplayFromStr")] sqlx::Error),
     // Wrapped errors
     Security(security::Error),
@@ -28,7 +69,17 @@ impl From<security::Error> for Error {
 
 impl From<sqlx::Error> for Error {
     fn from(err: sqlx::Error) -> Self {
-        Self::Sqlx(err)
+        // NOTE: constraint violations are caused by the request, not the server, so they
+        //       are split out here to allow the HTTP layer to report them differently.
+        match err {
+            sqlx::Error::Database(ref db_err) if db_err.is_unique_violation() => {
+                Self::UniqueViolation(db_err.message().to_string())
+            }
+            sqlx::Error::Database(ref db_err) if db_err.is_foreign_key_violation() => {
+                Self::ForeignKeyViolation(db_err.message().to_string())
+            }
+            _ => Self::Sqlx(err),
+        }
     }
 }
 
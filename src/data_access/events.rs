@@ -0,0 +1,37 @@
+//! In-process notifications of writes, for side effects (cache invalidation,
+//! pushes to clients) that should not be wired into every handler.
+//!
+//! `DbCrudAction` publishes an `EntityEvent` once each create, update or delete
+//! has been committed; anything interested subscribes with
+//! `DataAccessManager::subscribe`. Publishing never fails a write: with nobody
+//! subscribed the event is simply dropped, and a subscriber that falls more than
+//! `EVENT_CHANNEL_CAPACITY` events behind is told how many it missed (see
+//! `broadcast::error::RecvError::Lagged`).
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+/// How many events are kept for the slowest subscriber.
+pub const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// The kind of write an `EntityEvent` reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Op {
+    Create,
+    Update,
+    Delete,
+}
+
+/// A committed write to the row `id` of `table`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct EntityEvent {
+    pub table: &'static str,
+    pub op: Op,
+    pub id: i64,
+}
+
+pub(in crate::data_access) fn channel() -> broadcast::Sender<EntityEvent> {
+    let (sender, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+
+    sender
+}
@@ -8,20 +8,51 @@
 //! 4. The `DataModelControllers` implement CRUD and other data access methods on "entities".
 //! 5. In frameworks like Axum, the manager is used as the app state.
 //! 6. The manager is designed to be passed as an argument to all controller functions.
+mod cache;
 mod error;
-mod model;
+mod events;
+pub mod model;
 mod store;
 
+use self::cache::TtlCache;
 pub use self::error::{Error, Result};
-use store::db::{create_database_pool, DbCrudAction, DbCrudServer, DbPool};
+pub use self::events::{EntityEvent, Op};
+use crate::config::get_config;
+use model::celestial_region::Region;
+use model::celestial_subregion::Subregion;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+pub(crate) use store::db::configured_pool_options;
+use store::db::{create_database_pool, with_retry, DbPool, DbTransaction};
+pub use store::db::{DbCrudAction, DbCrudServer, SortSpec, ID_BATCH_LIMIT};
+use tokio::sync::broadcast;
 
 // -----------------------------------------------------------------------------
 //
 // -----------------------------------------------------------------------------
 
+/// A snapshot of the connection pool, for diagnosing acquire timeouts: if `idle`
+/// is at zero and `size` at `max_connections`, every connection is in use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct PoolStats {
+    /// Connections currently open, whether in use or idle.
+    pub size: u32,
+    /// Open connections not currently in use.
+    pub idle: usize,
+    pub max_connections: u32,
+}
+
+/// Regions and subregions are read on nearly every body request, but rarely
+/// change, so reads of them are cached (see `cache::TtlCache`). The caches are
+/// behind `Arc`s so that every clone of the manager shares them, as is the
+/// sender for write events (see `events`).
 #[derive(Clone)]
 pub struct DataAccessManager {
     db_pool: DbPool,
+    region_cache: Arc<TtlCache<Region>>,
+    subregion_cache: Arc<TtlCache<Subregion>>,
+    events: broadcast::Sender<EntityEvent>,
 }
 
 impl DataAccessManager {
@@ -29,11 +60,79 @@ impl DataAccessManager {
     pub async fn new() -> Result<Self> {
         let db_pool = create_database_pool().await?;
 
-        Ok(DataAccessManager { db_pool })
+        Self::new_from_existing_resources(db_pool).await
     }
 
     pub async fn new_from_existing_resources(db_pool: DbPool) -> Result<Self> {
-        Ok(DataAccessManager { db_pool })
+        let ttl = Duration::from_secs(get_config().CACHE_TTL_SECONDS);
+
+        Ok(DataAccessManager {
+            db_pool,
+            region_cache: Arc::new(TtlCache::new(ttl)),
+            subregion_cache: Arc::new(TtlCache::new(ttl)),
+            events: events::channel(),
+        })
+    }
+
+    /// Begin a transaction, for grouping several writes atomically via the
+    /// `DbCrudAction::*_in_transaction` methods. The transaction is rolled back
+    /// if it is dropped without calling `commit`.
+    pub async fn begin(&self) -> Result<DbTransaction> {
+        with_retry(|| self.db_pool.begin()).await
+    }
+
+    /// Run a trivial query against the database, to check it is reachable.
+    pub async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.db_pool).await?;
+
+        Ok(())
+    }
+
+    /// Receive an `EntityEvent` for every write committed from now on, by any
+    /// clone of this manager.
+    pub fn subscribe(&self) -> broadcast::Receiver<EntityEvent> {
+        self.events.subscribe()
+    }
+
+    pub fn pool_stats(&self) -> PoolStats {
+        PoolStats {
+            size: self.db_pool.size(),
+            idle: self.db_pool.num_idle(),
+            max_connections: self.db_pool.options().get_max_connections(),
+        }
+    }
+
+    /// Delete every row from `tables`, in the order given, and reset any
+    /// `AUTOINCREMENT` counters for them. For test isolation only.
+    #[cfg(test)]
+    pub(crate) async fn truncate(&self, tables: &[&str]) -> Result<()> {
+        let mut tx = self.db_pool.begin().await?;
+        for table in tables {
+            sqlx::query(&format!("DELETE FROM \"{table}\""))
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        // NOTE: `sqlite_sequence` only exists once an `AUTOINCREMENT` table has been created.
+        let has_sequence: bool = sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE name = 'sqlite_sequence')",
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        if has_sequence {
+            for table in tables {
+                sqlx::query("DELETE FROM sqlite_sequence WHERE name = ?1")
+                    .bind(table)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+        tx.commit().await?;
+        // NOTE: row ids restart once the sequences are reset, so nothing cached is valid.
+        self.region_cache.clear();
+        self.subregion_cache.clear();
+
+        Ok(())
     }
 
     // NOTE: the `pub(in crate::data_model)` syntax is used to make the method
@@ -41,4 +140,40 @@ impl DataAccessManager {
     pub(in crate::data_access) fn db_pool(&self) -> &DbPool {
         &self.db_pool
     }
+
+    pub(in crate::data_access) fn region_cache(&self) -> &TtlCache<Region> {
+        &self.region_cache
+    }
+
+    pub(in crate::data_access) fn subregion_cache(&self) -> &TtlCache<Subregion> {
+        &self.subregion_cache
+    }
+
+    pub(in crate::data_access) fn publish(&self, table: &'static str, op: Op, id: i64) {
+        // NOTE: this only fails if nobody is subscribed, which is not the writer's concern.
+        let _ = self.events.send(EntityEvent { table, op, id });
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_pool_stats() -> anyhow::Result<()> {
+        let max_connections = get_config().DATABASE_POOL_MAX_CONNECTIONS;
+        let db_pool = configured_pool_options().connect("sqlite::memory:").await?;
+        let dam = DataAccessManager::new_from_existing_resources(db_pool).await?;
+
+        let stats = dam.pool_stats();
+        assert_eq!(stats.max_connections, max_connections);
+        assert!(stats.size <= max_connections);
+        assert!(stats.idle <= stats.size as usize);
+
+        Ok(())
+    }
 }
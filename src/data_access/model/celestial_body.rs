@@ -1,14 +1,65 @@
-use crate::data_access::store::db::{DbCrudAction, DbCrudServer};
-use crate::data_access::{DataAccessManager, Result};
+use crate::data_access::model::{celestial_region, celestial_subregion};
+use crate::data_access::store::db::{
+    acquire, quote_identifier, select_columns, slow_query_threshold, timed, where_clause,
+    DbCrudAction, DbCrudServer,
+};
+use crate::data_access::{DataAccessManager, Error, Op, Result, SortSpec};
+use crate::generic_utils::levenshtein_within;
 use crate::RequestContext;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlb::Fields;
+use serde_json::Value;
+use sqlb::{Fields, HasFields};
 use sqlx::FromRow;
+use std::collections::{HashMap, HashSet};
+use std::f64::consts::TAU;
+use tokio::sync::mpsc;
 
 // -----------------------------------------------------------------------------
 // Types
 // -----------------------------------------------------------------------------
 
+/// The Sun's standard gravitational parameter (GM), in km^3/s^2.
+pub const SUN_GRAVITATIONAL_PARAMETER: f64 = 1.327_124_400_18e11;
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// The Newtonian constant of gravitation, in m^3/(kg·s^2).
+pub const GRAVITATIONAL_CONSTANT: f64 = 6.674_30e-11;
+
+/// The Sun's mass, in kg.
+pub const SUN_MASS: f64 = 1.988_47e30;
+
+/// Kilometres in one astronomical unit (the IAU 2012 definition).
+pub const KM_PER_AU: f64 = 149_597_870.7;
+
+/// The nominal solar radius, in km (the IAU 2015 definition).
+pub const KM_PER_SOLAR_RADIUS: f64 = 695_700.0;
+
+/// The Earth's mean radius, in km.
+pub const KM_PER_EARTH_RADIUS: f64 = 6_371.0;
+
+/// The most typos `suggest_by_name` allows for, however long the query.
+const SUGGEST_MAX_DISTANCE: usize = 3;
+
+/// The most candidate rows `suggest_by_name` fetches to rank.
+const SUGGEST_CANDIDATE_LIMIT: i64 = 500;
+
+/// J2000.0 (2000-01-01T12:00:00Z) as a Unix timestamp. No perihelion dates are
+/// recorded, so every body is assumed to have passed perihelion at this epoch
+/// when estimating positions (see `CelestialBody::position_at`).
+pub const REFERENCE_EPOCH_TIMESTAMP: i64 = 946_728_000;
+
+/// `REFERENCE_EPOCH_TIMESTAMP`, as a date.
+pub fn reference_epoch() -> DateTime<Utc> {
+    DateTime::<Utc>::from_timestamp(REFERENCE_EPOCH_TIMESTAMP, 0)
+        .expect("the reference epoch is a valid timestamp")
+}
+
+/// Convergence tolerance (in radians) when solving Kepler's equation.
+const KEPLER_EQUATION_TOLERANCE: f64 = 1e-8;
+const KEPLER_EQUATION_MAX_ITERATIONS: usize = 100;
+
 // SQLite does not support enums. This is not a huge issue: a table can be created
 // to store the enum values, and then a foreign key can be used to reference them.
 // However, to save on boilerplate, this will be enforced outside of the database.
@@ -35,21 +86,482 @@ pub enum CelestialSubregion {
 /// This is the "entity" that is used by the application. It maps to database table/s,
 /// so sqlx's `FromRow` is implemented.
 #[derive(Clone, Debug, Fields, FromRow, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct CelestialBody {
     pub id: i64,
     pub name: String,
+    pub region: Option<i64>,
+    pub subregion: Option<i64>,
+    /// The body this one orbits, *eg* a moon's planet. `None` means the Sun.
+    pub parent_id: Option<i64>,
+    /// Farthest distance from the primary (the Sun, or the parent body), in km.
+    pub aphelion: f64,
+    /// Closest distance to the primary (the Sun, or the parent body), in km.
+    pub perihelion: f64,
+    /// Sidereal orbital period, in days.
+    pub orbital_period: f64,
+    /// Mean radius, in km.
+    pub radius: f64,
+    /// Mass, in kg.
+    pub mass: f64,
+    pub created_at: i64,
+    pub updated_at: Option<i64>,
+}
+
+impl CelestialBody {
+    // -------------------------------------------------------------------------
+    // Orbital mechanics
+    // -------------------------------------------------------------------------
+
+    /// Orbital eccentricity, `(aphelion - perihelion) / (aphelion + perihelion)`.
+    /// This is in `[0, 1)` for a bound orbit.
+    ///
+    /// NOTE: a body with no orbit (both values `0.0`, *eg* the Sun) returns `0.0`
+    ///       rather than `NaN`. If the perihelion is greater than the aphelion the
+    ///       values are treated as swapped, so the result is never negative.
+    pub fn eccentricity(&self) -> f64 {
+        let sum = self.aphelion + self.perihelion;
+
+        if sum == 0.0 {
+            0.0
+        } else {
+            (self.aphelion - self.perihelion).abs() / sum
+        }
+    }
+
+    /// Semi-major axis of the orbit, in km: the mean of the aphelion and perihelion.
+    ///
+    /// ```
+    /// # use orrery::data_access::model::celestial_body::CelestialBody;
+    /// let mars = CelestialBody {
+    ///     aphelion: 249_261_000.0,
+    ///     perihelion: 206_650_000.0,
+    /// #   id: 4, name: "Mars".to_string(), region: None, subregion: None,
+    /// #   parent_id: None, orbital_period: 686.98, radius: 3_389.5, mass: 6.417e23,
+    /// #   created_at: 0, updated_at: None,
+    ///     // ...
+    /// };
+    /// assert_eq!(mars.semi_major_axis(), 227_955_500.0);
+    /// ```
+    pub fn semi_major_axis(&self) -> f64 {
+        (self.aphelion + self.perihelion) / 2.0
+    }
+
+    /// Semi-minor axis of the orbit, in km, derived as `a * sqrt(1 - e^2)`.
+    ///
+    /// ```
+    /// # use orrery::data_access::model::celestial_body::CelestialBody;
+    /// let circular = CelestialBody {
+    ///     aphelion: 1_000.0,
+    ///     perihelion: 1_000.0,
+    /// #   id: 1, name: "Circular".to_string(), region: None, subregion: None,
+    /// #   parent_id: None, orbital_period: 0.0, radius: 0.0, mass: 0.0,
+    /// #   created_at: 0, updated_at: None,
+    ///     // ...
+    /// };
+    /// assert_eq!(circular.semi_minor_axis(), circular.semi_major_axis());
+    /// ```
+    pub fn semi_minor_axis(&self) -> f64 {
+        let e = self.eccentricity();
+
+        self.semi_major_axis() * (1.0 - e * e).sqrt()
+    }
+
+    // -------------------------------------------------------------------------
+    // Units
+    // -------------------------------------------------------------------------
+
+    // NOTE: distances are stored in km; these are conversions for display only.
+
+    /// Aphelion, in AU.
+    pub fn aphelion_au(&self) -> f64 {
+        self.aphelion / KM_PER_AU
+    }
+
+    /// Perihelion, in AU.
+    pub fn perihelion_au(&self) -> f64 {
+        self.perihelion / KM_PER_AU
+    }
+
+    /// Semi-major axis, in AU.
+    pub fn semi_major_axis_au(&self) -> f64 {
+        self.semi_major_axis() / KM_PER_AU
+    }
+
+    /// Mean radius, in solar radii.
+    pub fn radius_in_solar_radii(&self) -> f64 {
+        self.radius / KM_PER_SOLAR_RADIUS
+    }
+
+    // -------------------------------------------------------------------------
+    // Orbital position
+    // -------------------------------------------------------------------------
+
+    /// Mean anomaly at `epoch`, in radians in `[0, 2π)`, given that the body was at
+    /// perihelion at `reference`. Epochs before the reference wrap around as well.
+    ///
+    /// NOTE: a body with no orbital period does not move, so this returns `0.0`.
+    pub fn mean_anomaly_at(&self, epoch: DateTime<Utc>, reference: DateTime<Utc>) -> f64 {
+        if self.orbital_period <= 0.0 {
+            return 0.0;
+        }
+
+        let elapsed_days =
+            (epoch - reference).num_milliseconds() as f64 / 1_000.0 / SECONDS_PER_DAY;
+
+        (TAU * elapsed_days / self.orbital_period).rem_euclid(TAU)
+    }
+
+    /// True anomaly at `epoch` (the angle from perihelion to the body, as seen from
+    /// the Sun), in radians in `[0, 2π)`. Kepler's equation `M = E - e·sin(E)` is
+    /// solved for the eccentric anomaly `E` by Newton iteration.
+    ///
+    /// Only bound (elliptical) orbits are supported: `e >= 1` is an error.
+    pub fn true_anomaly_at(&self, epoch: DateTime<Utc>, reference: DateTime<Utc>) -> Result<f64> {
+        let e = self.eccentricity();
+        if e >= 1.0 {
+            return Err(Error::UnboundOrbit { eccentricity: e });
+        }
+
+        let mean_anomaly = self.mean_anomaly_at(epoch, reference);
+        // NOTE: starting from π converges reliably for highly eccentric orbits.
+        let mut eccentric_anomaly = if e < 0.8 {
+            mean_anomaly
+        } else {
+            std::f64::consts::PI
+        };
+        for _ in 0..KEPLER_EQUATION_MAX_ITERATIONS {
+            let delta = (eccentric_anomaly - e * eccentric_anomaly.sin() - mean_anomaly)
+                / (1.0 - e * eccentric_anomaly.cos());
+            eccentric_anomaly -= delta;
+            if delta.abs() < KEPLER_EQUATION_TOLERANCE {
+                break;
+            }
+        }
+
+        let true_anomaly = 2.0
+            * f64::atan2(
+                (1.0 + e).sqrt() * (eccentric_anomaly / 2.0).sin(),
+                (1.0 - e).sqrt() * (eccentric_anomaly / 2.0).cos(),
+            );
+
+        Ok(true_anomaly.rem_euclid(TAU))
+    }
+
+    /// Distance from the Sun at `epoch`, in km (see `true_anomaly_at`).
+    pub fn distance_from_sun_at(
+        &self,
+        epoch: DateTime<Utc>,
+        reference: DateTime<Utc>,
+    ) -> Result<f64> {
+        let true_anomaly = self.true_anomaly_at(epoch, reference)?;
+
+        Ok(self.distance_at_true_anomaly(true_anomaly))
+    }
+
+    /// Position relative to the primary at `epoch`, as `(x, y)` in km: polar
+    /// coordinates `(r, ν)` from `distance_from_sun_at` and `true_anomaly_at`.
+    ///
+    /// NOTE: this is a first approximation. Every orbit is taken to lie in the
+    ///       plane of the ecliptic (no inclination), with its perihelion along the
+    ///       x-axis (no longitude of perihelion), and every body to have passed
+    ///       perihelion at `reference`. Distances between bodies can be out by
+    ///       up to the sum of their orbital radii.
+    pub fn position_at(
+        &self,
+        epoch: DateTime<Utc>,
+        reference: DateTime<Utc>,
+    ) -> Result<(f64, f64)> {
+        let true_anomaly = self.true_anomaly_at(epoch, reference)?;
+        let distance = self.distance_at_true_anomaly(true_anomaly);
+
+        Ok((distance * true_anomaly.cos(), distance * true_anomaly.sin()))
+    }
+
+    /// The orbit equation, `r = a(1 - e²) / (1 + e·cos(ν))`. This is the perihelion
+    /// at `ν = 0` and the aphelion at `ν = π`; for `e = 0` it is simply `a`.
+    fn distance_at_true_anomaly(&self, true_anomaly: f64) -> f64 {
+        let e = self.eccentricity();
+
+        self.semi_major_axis() * (1.0 - e * e) / (1.0 + e * true_anomaly.cos())
+    }
+
+    // -------------------------------------------------------------------------
+    // Orbital velocity
+    // -------------------------------------------------------------------------
+
+    /// Orbital speed at `distance_km` from the Sun, in km/s, from the vis-viva
+    /// equation `v = sqrt(μ(2/r - 1/a))`.
+    ///
+    /// NOTE: returns `0.0` if the distance or semi-major axis is not positive, or
+    ///       if the distance is beyond the reach of the orbit (`r > 2a`).
+    pub fn orbital_velocity_at(&self, distance_km: f64) -> f64 {
+        self.vis_viva(distance_km, SUN_GRAVITATIONAL_PARAMETER)
+    }
+
+    /// As `orbital_velocity_at`, for a body orbiting a primary of `primary_mass_kg`
+    /// (*eg* a moon around its parent planet) rather than the Sun.
+    pub fn orbital_velocity_around(&self, distance_km: f64, primary_mass_kg: f64) -> f64 {
+        self.vis_viva(distance_km, gravitational_parameter(primary_mass_kg))
+    }
+
+    fn vis_viva(&self, distance_km: f64, mu: f64) -> f64 {
+        let a = self.semi_major_axis();
+        if distance_km <= 0.0 || a <= 0.0 {
+            return 0.0;
+        }
+
+        let v_squared = mu * (2.0 / distance_km - 1.0 / a);
+
+        v_squared.max(0.0).sqrt()
+    }
+
+    /// The fastest orbital speed, in km/s.
+    pub fn velocity_at_perihelion(&self) -> f64 {
+        self.orbital_velocity_at(self.perihelion.min(self.aphelion))
+    }
+
+    /// The slowest orbital speed, in km/s.
+    pub fn velocity_at_aphelion(&self) -> f64 {
+        self.orbital_velocity_at(self.aphelion.max(self.perihelion))
+    }
+    // -------------------------------------------------------------------------
+    // Surface properties
+    // -------------------------------------------------------------------------
+
+    /// Gravitational acceleration at the surface, in m/s^2: `g = GM / r^2`.
+    ///
+    /// NOTE: a body with no recorded radius returns `0.0`.
+    pub fn surface_gravity(&self) -> f64 {
+        let radius_m = self.radius * 1_000.0;
+        if radius_m <= 0.0 {
+            return 0.0;
+        }
+
+        GRAVITATIONAL_CONSTANT * self.mass / (radius_m * radius_m)
+    }
+
+    /// Escape velocity from the surface, in km/s: `v = sqrt(2GM / r)`.
+    ///
+    /// NOTE: a body with no recorded radius returns `0.0`.
+    pub fn escape_velocity(&self) -> f64 {
+        let radius_m = self.radius * 1_000.0;
+        if radius_m <= 0.0 {
+            return 0.0;
+        }
+
+        (2.0 * GRAVITATIONAL_CONSTANT * self.mass / radius_m).sqrt() / 1_000.0
+    }
+
+    /// Radius of the Hill sphere, in km: the region in which this body's gravity
+    /// dominates that of `primary_mass_kg` (the Sun, or the parent planet for a
+    /// moon), so where its own satellites can orbit stably.
+    ///
+    /// Uses the two-body approximation `r_H ≈ a(1 - e)·cbrt(m / 3M)`, which assumes
+    /// this body is much lighter than its primary and ignores any other bodies.
+    /// Returns `0.0` for a non-positive primary mass.
+    pub fn hill_sphere_radius(&self, primary_mass_kg: f64) -> f64 {
+        if primary_mass_kg <= 0.0 {
+            return 0.0;
+        }
+
+        let a = self.semi_major_axis();
+        let e = self.eccentricity();
+
+        a * (1.0 - e) * (self.mass / (3.0 * primary_mass_kg)).cbrt()
+    }
+}
+
+/// A body with the names of its region and subregion resolved, to save clients
+/// a lookup per body. A name is `None` if the id is, or if the region (or
+/// subregion) has since been deleted.
+#[derive(Clone, Debug, FromRow, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CelestialBodyExpanded {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub body: CelestialBody,
+    pub region_name: Option<String>,
+    pub subregion_name: Option<String>,
 }
 
 /// Sent to the data access layer, hence `Deserialize`.
+/// NOTE: `None` fields are skipped on insert, so the region/subregion columns
+///       are left `NULL` rather than being written as `0` or an empty string.
 #[derive(Fields, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct CelestialBodyCreate {
     pub name: String,
+    pub region: Option<i64>,
+    pub subregion: Option<i64>,
+    #[serde(default)]
+    pub parent_id: Option<i64>,
+    pub aphelion: f64,
+    pub perihelion: f64,
+    pub orbital_period: f64,
+    /// Optional in requests, as it plays no part in the orbital validation.
+    #[serde(default)]
+    pub radius: f64,
+    #[serde(default)]
+    pub mass: f64,
 }
 
 /// Sent to the data access layer, hence `Deserialize`.
-#[derive(Fields, Deserialize)]
+#[derive(Default, Fields, Deserialize)]
 pub struct CelestialBodyUpdate {
     pub name: Option<String>,
+    pub parent_id: Option<i64>,
+}
+
+/// An ordering of the body listing by a (possibly computed) quantity, done in SQL.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BodyOrdering {
+    /// By orbital period.
+    Period,
+    /// By the sum of the aphelion and perihelion (twice the semi-major axis), so
+    /// by mean distance from the primary.
+    Distance,
+}
+
+impl BodyOrdering {
+    fn expression(self) -> String {
+        match self {
+            Self::Period => quote_identifier("orbital_period"),
+            Self::Distance => format!(
+                "({} + {})",
+                quote_identifier("aphelion"),
+                quote_identifier("perihelion")
+            ),
+        }
+    }
+}
+
+/// A derived field rewritten by `Client::recompute_periods`; never sent by clients.
+#[derive(Fields)]
+struct PeriodUpdate {
+    orbital_period: f64,
+}
+
+/// The region written by `Client::move_to_region`, once it has been checked.
+#[derive(Fields)]
+struct RegionMove {
+    region: i64,
+}
+
+// -----------------------------------------------------------------------------
+// Relative motion
+// -----------------------------------------------------------------------------
+
+/// The synodic period of two bodies, in days: how long it takes for them to
+/// return to the same relative position (*eg* conjunction to conjunction),
+/// `1 / |1/T1 - 1/T2|`. Symmetric in its arguments.
+///
+/// Returns `None` if the periods are equal (the bodies never realign, so the
+/// period is infinite), or if either body has no orbital period.
+pub fn synodic_period(a: &CelestialBody, b: &CelestialBody) -> Option<f64> {
+    if a.orbital_period <= 0.0 || b.orbital_period <= 0.0 {
+        return None;
+    }
+
+    let relative_frequency = (1.0 / a.orbital_period - 1.0 / b.orbital_period).abs();
+    if relative_frequency == 0.0 {
+        None
+    } else {
+        Some(1.0 / relative_frequency)
+    }
+}
+
+/// As `Client::heliocentric_position_at`, but with the ancestors looked up in
+/// `bodies` (by id) rather than read one at a time, for when every body has been
+/// read already. An ancestor missing from `bodies` is an `EntityNotFound`.
+pub fn heliocentric_position_among(
+    body: &CelestialBody,
+    bodies: &HashMap<i64, &CelestialBody>,
+    epoch: DateTime<Utc>,
+) -> Result<(f64, f64)> {
+    let reference = reference_epoch();
+    let (mut x, mut y) = body.position_at(epoch, reference)?;
+
+    // NOTE: parent cycles are refused on write (see `check_not_ancestor`).
+    let mut parent_id = body.parent_id;
+    while let Some(id) = parent_id {
+        let parent = bodies.get(&id).ok_or(Error::EntityNotFound {
+            entity: Client::TABLE,
+            id,
+        })?;
+        let (parent_x, parent_y) = parent.position_at(epoch, reference)?;
+        x += parent_x;
+        y += parent_y;
+        parent_id = parent.parent_id;
+    }
+
+    Ok((x, y))
+}
+
+// -----------------------------------------------------------------------------
+// Validation
+// -----------------------------------------------------------------------------
+
+/// The standard gravitational parameter (GM) of a body of `mass_kg`, in km^3/s^2.
+pub fn gravitational_parameter(mass_kg: f64) -> f64 {
+    // NOTE: G is in m^3/(kg·s^2), so the result is scaled from m^3 to km^3.
+    GRAVITATIONAL_CONSTANT * mass_kg / 1e9
+}
+
+/// The period, in days, of a heliocentric orbit with the given semi-major axis
+/// (in km), via Kepler's third law: `T = 2π * sqrt(a^3 / μ)`.
+pub fn keplerian_period(semi_major_axis: f64) -> f64 {
+    period_for(semi_major_axis, SUN_GRAVITATIONAL_PARAMETER)
+}
+
+/// As `keplerian_period`, for an orbit around a primary of `primary_mass_kg`.
+pub fn keplerian_period_around(semi_major_axis: f64, primary_mass_kg: f64) -> f64 {
+    period_for(semi_major_axis, gravitational_parameter(primary_mass_kg))
+}
+
+fn period_for(semi_major_axis: f64, mu: f64) -> f64 {
+    let seconds = 2.0 * std::f64::consts::PI * (semi_major_axis.powi(3) / mu).sqrt();
+
+    seconds / SECONDS_PER_DAY
+}
+
+/// Check that the supplied orbital period agrees with the one implied by the
+/// aphelion and perihelion, to within `tolerance` (a fraction, *eg* `0.05`).
+///
+/// NOTE: this assumes the body orbits the Sun. A body with no orbit (a semi-major
+///       axis of zero) is not checked.
+pub fn check_keplerian_consistency(body: &CelestialBodyCreate, tolerance: f64) -> Result<()> {
+    check_period(body, SUN_GRAVITATIONAL_PARAMETER, tolerance)
+}
+
+/// As `check_keplerian_consistency`, for a body orbiting a primary of
+/// `primary_mass_kg` (see `Client::primary_mass`). A primary with no recorded
+/// mass is not checked.
+pub fn check_keplerian_consistency_around(
+    body: &CelestialBodyCreate,
+    primary_mass_kg: f64,
+    tolerance: f64,
+) -> Result<()> {
+    check_period(body, gravitational_parameter(primary_mass_kg), tolerance)
+}
+
+fn check_period(body: &CelestialBodyCreate, mu: f64, tolerance: f64) -> Result<()> {
+    let semi_major_axis = (body.aphelion + body.perihelion) / 2.0;
+    if semi_major_axis == 0.0 || mu <= 0.0 {
+        return Ok(());
+    }
+
+    let expected_days = period_for(semi_major_axis, mu);
+    let deviation = (body.orbital_period - expected_days).abs() / expected_days;
+
+    if deviation > tolerance {
+        Err(Error::KeplerianInconsistency {
+            expected_days,
+            supplied_days: body.orbital_period,
+        })
+    } else {
+        Ok(())
+    }
 }
 
 // -----------------------------------------------------------------------------
@@ -60,6 +572,8 @@ pub struct Client;
 
 impl DbCrudServer for Client {
     const TABLE: &'static str = "celestial_body";
+    const TIMESTAMPED: bool = true;
+    const SOFT_DELETE: bool = true;
 }
 
 impl Client {
@@ -71,6 +585,64 @@ impl Client {
         DbCrudAction::create::<Self, _>(ctx, dam, data).await
     }
 
+    /// Insert every body in a single transaction, returning the ids in input order.
+    /// If any insert fails, nothing is persisted and `BatchItemFailed` names the
+    /// offending index.
+    pub async fn create_many(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        data: Vec<CelestialBodyCreate>,
+    ) -> Result<Vec<i64>> {
+        let mut tx = dam.begin().await?;
+        let mut ids = Vec::with_capacity(data.len());
+
+        for (index, item) in data.into_iter().enumerate() {
+            // NOTE: returning early drops `tx`, which rolls the batch back.
+            let id = DbCrudAction::create_in_transaction::<Self, _>(ctx, &mut tx, item)
+                .await
+                .map_err(|err| Error::BatchItemFailed {
+                    index,
+                    cause: Box::new(err),
+                })?;
+            ids.push(id);
+        }
+        tx.commit().await?;
+        for id in &ids {
+            dam.publish(Self::TABLE, Op::Create, *id);
+        }
+
+        Ok(ids)
+    }
+
+    /// Insert each body in one transaction, returning a result per body. Unlike
+    /// `create_many`, a failed insert does not stop the rest; but if
+    /// `all_or_nothing` is set and any failed, nothing is committed.
+    pub async fn create_each(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        data: Vec<CelestialBodyCreate>,
+        all_or_nothing: bool,
+    ) -> Result<Vec<Result<i64>>> {
+        let mut tx = dam.begin().await?;
+        let mut results = Vec::with_capacity(data.len());
+
+        // NOTE: SQLite only rolls back the failing statement, so the transaction
+        //       remains usable after an individual insert fails.
+        for item in data {
+            results.push(DbCrudAction::create_in_transaction::<Self, _>(ctx, &mut tx, item).await);
+        }
+        if all_or_nothing && results.iter().any(Result::is_err) {
+            tx.rollback().await?;
+        } else {
+            tx.commit().await?;
+            for id in results.iter().flatten() {
+                dam.publish(Self::TABLE, Op::Create, *id);
+            }
+        }
+
+        Ok(results)
+    }
+
     pub async fn read(
         ctx: &RequestContext,
         dam: &DataAccessManager,
@@ -79,6 +651,24 @@ impl Client {
         DbCrudAction::read::<Self, _>(ctx, dam, id).await
     }
 
+    pub async fn read_optional(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        id: i64,
+    ) -> Result<Option<CelestialBody>> {
+        DbCrudAction::read_optional::<Self, _>(ctx, dam, id).await
+    }
+
+    /// Only the given fields of the body (see `DbCrudAction::read_columns`).
+    pub async fn read_columns(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        id: i64,
+        fields: &[&str],
+    ) -> Result<Value> {
+        DbCrudAction::read_columns::<Self, CelestialBody>(ctx, dam, id, fields).await
+    }
+
     pub async fn read_all(
         ctx: &RequestContext,
         dam: &DataAccessManager,
@@ -86,11 +676,1069 @@ impl Client {
         DbCrudAction::read_all::<Self, _>(ctx, dam).await
     }
 
+    /// The bodies with the given ids, in the order given (see
+    /// `DbCrudAction::read_many`).
+    pub async fn read_many(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        ids: &[i64],
+    ) -> Result<Vec<CelestialBody>> {
+        DbCrudAction::read_many::<Self, _>(ctx, dam, ids).await
+    }
+
+    /// Every body, in id order, a row at a time (see `DbCrudAction::stream_all`).
+    pub fn stream_all(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+    ) -> mpsc::Receiver<Result<CelestialBody>> {
+        DbCrudAction::stream_all::<Self, _>(ctx, dam)
+    }
+
+    pub async fn read_page(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<CelestialBody>> {
+        DbCrudAction::read_page::<Self, _>(ctx, dam, limit, offset).await
+    }
+
+    pub async fn read_after(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<CelestialBody>> {
+        DbCrudAction::read_after::<Self, _>(ctx, dam, after_id, limit).await
+    }
+
+    pub async fn read_all_sorted(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        sort: SortSpec,
+    ) -> Result<Vec<CelestialBody>> {
+        DbCrudAction::read_all_sorted::<Self, _>(ctx, dam, sort).await
+    }
+
+    /// Bodies created or updated at or after `since`, oldest change first (see
+    /// `DbCrudAction::read_updated_since`).
+    pub async fn read_updated_since(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        since: i64,
+    ) -> Result<Vec<CelestialBody>> {
+        DbCrudAction::read_updated_since::<Self, _>(ctx, dam, since).await
+    }
+
+    /// As `read`, with the region and subregion names (see `CelestialBodyExpanded`).
+    pub async fn read_expanded(
+        _ctx: &RequestContext,
+        dam: &DataAccessManager,
+        id: i64,
+    ) -> Result<CelestialBodyExpanded> {
+        timed(
+            Self::TABLE,
+            "read_expanded",
+            slow_query_threshold(),
+            async {
+                let sql = format!("{} WHERE b.id = ?1", expanded_select());
+                let body: Option<CelestialBodyExpanded> = sqlx::query_as(&sql)
+                    .bind(id)
+                    .fetch_optional(&mut *acquire(dam).await?)
+                    .await?;
+
+                body.ok_or(Error::EntityNotFound {
+                    entity: Self::TABLE,
+                    id,
+                })
+            },
+        )
+        .await
+    }
+
+    /// As `read_all_sorted`, with the region and subregion names (see
+    /// `CelestialBodyExpanded`). Unknown sort columns fall back to `id`.
+    pub async fn read_all_expanded(
+        _ctx: &RequestContext,
+        dam: &DataAccessManager,
+        sort: SortSpec,
+    ) -> Result<Vec<CelestialBodyExpanded>> {
+        timed(
+            Self::TABLE,
+            "read_all_expanded",
+            slow_query_threshold(),
+            async {
+                let column = CelestialBody::field_names()
+                    .iter()
+                    .find(|name| **name == sort.column)
+                    .unwrap_or(&"id");
+                let direction = if sort.descending { "DESC" } else { "ASC" };
+                let sql = format!(
+                    "{} ORDER BY b.{} {direction}",
+                    expanded_select(),
+                    quote_identifier(column)
+                );
+                let bodies: Vec<CelestialBodyExpanded> = sqlx::query_as(&sql)
+                    .fetch_all(&mut *acquire(dam).await?)
+                    .await?;
+
+                Ok(bodies)
+            },
+        )
+        .await
+    }
+
+    /// Every body, in `ordering`; ties are broken by id.
+    pub async fn read_all_ordered(
+        _ctx: &RequestContext,
+        dam: &DataAccessManager,
+        ordering: BodyOrdering,
+        descending: bool,
+    ) -> Result<Vec<CelestialBody>> {
+        timed(
+            Self::TABLE,
+            "read_all_ordered",
+            slow_query_threshold(),
+            async {
+                let direction = if descending { "DESC" } else { "ASC" };
+                let sql = format!(
+                    "SELECT {} FROM {} {} ORDER BY {} {direction}, id",
+                    select_columns::<CelestialBody>(),
+                    Self::TABLE,
+                    where_clause::<Self>(&[]),
+                    ordering.expression()
+                );
+                let bodies: Vec<CelestialBody> = sqlx::query_as(&sql)
+                    .fetch_all(&mut *acquire(dam).await?)
+                    .await?;
+
+                Ok(bodies)
+            },
+        )
+        .await
+    }
+
+    /// All bodies in the given region, ordered by id. A region with no bodies (or
+    /// one that doesn't exist) returns an empty vec rather than an error.
+    pub async fn read_by_region(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        region_id: i64,
+    ) -> Result<Vec<CelestialBody>> {
+        DbCrudAction::read_all_where::<Self, _, _>(ctx, dam, ("region", "=", region_id)).await
+    }
+
+    /// All bodies in the given subregion, ordered by id. As with `read_by_region`,
+    /// a subregion with no bodies returns an empty vec.
+    pub async fn read_by_subregion(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        subregion_id: i64,
+    ) -> Result<Vec<CelestialBody>> {
+        DbCrudAction::read_all_where::<Self, _, _>(ctx, dam, ("subregion", "=", subregion_id)).await
+    }
+
+    /// The bodies orbiting `parent_id`, ordered by id.
+    pub async fn read_children(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        parent_id: i64,
+    ) -> Result<Vec<CelestialBody>> {
+        DbCrudAction::read_all_where::<Self, _, _>(ctx, dam, ("parent_id", "=", parent_id)).await
+    }
+
+    /// Position of `body` relative to the Sun at `epoch`, as `(x, y)` in km (see
+    /// `CelestialBody::position_at` for the approximations made). A moon's
+    /// position is offset by its parent's, and so on up to the Sun.
+    pub async fn heliocentric_position_at(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        body: &CelestialBody,
+        epoch: DateTime<Utc>,
+    ) -> Result<(f64, f64)> {
+        let reference = reference_epoch();
+        let (mut x, mut y) = body.position_at(epoch, reference)?;
+
+        // NOTE: parent cycles are refused on write (see `check_not_ancestor`), so
+        //       this always reaches a body orbiting the Sun.
+        let mut parent_id = body.parent_id;
+        while let Some(id) = parent_id {
+            let parent = Self::read(ctx, dam, id).await?;
+            let (parent_x, parent_y) = parent.position_at(epoch, reference)?;
+            x += parent_x;
+            y += parent_y;
+            parent_id = parent.parent_id;
+        }
+
+        Ok((x, y))
+    }
+
+    /// The mass, in kg, of the body orbited by a body with `parent_id`: the
+    /// parent's, or the Sun's if there is none.
+    pub async fn primary_mass(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        parent_id: Option<i64>,
+    ) -> Result<f64> {
+        match parent_id {
+            Some(parent_id) => Ok(Self::read(ctx, dam, parent_id).await?.mass),
+            None => Ok(SUN_MASS),
+        }
+    }
+
+    /// Recompute every body's orbital period from its semi-major axis, via Kepler's
+    /// third law around its primary (see `primary_mass`), and rewrite those whose
+    /// stored period deviates by more than `tolerance` (a fraction, *eg* `0.05`).
+    /// The writes are made in a single transaction. Returns how many changed.
+    ///
+    /// Bodies with no orbit, or orbiting a primary with no recorded mass, are left
+    /// alone, as there is no period to derive.
+    pub async fn recompute_periods(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        tolerance: f64,
+    ) -> Result<usize> {
+        // NOTE: everything is read before the transaction begins, as the test pool
+        //       has a single connection, which the transaction holds until commit.
+        let bodies = Self::read_all(ctx, dam).await?;
+        let masses: HashMap<i64, f64> = bodies.iter().map(|body| (body.id, body.mass)).collect();
+
+        let mut tx = dam.begin().await?;
+        let mut changed = Vec::new();
+        for body in &bodies {
+            let mu = match body.parent_id {
+                Some(parent_id) => {
+                    let primary_mass = masses.get(&parent_id).copied().unwrap_or(0.0);
+                    gravitational_parameter(primary_mass)
+                }
+                None => SUN_GRAVITATIONAL_PARAMETER,
+            };
+            let semi_major_axis = body.semi_major_axis();
+            if semi_major_axis == 0.0 || mu <= 0.0 {
+                continue;
+            }
+
+            let expected_days = period_for(semi_major_axis, mu);
+            let deviation = (body.orbital_period - expected_days).abs() / expected_days;
+            if deviation > tolerance {
+                let data = PeriodUpdate {
+                    orbital_period: expected_days,
+                };
+                DbCrudAction::update_in_transaction::<Self, _>(ctx, &mut tx, body.id, data).await?;
+                changed.push(body.id);
+            }
+        }
+        tx.commit().await?;
+        for id in &changed {
+            dam.publish(Self::TABLE, Op::Update, *id);
+        }
+
+        Ok(changed.len())
+    }
+
+    /// Move the body into another region. Both must exist (and not be deleted),
+    /// otherwise `EntityNotFound` names whichever is missing; both are checked in
+    /// the same transaction as the update, so neither can be deleted in between.
+    ///
+    /// NOTE: the subregion is left as it is, even if it is in the old region.
+    pub async fn move_to_region(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        body_id: i64,
+        region_id: i64,
+    ) -> Result<()> {
+        let mut tx = dam.begin().await?;
+        if !DbCrudAction::exists_in_transaction::<Self>(ctx, &mut tx, body_id).await? {
+            return Err(Error::EntityNotFound {
+                entity: Self::TABLE,
+                id: body_id,
+            });
+        }
+        if !DbCrudAction::exists_in_transaction::<celestial_region::Client>(ctx, &mut tx, region_id)
+            .await?
+        {
+            return Err(Error::EntityNotFound {
+                entity: celestial_region::Client::TABLE,
+                id: region_id,
+            });
+        }
+        let data = RegionMove { region: region_id };
+        DbCrudAction::update_in_transaction::<Self, _>(ctx, &mut tx, body_id, data).await?;
+        tx.commit().await?;
+        dam.publish(Self::TABLE, Op::Update, body_id);
+
+        Ok(())
+    }
+
+    /// Partial update: `None` fields are skipped by `not_none_fields`, so they are
+    /// left untouched rather than being nulled out.
+    pub async fn update(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        id: i64,
+        data: CelestialBodyUpdate,
+    ) -> Result<()> {
+        if let Some(parent_id) = data.parent_id {
+            Self::check_not_ancestor(ctx, dam, id, parent_id).await?;
+        }
+
+        DbCrudAction::update::<Self, _>(ctx, dam, id, data).await
+    }
+
+    /// Reject making `parent_id` the parent of `id` if that would make `id` its
+    /// own ancestor. Only updates need checking: a new body has no descendants.
+    async fn check_not_ancestor(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        id: i64,
+        parent_id: i64,
+    ) -> Result<()> {
+        // NOTE: the visited set stops the walk if the data already has a cycle.
+        let mut visited = HashSet::new();
+        let mut ancestor = Some(parent_id);
+        while let Some(ancestor_id) = ancestor {
+            if ancestor_id == id {
+                return Err(Error::ParentCycle { id, parent_id });
+            }
+            if !visited.insert(ancestor_id) {
+                break;
+            }
+            ancestor = Self::read(ctx, dam, ancestor_id).await?.parent_id;
+        }
+
+        Ok(())
+    }
+
+    pub async fn exists(ctx: &RequestContext, dam: &DataAccessManager, id: i64) -> Result<bool> {
+        DbCrudAction::exists::<Self>(ctx, dam, id).await
+    }
+
+    pub async fn count(ctx: &RequestContext, dam: &DataAccessManager) -> Result<i64> {
+        DbCrudAction::count::<Self>(ctx, dam).await
+    }
+
+    pub async fn search_by_name(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        query: &str,
+        limit: i64,
+    ) -> Result<Vec<CelestialBody>> {
+        DbCrudAction::search_by_name::<Self, _>(ctx, dam, query, limit).await
+    }
+
+    /// Bodies whose name is a close match for `query`, even with a typo or two
+    /// (*eg* "jupter"), best first, up to `limit`. Each comes with a score from
+    /// `0.0` to `1.0` (an exact match, ignoring case).
+    ///
+    /// A third of the query's length may be edits, from 1 to `SUGGEST_MAX_DISTANCE`,
+    /// so short queries only match near-identical names. An empty query matches
+    /// nothing.
+    pub async fn suggest_by_name(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<(CelestialBody, f64)>> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let length = query.chars().count();
+        let max_distance = (length / 3).clamp(1, SUGGEST_MAX_DISTANCE);
+        let candidates: Vec<CelestialBody> = DbCrudAction::read_name_candidates::<Self, _>(
+            ctx,
+            dam,
+            &query,
+            max_distance,
+            SUGGEST_CANDIDATE_LIMIT,
+        )
+        .await?;
+
+        let mut suggestions: Vec<(CelestialBody, f64)> = candidates
+            .into_iter()
+            .filter_map(|body| {
+                let name = body.name.to_lowercase();
+                let distance = levenshtein_within(&query, &name, max_distance)?;
+                let longest = length.max(name.chars().count());
+                let score = 1.0 - distance as f64 / longest as f64;
+                Some((body, score))
+            })
+            .collect();
+        suggestions.sort_by(|(a, a_score), (b, b_score)| {
+            b_score.total_cmp(a_score).then_with(|| a.name.cmp(&b.name))
+        });
+        suggestions.truncate(limit);
+
+        Ok(suggestions)
+    }
+
+    /// As `update`, but rejected with `StaleWrite` if the row has been updated
+    /// since the caller read `expected_updated_at`.
+    pub async fn update_if_unchanged(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        id: i64,
+        expected_updated_at: Option<i64>,
+        data: CelestialBodyUpdate,
+    ) -> Result<()> {
+        if let Some(parent_id) = data.parent_id {
+            Self::check_not_ancestor(ctx, dam, id, parent_id).await?;
+        }
+
+        DbCrudAction::update_if_unchanged::<Self, _>(ctx, dam, id, expected_updated_at, data).await
+    }
+
+    /// Includes soft-deleted rows: for admin use only.
+    pub async fn read_all_including_deleted(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+    ) -> Result<Vec<CelestialBody>> {
+        DbCrudAction::read_all_including_deleted::<Self, _>(ctx, dam).await
+    }
+
+    /// Soft delete: the row is kept, but no longer returned by reads.
     pub async fn delete(ctx: &RequestContext, dam: &DataAccessManager, id: i64) -> Result<()> {
         DbCrudAction::delete::<Self>(ctx, dam, id).await
     }
+
+    /// Soft delete every body with one of the given ids, returning how many were
+    /// deleted. Unknown ids are skipped rather than being an error.
+    pub async fn delete_many(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        ids: &[i64],
+    ) -> Result<u64> {
+        DbCrudAction::delete_many::<Self>(ctx, dam, ids).await
+    }
+}
+
+/// The `SELECT` for `CelestialBodyExpanded`, with the bodies aliased as `b`. Each
+/// table is filtered by its own `where_clause`, so deleted bodies are skipped, and
+/// so are the names of deleted regions and subregions.
+fn expanded_select() -> String {
+    format!(
+        "SELECT b.*, r.name AS region_name, s.name AS subregion_name \
+         FROM (SELECT {} FROM {} {}) AS b \
+         LEFT JOIN (SELECT id, name FROM {} {}) AS r ON r.id = b.region \
+         LEFT JOIN (SELECT id, name FROM {} {}) AS s ON s.id = b.subregion",
+        select_columns::<CelestialBody>(),
+        Client::TABLE,
+        where_clause::<Client>(&[]),
+        celestial_region::Client::TABLE,
+        where_clause::<celestial_region::Client>(&[]),
+        celestial_subregion::Client::TABLE,
+        where_clause::<celestial_subregion::Client>(&[])
+    )
 }
 
 // -----------------------------------------------------------------------------
 // Tests
 // -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils::{body_create, initialise_test_environment};
+    use crate::data_access::EntityEvent;
+    use serial_test::serial;
+
+    fn fixture(name: &str, aphelion: f64, perihelion: f64) -> CelestialBody {
+        CelestialBody {
+            id: 1,
+            name: name.to_string(),
+            region: None,
+            subregion: None,
+            parent_id: None,
+            aphelion,
+            perihelion,
+            orbital_period: 0.0,
+            radius: 0.0,
+            mass: 0.0,
+            created_at: 0,
+            updated_at: None,
+        }
+    }
+
+    #[test]
+    fn test_eccentricity() {
+        let earth = fixture("Earth", 152_100_000.0, 147_095_000.0);
+        assert!((earth.eccentricity() - 0.0167).abs() < 1e-4);
+
+        // Halley's Comet: aphelion 35.14 AU, perihelion 0.586 AU.
+        let halley = fixture("Halley", 5_256_947_000.0, 87_664_000.0);
+        assert!((halley.eccentricity() - 0.967).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_eccentricity_degenerate_cases() {
+        assert_eq!(fixture("Sun", 0.0, 0.0).eccentricity(), 0.0);
+
+        let swapped = fixture("Swapped", 147_095_000.0, 152_100_000.0);
+        let earth = fixture("Earth", 152_100_000.0, 147_095_000.0);
+        assert_eq!(swapped.eccentricity(), earth.eccentricity());
+    }
+
+    #[test]
+    fn test_mars_axes() {
+        let mars = fixture("Mars", 249_261_000.0, 206_650_000.0);
+        // Published values: a = 227.94 million km, b = 226.94 million km.
+        assert!((mars.semi_major_axis() - 227_939_200.0).abs() / 227_939_200.0 < 1e-3);
+        assert!((mars.semi_minor_axis() - 226_940_000.0).abs() / 226_940_000.0 < 1e-3);
+    }
+
+    #[test]
+    fn test_unit_conversions() {
+        let earth = CelestialBody {
+            radius: 6_371.0,
+            ..fixture("Earth", 152_100_000.0, 147_095_000.0)
+        };
+        assert!((earth.semi_major_axis_au() - 1.0).abs() < 1e-3);
+        assert!((earth.aphelion_au() - 1.0167).abs() < 1e-4);
+        assert!((earth.perihelion_au() - 0.9833).abs() < 1e-4);
+        assert!((earth.radius_in_solar_radii() - 0.009_158).abs() < 1e-6);
+
+        let sun = CelestialBody {
+            radius: 695_700.0,
+            ..fixture("Sun", 0.0, 0.0)
+        };
+        assert_eq!(sun.radius_in_solar_radii(), 1.0);
+        assert_eq!(sun.semi_major_axis_au(), 0.0);
+    }
+
+    #[test]
+    fn test_circular_orbit_true_anomaly_equals_mean_anomaly() {
+        let reference = DateTime::<Utc>::from_timestamp(0, 0).unwrap();
+        let body = CelestialBody {
+            orbital_period: 100.0,
+            ..fixture("Circular", 1_000.0, 1_000.0)
+        };
+
+        for days in [0, 25, 50, 99, 150, -25] {
+            let epoch = reference + chrono::Duration::days(days);
+            let mean = body.mean_anomaly_at(epoch, reference);
+            let true_anomaly = body.true_anomaly_at(epoch, reference).unwrap();
+            assert!((0.0..TAU).contains(&mean), "{days}: {mean}");
+            assert!(
+                (true_anomaly - mean).abs() < 1e-8,
+                "{days}: {true_anomaly} != {mean}"
+            );
+        }
+
+        let quarter = reference + chrono::Duration::days(25);
+        assert!((body.mean_anomaly_at(quarter, reference) - TAU / 4.0).abs() < 1e-12);
+        let before = reference - chrono::Duration::days(25);
+        assert!((body.mean_anomaly_at(before, reference) - 3.0 * TAU / 4.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_eccentric_orbit_true_anomaly() {
+        let reference = DateTime::<Utc>::from_timestamp(0, 0).unwrap();
+        let body = CelestialBody {
+            orbital_period: 100.0,
+            ..fixture("Eccentric", 3_000.0, 1_000.0)
+        };
+
+        // Half an orbit from perihelion is aphelion, whatever the eccentricity.
+        let half = reference + chrono::Duration::days(50);
+        let at_aphelion = body.true_anomaly_at(half, reference).unwrap();
+        assert!((at_aphelion - std::f64::consts::PI).abs() < 1e-8);
+
+        // Bodies move fastest near perihelion, so the true anomaly leads the mean.
+        let early = reference + chrono::Duration::days(10);
+        assert!(
+            body.true_anomaly_at(early, reference).unwrap()
+                > body.mean_anomaly_at(early, reference)
+        );
+    }
+
+    #[test]
+    fn test_distance_from_sun() {
+        let reference = DateTime::<Utc>::from_timestamp(0, 0).unwrap();
+        let earth = CelestialBody {
+            orbital_period: 365.256,
+            ..fixture("Earth", 152_100_000.0, 147_095_000.0)
+        };
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-3;
+
+        let at_perihelion = earth.distance_from_sun_at(reference, reference).unwrap();
+        assert!(close(at_perihelion, earth.perihelion), "{at_perihelion}");
+        let half_year = reference + chrono::Duration::milliseconds(365_256 * 43_200);
+        let at_aphelion = earth.distance_from_sun_at(half_year, reference).unwrap();
+        assert!(close(at_aphelion, earth.aphelion), "{at_aphelion}");
+
+        // At ν = π/2 the distance is the semi-latus rectum, a(1 - e²).
+        let e = earth.eccentricity();
+        let expected = earth.semi_major_axis() * (1.0 - e * e);
+        let mid_orbit = earth.distance_at_true_anomaly(std::f64::consts::FRAC_PI_2);
+        assert!(close(mid_orbit, expected), "{mid_orbit}");
+        assert!(earth.perihelion < mid_orbit && mid_orbit < earth.aphelion);
+    }
+
+    #[test]
+    fn test_orbital_velocity() {
+        let earth = fixture("Earth", 152_100_000.0, 147_095_000.0);
+        assert!((earth.velocity_at_perihelion() - 30.29).abs() < 0.05);
+        assert!((earth.velocity_at_aphelion() - 29.29).abs() < 0.05);
+
+        assert_eq!(earth.orbital_velocity_at(0.0), 0.0);
+        assert_eq!(earth.orbital_velocity_at(-1.0), 0.0);
+        assert_eq!(fixture("Sun", 0.0, 0.0).velocity_at_perihelion(), 0.0);
+    }
+
+    #[test]
+    fn test_surface_gravity_and_escape_velocity() {
+        let earth = CelestialBody {
+            radius: 6_371.0,
+            mass: 5.972e24,
+            ..fixture("Earth", 152_100_000.0, 147_095_000.0)
+        };
+        assert!((earth.surface_gravity() - 9.8).abs() < 0.05);
+        assert!((earth.escape_velocity() - 11.2).abs() < 0.05);
+
+        let unmeasured = fixture("Unmeasured", 0.0, 0.0);
+        assert_eq!(unmeasured.surface_gravity(), 0.0);
+        assert_eq!(unmeasured.escape_velocity(), 0.0);
+    }
+
+    #[test]
+    fn test_hill_sphere_radius() {
+        let earth = CelestialBody {
+            mass: 5.972e24,
+            ..fixture("Earth", 152_100_000.0, 147_095_000.0)
+        };
+        let radius = earth.hill_sphere_radius(SUN_MASS);
+        assert!((radius - 1_500_000.0).abs() < 50_000.0, "{radius}");
+
+        assert_eq!(earth.hill_sphere_radius(0.0), 0.0);
+    }
+
+    #[test]
+    fn test_orbits_around_a_parent() {
+        const EARTH_MASS: f64 = 5.972e24;
+        // The Moon: aphelion 405,400 km, perihelion 362,600 km (from the Earth).
+        let moon = CelestialBody {
+            orbital_period: 27.32,
+            ..fixture("Moon", 405_400.0, 362_600.0)
+        };
+
+        let period = keplerian_period_around(moon.semi_major_axis(), EARTH_MASS);
+        assert!((period - 27.3).abs() < 0.5, "{period}");
+        let speed = moon.orbital_velocity_around(moon.semi_major_axis(), EARTH_MASS);
+        assert!((speed - 1.02).abs() < 0.02, "{speed}");
+
+        let moon = CelestialBodyCreate {
+            aphelion: 405_400.0,
+            perihelion: 362_600.0,
+            orbital_period: 27.32,
+            ..body_create("Moon")
+        };
+        assert!(check_keplerian_consistency_around(&moon, EARTH_MASS, 0.05).is_ok());
+        assert!(check_keplerian_consistency(&moon, 0.05).is_err());
+        assert!(check_keplerian_consistency_around(&moon, 0.0, 0.05).is_ok());
+    }
+
+    #[test]
+    fn test_true_anomaly_rejects_unbound_orbit() {
+        let epoch = DateTime::<Utc>::from_timestamp(0, 0).unwrap();
+        let body = CelestialBody {
+            orbital_period: 100.0,
+            ..fixture("Parabolic", 1_000.0, 0.0)
+        };
+
+        assert!(matches!(
+            body.true_anomaly_at(epoch, epoch),
+            Err(Error::UnboundOrbit { .. })
+        ));
+    }
+
+    #[test]
+    fn test_synodic_period() {
+        let earth = CelestialBody {
+            orbital_period: 365.256,
+            ..fixture("Earth", 0.0, 0.0)
+        };
+        let mars = CelestialBody {
+            orbital_period: 686.98,
+            ..fixture("Mars", 0.0, 0.0)
+        };
+
+        let period = synodic_period(&earth, &mars).unwrap();
+        assert!((period - 780.0).abs() < 1.0, "{period}");
+        assert_eq!(synodic_period(&mars, &earth), Some(period));
+
+        assert_eq!(synodic_period(&earth, &earth.clone()), None);
+        assert_eq!(synodic_period(&earth, &fixture("Sun", 0.0, 0.0)), None);
+    }
+
+    #[test]
+    fn test_keplerian_period() {
+        // One astronomical unit should give (very nearly) one sidereal year.
+        assert!((keplerian_period(149_597_870.7) - 365.256).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_check_keplerian_consistency() {
+        let earth = |orbital_period| CelestialBodyCreate {
+            aphelion: 152_100_000.0,
+            perihelion: 147_095_000.0,
+            orbital_period,
+            ..body_create("Earth")
+        };
+        assert!(check_keplerian_consistency(&earth(365.256), 0.05).is_ok());
+
+        let within = earth(365.256 * 1.04);
+        assert!(check_keplerian_consistency(&within, 0.05).is_ok());
+
+        let outside = earth(365.256 * 1.06);
+        assert!(matches!(
+            check_keplerian_consistency(&outside, 0.05),
+            Err(Error::KeplerianInconsistency { .. })
+        ));
+
+        let no_orbit = body_create("No orbit");
+        assert!(check_keplerian_consistency(&no_orbit, 0.05).is_ok());
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_exists() -> anyhow::Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let data = body_create("test_exists");
+        let id = Client::create(&ctx, &dam, data).await?;
+
+        assert!(Client::exists(&ctx, &dam, id).await?);
+        assert!(!Client::exists(&ctx, &dam, 999_999).await?);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_update() -> anyhow::Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let data = body_create("test_update_before");
+        let id = Client::create(&ctx, &dam, data).await?;
+
+        let data = CelestialBodyUpdate {
+            name: Some("test_update_after".to_string()),
+            ..Default::default()
+        };
+        Client::update(&ctx, &dam, id, data).await?;
+        let body = Client::read(&ctx, &dam, id).await?;
+        assert_eq!(body.name, "test_update_after");
+        assert!(body.updated_at.is_some());
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_move_to_region() -> anyhow::Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let region = celestial_region::RegionCreate {
+            name: "test_move_destination".to_string(),
+            description: None,
+        };
+        let region_id = celestial_region::Client::create(&ctx, &dam, region).await?;
+        let data = body_create("test_move_body");
+        let id = Client::create(&ctx, &dam, data).await?;
+
+        Client::move_to_region(&ctx, &dam, id, region_id).await?;
+        let body = Client::read(&ctx, &dam, id).await?;
+        assert_eq!(body.region, Some(region_id));
+        assert!(body.updated_at.is_some());
+
+        // Each missing id is reported as such, and nothing is changed.
+        let result = Client::move_to_region(&ctx, &dam, 999_999, region_id).await;
+        assert!(matches!(
+            result,
+            Err(Error::EntityNotFound {
+                entity: "celestial_body",
+                id: 999_999
+            })
+        ));
+        let result = Client::move_to_region(&ctx, &dam, id, 999_999).await;
+        assert!(matches!(
+            result,
+            Err(Error::EntityNotFound {
+                entity: "celestial_region",
+                id: 999_999
+            })
+        ));
+        assert_eq!(Client::read(&ctx, &dam, id).await?.region, Some(region_id));
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_update_with_no_fields_keeps_name() -> anyhow::Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let data = body_create("test_update_with_no_fields");
+        let id = Client::create(&ctx, &dam, data).await?;
+
+        Client::update(&ctx, &dam, id, CelestialBodyUpdate::default()).await?;
+        let body = Client::read(&ctx, &dam, id).await?;
+        assert_eq!(body.name, "test_update_with_no_fields");
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_read_by_region_and_subregion() -> anyhow::Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let region_id = celestial_region::Client::create(
+            &ctx,
+            &dam,
+            celestial_region::RegionCreate {
+                name: "test_read_by_region".to_string(),
+                description: None,
+            },
+        )
+        .await?;
+        let subregion_id = celestial_subregion::Client::create(
+            &ctx,
+            &dam,
+            celestial_subregion::SubregionCreate {
+                name: "test_read_by_subregion".to_string(),
+                description: None,
+                region_id,
+            },
+        )
+        .await?;
+        for name in ["test_read_by_region_a", "test_read_by_region_b"] {
+            let data = CelestialBodyCreate {
+                region: Some(region_id),
+                subregion: Some(subregion_id),
+                ..body_create(name)
+            };
+            Client::create(&ctx, &dam, data).await?;
+        }
+
+        let bodies = Client::read_by_region(&ctx, &dam, region_id).await?;
+        let names: Vec<&str> = bodies.iter().map(|body| body.name.as_str()).collect();
+        assert_eq!(names, ["test_read_by_region_a", "test_read_by_region_b"]);
+        assert_eq!(
+            Client::read_by_subregion(&ctx, &dam, subregion_id)
+                .await?
+                .len(),
+            2
+        );
+
+        assert!(Client::read_by_region(&ctx, &dam, 999_999)
+            .await?
+            .is_empty());
+        assert!(Client::read_by_subregion(&ctx, &dam, 999_999)
+            .await?
+            .is_empty());
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_read_expanded() -> anyhow::Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let region_id = celestial_region::Client::create(
+            &ctx,
+            &dam,
+            celestial_region::RegionCreate {
+                name: "test_expanded_region".to_string(),
+                description: None,
+            },
+        )
+        .await?;
+        let subregion_id = celestial_subregion::Client::create(
+            &ctx,
+            &dam,
+            celestial_subregion::SubregionCreate {
+                name: "test_expanded_subregion".to_string(),
+                description: None,
+                region_id,
+            },
+        )
+        .await?;
+        let placed = CelestialBodyCreate {
+            region: Some(region_id),
+            subregion: Some(subregion_id),
+            ..body_create("test_expanded_placed")
+        };
+        let placed = Client::create(&ctx, &dam, placed).await?;
+        let unplaced = body_create("test_expanded_unplaced");
+        let unplaced = Client::create(&ctx, &dam, unplaced).await?;
+
+        let expanded = Client::read_expanded(&ctx, &dam, placed).await?;
+        assert_eq!(expanded.body.name, "test_expanded_placed");
+        assert_eq!(
+            expanded.region_name.as_deref(),
+            Some("test_expanded_region")
+        );
+        assert_eq!(
+            expanded.subregion_name.as_deref(),
+            Some("test_expanded_subregion")
+        );
+
+        // Null foreign keys give null names, not errors.
+        let expanded = Client::read_expanded(&ctx, &dam, unplaced).await?;
+        assert_eq!(
+            (expanded.region_name, expanded.subregion_name),
+            (None, None)
+        );
+
+        let all = Client::read_all_expanded(&ctx, &dam, SortSpec::default()).await?;
+        let placed = all
+            .iter()
+            .find(|expanded| expanded.body.id == placed)
+            .unwrap();
+        assert_eq!(placed.region_name.as_deref(), Some("test_expanded_region"));
+        assert!(matches!(
+            Client::read_expanded(&ctx, &dam, 999_999).await,
+            Err(Error::EntityNotFound { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_many() -> anyhow::Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let mut events = dam.subscribe();
+        let names = [
+            "test_create_many_a",
+            "test_create_many_b",
+            "test_create_many_c",
+        ];
+        let data = names.iter().map(|name| body_create(name)).collect();
+
+        let ids = Client::create_many(&ctx, &dam, data).await?;
+        assert_eq!(ids.len(), 3);
+        // Each body is published once the batch has been committed.
+        for id in &ids {
+            let event = EntityEvent {
+                table: Client::TABLE,
+                op: Op::Create,
+                id: *id,
+            };
+            assert_eq!(events.try_recv()?, event);
+        }
+        for (id, name) in ids.into_iter().zip(names) {
+            assert_eq!(Client::read(&ctx, &dam, id).await?.name, name);
+        }
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_many_rolls_back_on_failure() -> anyhow::Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let mut events = dam.subscribe();
+        let before = Client::count(&ctx, &dam).await?;
+        let data = [
+            "test_create_many_dup",
+            "test_create_many_ok",
+            "test_create_many_dup",
+        ]
+        .iter()
+        .map(|name| body_create(name))
+        .collect();
+
+        let result = Client::create_many(&ctx, &dam, data).await;
+        assert!(matches!(
+            result,
+            Err(Error::BatchItemFailed { index: 2, ref cause })
+                if matches!(**cause, Error::UniqueViolation(_))
+        ));
+        assert_eq!(Client::count(&ctx, &dam).await?, before);
+        assert!(events.try_recv().is_err());
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_each() -> anyhow::Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let data = |names: [&str; 3]| names.iter().map(|name| body_create(name)).collect();
+        let before = Client::count(&ctx, &dam).await?;
+
+        // All or nothing: the duplicate rolls back the whole batch.
+        let names = [
+            "test_each_strict",
+            "test_each_strict_ok",
+            "test_each_strict",
+        ];
+        let results = Client::create_each(&ctx, &dam, data(names), true).await?;
+        assert!(results[0].is_ok() && results[1].is_ok());
+        assert!(matches!(results[2], Err(Error::UniqueViolation(_))));
+        assert_eq!(Client::count(&ctx, &dam).await?, before);
+
+        // Otherwise the other inserts are kept.
+        let names = ["test_each_dup", "test_each_ok", "test_each_dup"];
+        let results = Client::create_each(&ctx, &dam, data(names), false).await?;
+        assert!(matches!(results[2], Err(Error::UniqueViolation(_))));
+        assert_eq!(Client::count(&ctx, &dam).await?, before + 2);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_planet_with_moons() -> anyhow::Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let create = |name: &str, parent_id: Option<i64>| CelestialBodyCreate {
+            parent_id,
+            mass: 1.0e20,
+            ..body_create(name)
+        };
+        let planet = Client::create(&ctx, &dam, create("test_moons_planet", None)).await?;
+        let phobos = Client::create(&ctx, &dam, create("test_moons_phobos", Some(planet))).await?;
+        let deimos = Client::create(&ctx, &dam, create("test_moons_deimos", Some(planet))).await?;
+
+        let children = Client::read_children(&ctx, &dam, planet).await?;
+        let ids: Vec<i64> = children.iter().map(|body| body.id).collect();
+        assert_eq!(ids, [phobos, deimos]);
+        assert!(Client::read_children(&ctx, &dam, phobos).await?.is_empty());
+        assert_eq!(
+            Client::primary_mass(&ctx, &dam, Some(planet)).await?,
+            1.0e20
+        );
+        assert_eq!(Client::primary_mass(&ctx, &dam, None).await?, SUN_MASS);
+
+        // Neither a body nor one of its moons may become its parent.
+        for parent_id in [planet, phobos] {
+            let data = CelestialBodyUpdate {
+                parent_id: Some(parent_id),
+                ..Default::default()
+            };
+            let result = Client::update(&ctx, &dam, planet, data).await;
+            assert!(matches!(result, Err(Error::ParentCycle { id, .. }) if id == planet));
+        }
+
+        // Moving a moon to orbit its sibling is fine.
+        let data = CelestialBodyUpdate {
+            parent_id: Some(phobos),
+            ..Default::default()
+        };
+        Client::update(&ctx, &dam, deimos, data).await?;
+        assert_eq!(
+            Client::read(&ctx, &dam, deimos).await?.parent_id,
+            Some(phobos)
+        );
+
+        Ok(())
+    }
+}
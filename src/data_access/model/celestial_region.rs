@@ -0,0 +1,209 @@
+use crate::data_access::store::db::{DbCrudAction, DbCrudServer};
+use crate::data_access::{DataAccessManager, Result};
+use crate::RequestContext;
+use serde::{Deserialize, Serialize};
+use sqlb::Fields;
+use sqlx::FromRow;
+
+// -----------------------------------------------------------------------------
+// Types
+// -----------------------------------------------------------------------------
+
+/// Returned from the data access layer, hence `Serialize`.
+#[derive(Clone, Debug, Fields, FromRow, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Region {
+    pub id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: i64,
+    pub updated_at: Option<i64>,
+}
+
+/// Sent to the data access layer, hence `Deserialize`.
+#[derive(Fields, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RegionCreate {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+/// Sent to the data access layer, hence `Deserialize`.
+#[derive(Default, Fields, Deserialize)]
+pub struct RegionUpdate {
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+// -----------------------------------------------------------------------------
+// Client
+// -----------------------------------------------------------------------------
+
+pub struct Client;
+
+impl DbCrudServer for Client {
+    const TABLE: &'static str = "celestial_region";
+    const TIMESTAMPED: bool = true;
+    const SOFT_DELETE: bool = true;
+}
+
+impl Client {
+    pub async fn create(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        data: RegionCreate,
+    ) -> Result<i64> {
+        let id = DbCrudAction::create::<Self, _>(ctx, dam, data).await?;
+        // NOTE: ids can be reused after a reset, so a stale entry may exist.
+        dam.region_cache().invalidate(id);
+
+        Ok(id)
+    }
+
+    pub async fn read(ctx: &RequestContext, dam: &DataAccessManager, id: i64) -> Result<Region> {
+        if let Some(cached) = dam.region_cache().get(id) {
+            return Ok(cached);
+        }
+
+        let entity: Region = DbCrudAction::read::<Self, _>(ctx, dam, id).await?;
+        dam.region_cache().insert(id, entity.clone());
+
+        Ok(entity)
+    }
+
+    pub async fn exists(ctx: &RequestContext, dam: &DataAccessManager, id: i64) -> Result<bool> {
+        DbCrudAction::exists::<Self>(ctx, dam, id).await
+    }
+
+    pub async fn read_all(ctx: &RequestContext, dam: &DataAccessManager) -> Result<Vec<Region>> {
+        DbCrudAction::read_all::<Self, _>(ctx, dam).await
+    }
+
+    /// The regions with the given ids, in the order given; missing ids are
+    /// skipped, not errors.
+    pub async fn read_many(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        ids: &[i64],
+    ) -> Result<Vec<Region>> {
+        DbCrudAction::read_many::<Self, _>(ctx, dam, ids).await
+    }
+
+    pub async fn read_page(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Region>> {
+        DbCrudAction::read_page::<Self, _>(ctx, dam, limit, offset).await
+    }
+
+    pub async fn update(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        id: i64,
+        data: RegionUpdate,
+    ) -> Result<()> {
+        DbCrudAction::update::<Self, _>(ctx, dam, id, data).await?;
+        dam.region_cache().invalidate(id);
+
+        Ok(())
+    }
+
+    pub async fn search_by_name(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        query: &str,
+        limit: i64,
+    ) -> Result<Vec<Region>> {
+        DbCrudAction::search_by_name::<Self, _>(ctx, dam, query, limit).await
+    }
+
+    /// As `update`, but rejected with `StaleWrite` if the row has been updated
+    /// since the caller read `expected_updated_at`.
+    pub async fn update_if_unchanged(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        id: i64,
+        expected_updated_at: Option<i64>,
+        data: RegionUpdate,
+    ) -> Result<()> {
+        DbCrudAction::update_if_unchanged::<Self, _>(ctx, dam, id, expected_updated_at, data)
+            .await?;
+        dam.region_cache().invalidate(id);
+
+        Ok(())
+    }
+
+    /// Includes soft-deleted rows: for admin use only.
+    pub async fn read_all_including_deleted(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+    ) -> Result<Vec<Region>> {
+        DbCrudAction::read_all_including_deleted::<Self, _>(ctx, dam).await
+    }
+
+    /// Soft delete: the row is kept, but no longer returned by reads.
+    pub async fn delete(ctx: &RequestContext, dam: &DataAccessManager, id: i64) -> Result<()> {
+        DbCrudAction::delete::<Self>(ctx, dam, id).await?;
+        dam.region_cache().invalidate(id);
+
+        Ok(())
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils::initialise_test_environment;
+    use serial_test::serial;
+
+    fn rename(name: &str) -> RegionUpdate {
+        RegionUpdate {
+            name: Some(name.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_read_is_cached_until_a_write() -> anyhow::Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let data = RegionCreate {
+            name: "test_region_cache_before".to_string(),
+            description: None,
+        };
+        let id = Client::create(&ctx, &dam, data).await?;
+        assert_eq!(
+            Client::read(&ctx, &dam, id).await?.name,
+            "test_region_cache_before"
+        );
+
+        // Written behind the client's back, so the cached row is still returned,
+        // including through a clone of the manager.
+        DbCrudAction::update::<Client, _>(&ctx, &dam, id, rename("test_region_cache_hidden"))
+            .await?;
+        let clone = dam.clone();
+        assert_eq!(
+            Client::read(&ctx, &clone, id).await?.name,
+            "test_region_cache_before"
+        );
+
+        // Written through the client, so the entry is invalidated.
+        Client::update(&ctx, &clone, id, rename("test_region_cache_after")).await?;
+        assert_eq!(
+            Client::read(&ctx, &dam, id).await?.name,
+            "test_region_cache_after"
+        );
+
+        Client::delete(&ctx, &dam, id).await?;
+        assert!(Client::read(&ctx, &dam, id).await.is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+use crate::data_access::store::db::{DbCrudAction, DbCrudServer};
+use crate::data_access::{DataAccessManager, Result};
+use crate::RequestContext;
+use serde::{Deserialize, Serialize};
+use sqlb::Fields;
+use sqlx::FromRow;
+
+// -----------------------------------------------------------------------------
+// Types
+// -----------------------------------------------------------------------------
+
+/// Returned from the data access layer, hence `Serialize`.
+#[derive(Clone, Debug, Fields, FromRow, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Subregion {
+    pub id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    /// The region this is part of. `None` only for subregions that predate the link.
+    pub region_id: Option<i64>,
+    pub created_at: i64,
+    pub updated_at: Option<i64>,
+}
+
+/// Sent to the data access layer, hence `Deserialize`.
+#[derive(Fields, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SubregionCreate {
+    pub name: String,
+    pub description: Option<String>,
+    pub region_id: i64,
+}
+
+// -----------------------------------------------------------------------------
+// Client
+// -----------------------------------------------------------------------------
+
+pub struct Client;
+
+impl DbCrudServer for Client {
+    const TABLE: &'static str = "celestial_subregion";
+    const TIMESTAMPED: bool = true;
+    const SOFT_DELETE: bool = true;
+}
+
+impl Client {
+    pub async fn create(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        data: SubregionCreate,
+    ) -> Result<i64> {
+        let id = DbCrudAction::create::<Self, _>(ctx, dam, data).await?;
+        // NOTE: ids can be reused after a reset, so a stale entry may exist.
+        dam.subregion_cache().invalidate(id);
+
+        Ok(id)
+    }
+
+    pub async fn read(ctx: &RequestContext, dam: &DataAccessManager, id: i64) -> Result<Subregion> {
+        if let Some(cached) = dam.subregion_cache().get(id) {
+            return Ok(cached);
+        }
+
+        let entity: Subregion = DbCrudAction::read::<Self, _>(ctx, dam, id).await?;
+        dam.subregion_cache().insert(id, entity.clone());
+
+        Ok(entity)
+    }
+
+    pub async fn read_all(ctx: &RequestContext, dam: &DataAccessManager) -> Result<Vec<Subregion>> {
+        DbCrudAction::read_all::<Self, _>(ctx, dam).await
+    }
+
+    /// All subregions of the given region, ordered by id. A region with none (or
+    /// one that doesn't exist) returns an empty vec.
+    pub async fn read_by_region(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        region_id: i64,
+    ) -> Result<Vec<Subregion>> {
+        DbCrudAction::read_all_where::<Self, _, _>(ctx, dam, ("region_id", "=", region_id)).await
+    }
+
+    pub async fn read_page(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Subregion>> {
+        DbCrudAction::read_page::<Self, _>(ctx, dam, limit, offset).await
+    }
+
+    /// Includes soft-deleted rows: for admin use only.
+    pub async fn read_all_including_deleted(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+    ) -> Result<Vec<Subregion>> {
+        DbCrudAction::read_all_including_deleted::<Self, _>(ctx, dam).await
+    }
+
+    /// Soft delete: the row is kept, but no longer returned by reads.
+    pub async fn delete(ctx: &RequestContext, dam: &DataAccessManager, id: i64) -> Result<()> {
+        DbCrudAction::delete::<Self>(ctx, dam, id).await?;
+        dam.subregion_cache().invalidate(id);
+
+        Ok(())
+    }
+}
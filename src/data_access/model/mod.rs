@@ -1,2 +1,4 @@
 pub mod celestial_body;
+pub mod celestial_region;
+pub mod celestial_subregion;
 pub mod user;
@@ -1,5 +1,5 @@
-use crate::data_access::{DataAccessManager, DbCrudAction, DbCrudServer, Result};
-use crate::{security, RequestContext};
+use crate::data_access::{DataAccessManager, DbCrudAction, DbCrudServer, Error, Op, Result};
+use crate::{security, RequestContext, Role, UserId};
 use serde::{Deserialize, Serialize};
 use sqlb::{Fields, HasFields};
 use sqlx::sqlite::SqliteRow;
@@ -46,6 +46,8 @@ pub struct UserAuth {
     pub id: i64,
     pub username: String,
     pub token_salt: String,
+    /// Parsed into a `Role` when building a `RequestContext`.
+    pub role: String,
 }
 
 /// Marker trait. This, by itself, does very little, but it allows the `User`
@@ -91,7 +93,43 @@ impl Server {
         Ok(user)
     }
 
-    pub async fn update_password<E>(
+    /// Register a user. The row is inserted first, so the database generates its
+    /// salts, then the password is hashed with the new `pwd_salt`; both steps share
+    /// a transaction, so a user is never left without a password.
+    pub async fn create(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        data: UserCreate,
+    ) -> Result<i64> {
+        let UserCreate {
+            username,
+            pwd_clear,
+        } = data;
+        let mut tx = dam.begin().await?;
+
+        let id =
+            DbCrudAction::create_in_transaction::<Self, _>(ctx, &mut tx, UserInsert { username })
+                .await?;
+        let pwd_salt: String = sqlx::query_scalar("SELECT pwd_salt FROM user WHERE id = ?1")
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
+        let password = security::encrypt_password(&security::EncryptedContent {
+            content: pwd_clear,
+            salt: pwd_salt,
+        })?;
+        sqlx::query("UPDATE user SET pwd = ?1 WHERE id = ?2")
+            .bind(password)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        dam.publish(Self::TABLE, Op::Create, id);
+
+        Ok(id)
+    }
+
+    pub async fn update_password(
         ctx: &RequestContext,
         dam: &DataAccessManager,
         id: i64,
@@ -112,4 +150,129 @@ impl Server {
 
         Ok(())
     }
+
+    /// Replace the user's `token_salt` with a fresh random one. Tokens are signed
+    /// with the salt, so every token issued before this call stops validating.
+    pub async fn rotate_token_salt(
+        _ctx: &RequestContext,
+        dam: &DataAccessManager,
+        id: UserId,
+    ) -> Result<()> {
+        // NOTE: same generator as the column default, see the user migration.
+        let rows_affected =
+            sqlx::query("UPDATE user SET token_salt = lower(hex(randomblob(16))) WHERE id = ?1")
+                .bind(id.get())
+                .execute(dam.db_pool())
+                .await?
+                .rows_affected();
+        if rows_affected == 0 {
+            return Err(Error::EntityNotFound {
+                entity: Self::TABLE,
+                id: id.get(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Test helpers
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+impl Server {
+    /// Create a user with the given role, for tests that need to log in.
+    pub async fn create_for_test(
+        ctx: &RequestContext,
+        dam: &DataAccessManager,
+        username: &str,
+        cleartext_password: &str,
+        role: Role,
+    ) -> Result<i64> {
+        let data = UserCreate {
+            username: username.to_string(),
+            pwd_clear: cleartext_password.to_string(),
+        };
+        let id = Self::create(ctx, dam, data).await?;
+        sqlb::update()
+            .table(Self::TABLE)
+            .and_where("id", "=", id)
+            .data(vec![("role", role.as_str().to_string()).into()])
+            .exec(dam.db_pool())
+            .await?;
+
+        Ok(id)
+    }
+
+    /// Store `pwd` as the user's password hash as is, *eg* one from an older scheme.
+    pub async fn set_pwd_for_test(dam: &DataAccessManager, id: i64, pwd: &str) -> Result<()> {
+        sqlx::query("UPDATE user SET pwd = ?1 WHERE id = ?2")
+            .bind(pwd)
+            .bind(id)
+            .execute(dam.db_pool())
+            .await?;
+
+        Ok(())
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils::initialise_test_environment;
+    use anyhow::Result;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_rotate_token_salt_invalidates_tokens() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let id = Server::create_for_test(&ctx, &dam, "test_rotate_salt", "welcome", Role::Viewer)
+            .await?;
+        let before: UserAuth = Server::read(&ctx, &dam, id).await?;
+        let token = security::generate_web_token(&before.username, &before.token_salt)?;
+        security::validate_web_token(&token, &before.token_salt)?;
+
+        Server::rotate_token_salt(&ctx, &dam, UserId::new(id)?).await?;
+
+        let after: UserAuth = Server::read(&ctx, &dam, id).await?;
+        assert_ne!(after.token_salt, before.token_salt);
+        assert!(security::validate_web_token(&token, &after.token_salt).is_err());
+
+        // Unknown user.
+        let result = Server::rotate_token_salt(&ctx, &dam, UserId::new(i64::MAX)?).await;
+        assert!(matches!(result, Err(Error::EntityNotFound { .. })));
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_hashes_password() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let data = UserCreate {
+            username: "test_create_user".to_string(),
+            pwd_clear: "welcome".to_string(),
+        };
+
+        let id = Server::create(&ctx, &dam, data).await?;
+
+        let user: UserLogin = Server::read(&ctx, &dam, id).await?;
+        let pwd = user.pwd.unwrap();
+        assert_ne!(pwd, "welcome");
+        let content = security::EncryptedContent {
+            content: "welcome".to_string(),
+            salt: user.pwd_salt,
+        };
+        assert!(security::verify_password(&content, &pwd)?);
+
+        Ok(())
+    }
 }
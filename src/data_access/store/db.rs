@@ -1,23 +1,67 @@
 use crate::config::get_config;
-use crate::data_access::{DataAccessManager, Error, Result};
+use crate::data_access::{DataAccessManager, Error, Op, Result};
+use crate::generic_utils::now_utc;
 use crate::RequestContext;
+use futures_util::TryStreamExt;
+use serde_json::{Map, Value};
 use sqlb::HasFields;
+use sqlx::pool::PoolConnection;
 use sqlx::sqlite::{SqlitePoolOptions, SqliteRow};
-use sqlx::{FromRow, Pool, Sqlite};
-use std::time::Duration;
+use sqlx::{Column, Executor, FromRow, Pool, Row, Sqlite, Transaction, TypeInfo, ValueRef};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
 
 // -----------------------------------------------------------------------------
 // Sqlite database setup/connection handling
 // -----------------------------------------------------------------------------
 
 pub type DbPool = Pool<Sqlite>;
+pub type DbTransaction = Transaction<'static, Sqlite>;
 
-pub async fn create_database_pool() -> Result<DbPool> {
-    let connection_pool = SqlitePoolOptions::new()
-        .max_connections(get_config().DATABASE_POOL_MAX_CONNECTIONS)
+/// How many rows `DbCrudAction::stream_all` reads ahead of its consumer.
+const STREAM_BUFFER: usize = 64;
+
+/// The most distinct ids `DbCrudAction::read_many` and `delete_many` take at
+/// once, each being a bound parameter; well within SQLite's limit of 32766.
+pub const ID_BATCH_LIMIT: usize = 500;
+
+/// The alias `read_many` selects each row's id under, to put the rows in order
+/// whatever the fields of the entity.
+const READ_MANY_ID: &str = "_read_many_id";
+
+/// Run on every new connection. SQLite only enforces foreign keys when asked to,
+/// per connection; WAL lets readers proceed while a write is in progress; and the
+/// busy timeout makes a locked write wait rather than fail straight away.
+const CONNECTION_PRAGMAS: &str = "PRAGMA foreign_keys = ON; \
+                                  PRAGMA journal_mode = WAL; \
+                                  PRAGMA busy_timeout = 5000;";
+
+/// The pool sizing and timeouts from the config, plus the connection pragmas,
+/// shared by every pool constructor.
+pub(crate) fn configured_pool_options() -> SqlitePoolOptions {
+    let config = get_config();
+
+    SqlitePoolOptions::new()
+        .after_connect(|conn, _meta| {
+            Box::pin(async move {
+                conn.execute(CONNECTION_PRAGMAS).await?;
+                Ok(())
+            })
+        })
+        .max_connections(config.DATABASE_POOL_MAX_CONNECTIONS)
+        .min_connections(config.DATABASE_POOL_MIN_CONNECTIONS)
         .acquire_timeout(Duration::from_millis(
-            get_config().DATABASE_POOL_CONNECTION_TIMEOUT_MS,
+            config.DATABASE_POOL_CONNECTION_TIMEOUT_MS,
+        ))
+        .idle_timeout(Duration::from_secs(
+            config.DATABASE_POOL_IDLE_TIMEOUT_SECONDS,
         ))
+}
+
+pub async fn create_database_pool() -> Result<DbPool> {
+    let connection_pool = configured_pool_options()
         .connect(&get_config().DATABASE_URL)
         .await
         .map_err(|err| Error::FailedToCreatePool(err.to_string()));
@@ -31,8 +75,33 @@ pub async fn create_database_pool() -> Result<DbPool> {
 
 pub trait DbCrudServer {
     const TABLE: &'static str;
+    /// Whether the table has an `updated_at` column to stamp on every update.
+    const TIMESTAMPED: bool = false;
+    /// Whether the table has a `deleted_at` column. If so, `delete` stamps it rather
+    /// than removing the row, and the reads/counts skip rows where it is set.
+    const SOFT_DELETE: bool = false;
+}
+
+/// The column (and direction) to order a listing by.
+#[derive(Debug, Clone)]
+pub struct SortSpec {
+    pub column: String,
+    pub descending: bool,
+}
+
+impl Default for SortSpec {
+    fn default() -> Self {
+        Self {
+            column: "id".to_string(),
+            descending: false,
+        }
+    }
 }
 
+/// Each write made through here publishes an `EntityEvent` once it has been
+/// committed (see `DataAccessManager::subscribe`), except the `*_in_transaction`
+/// ones: only their caller knows whether the transaction is committed, so it
+/// publishes for each write once it has committed.
 pub struct DbCrudAction;
 
 impl DbCrudAction {
@@ -45,50 +114,659 @@ impl DbCrudAction {
         DBCS: DbCrudServer,
         E: HasFields,
     {
-        let (id,) = sqlb::insert()
-            .table(DBCS::TABLE)
-            .data(data.not_none_fields())
-            .returning(&["id"])
-            .fetch_one::<_, (i64,)>(dam.db_pool())
-            .await?;
+        let id = timed(DBCS::TABLE, "create", slow_query_threshold(), async {
+            create_with::<DBCS, _, _>(&mut *acquire(dam).await?, None, data).await
+        })
+        .await?;
+        dam.publish(DBCS::TABLE, Op::Create, id);
+
+        Ok(id)
+    }
+
+    /// As `create`, but with the given `id` rather than the next autoincremented
+    /// one, so that fixtures can pin their ids. An `id` already taken (including by
+    /// a soft-deleted row) is an `Error::DuplicateId`.
+    pub async fn create_with_id<DBCS, E>(
+        _ctx: &RequestContext,
+        dam: &DataAccessManager,
+        id: i64,
+        data: E,
+    ) -> Result<i64>
+    where
+        DBCS: DbCrudServer,
+        E: HasFields,
+    {
+        let id = timed(
+            DBCS::TABLE,
+            "create_with_id",
+            slow_query_threshold(),
+            async { create_with::<DBCS, _, _>(&mut *acquire(dam).await?, Some(id), data).await },
+        )
+        .await?;
+        dam.publish(DBCS::TABLE, Op::Create, id);
 
         Ok(id)
     }
 
+    pub async fn create_in_transaction<DBCS, E>(
+        _ctx: &RequestContext,
+        tx: &mut DbTransaction,
+        data: E,
+    ) -> Result<i64>
+    where
+        DBCS: DbCrudServer,
+        E: HasFields,
+    {
+        timed(
+            DBCS::TABLE,
+            "create_in_transaction",
+            slow_query_threshold(),
+            async { create_with::<DBCS, _, _>(&mut **tx, None, data).await },
+        )
+        .await
+    }
+
     pub async fn read<DBCS, E>(_ctx: &RequestContext, dam: &DataAccessManager, id: i64) -> Result<E>
     where
         DBCS: DbCrudServer,
         E: for<'r> FromRow<'r, SqliteRow> + Unpin + Send,
         E: HasFields,
     {
-        let entity: E = sqlb::select()
-            .table(DBCS::TABLE)
-            .columns(E::field_names())
-            .and_where("id", "=", id)
-            .fetch_optional(dam.db_pool())
-            .await?
-            .ok_or(Error::EntityNotFound {
-                entity: DBCS::TABLE,
-                id,
-            })?;
+        timed(DBCS::TABLE, "read", slow_query_threshold(), async {
+            let entity: E = read_optional_with::<DBCS, E, _>(&mut *acquire(dam).await?, id)
+                .await?
+                .ok_or(Error::EntityNotFound {
+                    entity: DBCS::TABLE,
+                    id,
+                })?;
 
-        Ok(entity)
+            Ok(entity)
+        })
+        .await
     }
 
-    pub async fn read_all<DBCS, E>(_ctx: &RequestContext, dam: &DataAccessManager) -> Result<Vec<E>>
+    /// As `read`, but a missing (or soft-deleted) entity is `None` rather than an
+    /// `EntityNotFound` error, for callers where absence is expected.
+    pub async fn read_optional<DBCS, E>(
+        _ctx: &RequestContext,
+        dam: &DataAccessManager,
+        id: i64,
+    ) -> Result<Option<E>>
     where
         DBCS: DbCrudServer,
         E: for<'r> FromRow<'r, SqliteRow> + Unpin + Send,
         E: HasFields,
     {
-        let entities: Vec<E> = sqlb::select()
-            .table(DBCS::TABLE)
-            .columns(E::field_names())
-            .order_by("id")
-            .fetch_all(dam.db_pool())
+        timed(
+            DBCS::TABLE,
+            "read_optional",
+            slow_query_threshold(),
+            async { read_optional_with::<DBCS, E, _>(&mut *acquire(dam).await?, id).await },
+        )
+        .await
+    }
+
+    /// As `read`, but only the given columns, as a JSON object keyed on column
+    /// name. Columns that are not fields of `E` are skipped with a warning (see
+    /// `unknown_columns`, to reject them instead); if none are left, every field
+    /// is read.
+    pub async fn read_columns<DBCS, E>(
+        _ctx: &RequestContext,
+        dam: &DataAccessManager,
+        id: i64,
+        columns: &[&str],
+    ) -> Result<Value>
+    where
+        DBCS: DbCrudServer,
+        E: HasFields,
+    {
+        let unknown = Self::unknown_columns::<E>(columns);
+        if !unknown.is_empty() {
+            tracing::warn!(table = DBCS::TABLE, ?unknown, "ignoring unknown columns");
+        }
+        let mut known: Vec<&str> = Vec::new();
+        for column in columns {
+            if E::field_names().contains(column) && !known.contains(column) {
+                known.push(*column);
+            }
+        }
+        if known.is_empty() {
+            known = E::field_names().to_vec();
+        }
+
+        timed(DBCS::TABLE, "read_columns", slow_query_threshold(), async {
+            let sql = format!(
+                "SELECT {} FROM {} {}",
+                known
+                    .iter()
+                    .map(|name| quote_identifier(name))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                DBCS::TABLE,
+                where_clause::<DBCS>(&["id = ?1"])
+            );
+            let row = sqlx::query(&sql)
+                .bind(id)
+                .fetch_optional(&mut *acquire(dam).await?)
+                .await?
+                .ok_or(Error::EntityNotFound {
+                    entity: DBCS::TABLE,
+                    id,
+                })?;
+
+            row_to_json(&row)
+        })
+        .await
+    }
+
+    /// Those of `columns` that are not fields of `E`, in the order given.
+    pub fn unknown_columns<E: HasFields>(columns: &[&str]) -> Vec<String> {
+        columns
+            .iter()
+            .filter(|column| !E::field_names().contains(*column))
+            .map(|column| column.to_string())
+            .collect()
+    }
+
+    pub async fn read_all<DBCS, E>(ctx: &RequestContext, dam: &DataAccessManager) -> Result<Vec<E>>
+    where
+        DBCS: DbCrudServer,
+        E: for<'r> FromRow<'r, SqliteRow> + Unpin + Send,
+        E: HasFields,
+    {
+        Self::read_all_sorted::<DBCS, E>(ctx, dam, SortSpec::default()).await
+    }
+
+    /// As `read_all`, but sent through a channel a row at a time as they are read,
+    /// so memory stays flat however large the table; for exports. A failure is
+    /// sent as the last item, and dropping the receiver stops the read.
+    ///
+    /// NOTE: a pooled connection is held until the stream ends, and the read is
+    ///       not `timed`, as its duration mostly depends on the consumer.
+    pub fn stream_all<DBCS, E>(
+        _ctx: &RequestContext,
+        dam: &DataAccessManager,
+    ) -> mpsc::Receiver<Result<E>>
+    where
+        DBCS: DbCrudServer,
+        E: for<'r> FromRow<'r, SqliteRow> + Unpin + Send + 'static,
+        E: HasFields,
+    {
+        let sql = format!(
+            "SELECT {} FROM {} {} ORDER BY id",
+            select_columns::<E>(),
+            DBCS::TABLE,
+            where_clause::<DBCS>(&[])
+        );
+        let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+        let dam = dam.clone();
+        tokio::spawn(async move {
+            let mut conn = match acquire(&dam).await {
+                Ok(conn) => conn,
+                Err(err) => {
+                    let _ = sender.send(Err(err)).await;
+                    return;
+                }
+            };
+            let mut rows = sqlx::query_as::<_, E>(&sql).fetch(&mut *conn);
+            loop {
+                let row = match rows.try_next().await {
+                    Ok(Some(entity)) => Ok(entity),
+                    Ok(None) => return,
+                    Err(err) => Err(Error::from(err)),
+                };
+                let failed = row.is_err();
+                // NOTE: a failed send means the receiver has been dropped.
+                if sender.send(row).await.is_err() || failed {
+                    return;
+                }
+            }
+        });
+
+        receiver
+    }
+
+    /// As `read_all`, ordered by `sort`. The column must be one of `E`'s fields;
+    /// anything else (including injection attempts) falls back to `id`.
+    pub async fn read_all_sorted<DBCS, E>(
+        _ctx: &RequestContext,
+        dam: &DataAccessManager,
+        sort: SortSpec,
+    ) -> Result<Vec<E>>
+    where
+        DBCS: DbCrudServer,
+        E: for<'r> FromRow<'r, SqliteRow> + Unpin + Send,
+        E: HasFields,
+    {
+        timed(
+            DBCS::TABLE,
+            "read_all_sorted",
+            slow_query_threshold(),
+            async {
+                let column = if E::field_names().contains(&sort.column.as_str()) {
+                    sort.column.as_str()
+                } else {
+                    "id"
+                };
+                let direction = if sort.descending { "DESC" } else { "ASC" };
+                let sql = format!(
+                    "SELECT {} FROM {} {} ORDER BY {} {direction}",
+                    select_columns::<E>(),
+                    DBCS::TABLE,
+                    where_clause::<DBCS>(&[]),
+                    quote_identifier(column)
+                );
+                let entities: Vec<E> = sqlx::query_as(&sql)
+                    .fetch_all(&mut *acquire(dam).await?)
+                    .await?;
+
+                Ok(entities)
+            },
+        )
+        .await
+    }
+
+    /// The rows with the given ids, in a single query, in the order the ids were
+    /// given. Repeated ids give the row once, at its first position, and ids with
+    /// no row are simply absent from the result. More than `ID_BATCH_LIMIT`
+    /// distinct ids is an `Error::TooManyIds`.
+    pub async fn read_many<DBCS, E>(
+        _ctx: &RequestContext,
+        dam: &DataAccessManager,
+        ids: &[i64],
+    ) -> Result<Vec<E>>
+    where
+        DBCS: DbCrudServer,
+        E: for<'r> FromRow<'r, SqliteRow> + Unpin + Send,
+        E: HasFields,
+    {
+        let mut seen = HashSet::new();
+        let ids: Vec<i64> = ids.iter().copied().filter(|id| seen.insert(*id)).collect();
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        if ids.len() > ID_BATCH_LIMIT {
+            return Err(Error::TooManyIds {
+                limit: ID_BATCH_LIMIT,
+            });
+        }
+
+        timed(DBCS::TABLE, "read_many", slow_query_threshold(), async {
+            let condition = id_in(ids.len());
+            let sql = format!(
+                "SELECT {}, id AS {READ_MANY_ID} FROM {} {}",
+                select_columns::<E>(),
+                DBCS::TABLE,
+                where_clause::<DBCS>(&[&condition])
+            );
+            let mut query = sqlx::query(&sql);
+            for id in &ids {
+                query = query.bind(*id);
+            }
+            let rows = query.fetch_all(&mut *acquire(dam).await?).await?;
+            let mut by_id = HashMap::with_capacity(rows.len());
+            for row in &rows {
+                by_id.insert(row.try_get::<i64, _>(READ_MANY_ID)?, E::from_row(row)?);
+            }
+
+            Ok(ids.iter().filter_map(|id| by_id.remove(id)).collect())
+        })
+        .await
+    }
+
+    /// The rows created or updated at or after `since` (a Unix timestamp), oldest
+    /// change first, for clients syncing incrementally. A row's change time is its
+    /// `updated_at`, or its `created_at` if it has never been updated.
+    ///
+    /// NOTE: soft-deleted rows are skipped like in every other read, so a sync
+    ///       client does not learn about deletions this way.
+    pub async fn read_updated_since<DBCS, E>(
+        _ctx: &RequestContext,
+        dam: &DataAccessManager,
+        since: i64,
+    ) -> Result<Vec<E>>
+    where
+        DBCS: DbCrudServer,
+        E: for<'r> FromRow<'r, SqliteRow> + Unpin + Send,
+        E: HasFields,
+    {
+        debug_assert!(
+            DBCS::TIMESTAMPED,
+            "{} has no updated_at column",
+            DBCS::TABLE
+        );
+
+        timed(
+            DBCS::TABLE,
+            "read_updated_since",
+            slow_query_threshold(),
+            async {
+                let sql = format!(
+                    "SELECT {} FROM {} {} ORDER BY COALESCE(updated_at, created_at), id",
+                    select_columns::<E>(),
+                    DBCS::TABLE,
+                    where_clause::<DBCS>(&[
+                        "(updated_at >= ?1 OR (updated_at IS NULL AND created_at >= ?1))"
+                    ])
+                );
+                let entities: Vec<E> = sqlx::query_as(&sql)
+                    .bind(since)
+                    .fetch_all(&mut *acquire(dam).await?)
+                    .await?;
+
+                Ok(entities)
+            },
+        )
+        .await
+    }
+
+    /// As `read_all`, but including soft-deleted rows. Intended for admin tooling
+    /// (audit, restore); callers are responsible for checking the role.
+    pub async fn read_all_including_deleted<DBCS, E>(
+        _ctx: &RequestContext,
+        dam: &DataAccessManager,
+    ) -> Result<Vec<E>>
+    where
+        DBCS: DbCrudServer,
+        E: for<'r> FromRow<'r, SqliteRow> + Unpin + Send,
+        E: HasFields,
+    {
+        timed(
+            DBCS::TABLE,
+            "read_all_including_deleted",
+            slow_query_threshold(),
+            async {
+                let sql = format!(
+                    "SELECT {} FROM {} ORDER BY id",
+                    select_columns::<E>(),
+                    DBCS::TABLE
+                );
+                let entities: Vec<E> = sqlx::query_as(&sql)
+                    .fetch_all(&mut *acquire(dam).await?)
+                    .await?;
+
+                Ok(entities)
+            },
+        )
+        .await
+    }
+
+    /// As `read_all`, restricted by a single `(column, operator, value)` condition.
+    /// Only comparison operators, and columns of `E`, are accepted (see `count_where`).
+    pub async fn read_all_where<DBCS, E, V>(
+        _ctx: &RequestContext,
+        dam: &DataAccessManager,
+        (column, operator, value): (&str, &str, V),
+    ) -> Result<Vec<E>>
+    where
+        DBCS: DbCrudServer,
+        E: for<'r> FromRow<'r, SqliteRow> + Unpin + Send,
+        E: HasFields,
+        V: for<'q> sqlx::Encode<'q, Sqlite> + sqlx::Type<Sqlite> + Send,
+    {
+        timed(
+            DBCS::TABLE,
+            "read_all_where",
+            slow_query_threshold(),
+            async {
+                if !COMPARISON_OPERATORS.contains(&operator) {
+                    return Err(Error::UnsupportedOperator(operator.to_string()));
+                }
+                check_column::<E>(column)?;
+
+                let condition = format!("{} {operator} ?1", quote_identifier(column));
+                let sql = format!(
+                    "SELECT {} FROM {} {} ORDER BY id",
+                    select_columns::<E>(),
+                    DBCS::TABLE,
+                    where_clause::<DBCS>(&[&condition])
+                );
+                let entities: Vec<E> = sqlx::query_as(&sql)
+                    .bind(value)
+                    .fetch_all(&mut *acquire(dam).await?)
+                    .await?;
+
+                Ok(entities)
+            },
+        )
+        .await
+    }
+
+    /// Rows whose `name` contains `query`, case-insensitively, up to `limit` rows.
+    /// `%` and `_` in the query are matched literally, not as wildcards.
+    pub async fn search_by_name<DBCS, E>(
+        _ctx: &RequestContext,
+        dam: &DataAccessManager,
+        query: &str,
+        limit: i64,
+    ) -> Result<Vec<E>>
+    where
+        DBCS: DbCrudServer,
+        E: for<'r> FromRow<'r, SqliteRow> + Unpin + Send,
+        E: HasFields,
+    {
+        timed(
+            DBCS::TABLE,
+            "search_by_name",
+            slow_query_threshold(),
+            async {
+                // NOTE: SQLite's `LIKE` is already case-insensitive (for ASCII), so no
+                //       `COLLATE NOCASE` is needed.
+                let sql = format!(
+                    "SELECT {} FROM {} {} ORDER BY id LIMIT ?2",
+                    select_columns::<E>(),
+                    DBCS::TABLE,
+                    where_clause::<DBCS>(&["name LIKE '%' || ?1 || '%' ESCAPE '\\'"])
+                );
+                let entities: Vec<E> = sqlx::query_as(&sql)
+                    .bind(escape_like(query))
+                    .bind(limit)
+                    .fetch_all(&mut *acquire(dam).await?)
+                    .await?;
+
+                Ok(entities)
+            },
+        )
+        .await
+    }
+
+    /// Candidates for a fuzzy match on `name`: rows whose name is within
+    /// `max_distance` characters of `query` in length, and shares its first or
+    /// last character, up to `limit` rows. A single typo cannot change both, so
+    /// this is a cheap prefetch to rank in Rust (see `levenshtein_within`).
+    pub async fn read_name_candidates<DBCS, E>(
+        _ctx: &RequestContext,
+        dam: &DataAccessManager,
+        query: &str,
+        max_distance: usize,
+        limit: i64,
+    ) -> Result<Vec<E>>
+    where
+        DBCS: DbCrudServer,
+        E: for<'r> FromRow<'r, SqliteRow> + Unpin + Send,
+        E: HasFields,
+    {
+        let (Some(first), Some(last)) = (query.chars().next(), query.chars().last()) else {
+            return Ok(Vec::new());
+        };
+        let length = query.chars().count() as i64;
+        let max_distance = max_distance as i64;
+
+        timed(
+            DBCS::TABLE,
+            "read_name_candidates",
+            slow_query_threshold(),
+            async {
+                let sql = format!(
+                    "SELECT {} FROM {} {} ORDER BY id LIMIT ?5",
+                    select_columns::<E>(),
+                    DBCS::TABLE,
+                    where_clause::<DBCS>(&[
+                        "length(name) BETWEEN ?1 AND ?2",
+                        "(name LIKE ?3 || '%' ESCAPE '\\' OR name LIKE '%' || ?4 ESCAPE '\\')"
+                    ])
+                );
+                let entities: Vec<E> = sqlx::query_as(&sql)
+                    .bind(length - max_distance)
+                    .bind(length + max_distance)
+                    .bind(escape_like(&first.to_string()))
+                    .bind(escape_like(&last.to_string()))
+                    .bind(limit)
+                    .fetch_all(&mut *acquire(dam).await?)
+                    .await?;
+
+                Ok(entities)
+            },
+        )
+        .await
+    }
+
+    pub async fn read_page<DBCS, E>(
+        _ctx: &RequestContext,
+        dam: &DataAccessManager,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<E>>
+    where
+        DBCS: DbCrudServer,
+        E: for<'r> FromRow<'r, SqliteRow> + Unpin + Send,
+        E: HasFields,
+    {
+        timed(DBCS::TABLE, "read_page", slow_query_threshold(), async {
+            let sql = format!(
+                "SELECT {} FROM {} {} ORDER BY id LIMIT ?1 OFFSET ?2",
+                select_columns::<E>(),
+                DBCS::TABLE,
+                where_clause::<DBCS>(&[])
+            );
+            let entities: Vec<E> = sqlx::query_as(&sql)
+                .bind(limit)
+                .bind(offset)
+                .fetch_all(&mut *acquire(dam).await?)
+                .await?;
+
+            Ok(entities)
+        })
+        .await
+    }
+
+    /// Keyset pagination: up to `limit` entities with an id greater than `after_id`,
+    /// in id order. Unlike `read_page`, rows inserted or deleted before the position
+    /// do not shift the page, and the cost does not grow with the position.
+    pub async fn read_after<DBCS, E>(
+        _ctx: &RequestContext,
+        dam: &DataAccessManager,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<E>>
+    where
+        DBCS: DbCrudServer,
+        E: for<'r> FromRow<'r, SqliteRow> + Unpin + Send,
+        E: HasFields,
+    {
+        timed(DBCS::TABLE, "read_after", slow_query_threshold(), async {
+            let sql = format!(
+                "SELECT {} FROM {} {} ORDER BY id LIMIT ?2",
+                select_columns::<E>(),
+                DBCS::TABLE,
+                where_clause::<DBCS>(&["id > ?1"])
+            );
+            let entities: Vec<E> = sqlx::query_as(&sql)
+                .bind(after_id)
+                .bind(limit)
+                .fetch_all(&mut *acquire(dam).await?)
+                .await?;
+
+            Ok(entities)
+        })
+        .await
+    }
+
+    /// Check for an entity without materialising it (or erroring when it's missing).
+    pub async fn exists<DBCS>(
+        _ctx: &RequestContext,
+        dam: &DataAccessManager,
+        id: i64,
+    ) -> Result<bool>
+    where
+        DBCS: DbCrudServer,
+    {
+        timed(DBCS::TABLE, "exists", slow_query_threshold(), async {
+            exists_with::<DBCS, _>(&mut *acquire(dam).await?, id).await
+        })
+        .await
+    }
+
+    pub async fn exists_in_transaction<DBCS>(
+        _ctx: &RequestContext,
+        tx: &mut DbTransaction,
+        id: i64,
+    ) -> Result<bool>
+    where
+        DBCS: DbCrudServer,
+    {
+        timed(
+            DBCS::TABLE,
+            "exists_in_transaction",
+            slow_query_threshold(),
+            async { exists_with::<DBCS, _>(&mut **tx, id).await },
+        )
+        .await
+    }
+
+    pub async fn count<DBCS>(_ctx: &RequestContext, dam: &DataAccessManager) -> Result<i64>
+    where
+        DBCS: DbCrudServer,
+    {
+        timed(DBCS::TABLE, "count", slow_query_threshold(), async {
+            // NOTE: the table name is a `const` on the server, never user input, so
+            //       formatting it into the statement is safe.
+            let count = sqlx::query_scalar(&format!(
+                "SELECT COUNT(*) FROM {} {}",
+                DBCS::TABLE,
+                where_clause::<DBCS>(&[])
+            ))
+            .fetch_one(&mut *acquire(dam).await?)
             .await?;
 
-        Ok(entities)
+            Ok(count)
+        })
+        .await
+    }
+
+    /// As `count`, restricted by a single `(column, operator, value)` condition,
+    /// *eg* `("name", "LIKE", "Mars%")`. Only comparison operators, and columns of
+    /// `E`, are accepted.
+    pub async fn count_where<DBCS, E, V>(
+        _ctx: &RequestContext,
+        dam: &DataAccessManager,
+        (column, operator, value): (&str, &str, V),
+    ) -> Result<i64>
+    where
+        DBCS: DbCrudServer,
+        E: HasFields,
+        V: for<'q> sqlx::Encode<'q, Sqlite> + sqlx::Type<Sqlite> + Send,
+    {
+        timed(DBCS::TABLE, "count_where", slow_query_threshold(), async {
+            if !COMPARISON_OPERATORS.contains(&operator) {
+                return Err(Error::UnsupportedOperator(operator.to_string()));
+            }
+            check_column::<E>(column)?;
+
+            let condition = format!("{} {operator} ?1", quote_identifier(column));
+            let sql = format!(
+                "SELECT COUNT(*) FROM {} {}",
+                DBCS::TABLE,
+                where_clause::<DBCS>(&[&condition])
+            );
+            let count = sqlx::query_scalar(&sql)
+                .bind(value)
+                .fetch_one(&mut *acquire(dam).await?)
+                .await?;
+
+            Ok(count)
+        })
+        .await
     }
 
     pub async fn update<DBCS, E>(
@@ -101,40 +779,1302 @@ impl DbCrudAction {
         DBCS: DbCrudServer,
         E: HasFields,
     {
-        let update_count = sqlb::update()
-            .table(DBCS::TABLE)
-            .and_where("id", "=", id)
-            .data(data.not_none_fields())
-            .exec(dam.db_pool())
-            .await?;
+        timed(DBCS::TABLE, "update", slow_query_threshold(), async {
+            update_with::<DBCS, _, _>(&mut *acquire(dam).await?, id, data).await
+        })
+        .await?;
+        dam.publish(DBCS::TABLE, Op::Update, id);
 
-        if update_count == 0 {
-            Err(Error::EntityNotFound {
-                entity: DBCS::TABLE,
-                id,
-            })
-        } else {
-            Ok(())
-        }
+        Ok(())
     }
 
-    pub async fn delete<DBCS>(_ctx: &RequestContext, dam: &DataAccessManager, id: i64) -> Result<()>
+    pub async fn update_in_transaction<DBCS, E>(
+        _ctx: &RequestContext,
+        tx: &mut DbTransaction,
+        id: i64,
+        data: E,
+    ) -> Result<()>
     where
         DBCS: DbCrudServer,
+        E: HasFields,
     {
-        let delete_count = sqlb::delete()
-            .table(DBCS::TABLE)
-            .and_where("id", "=", id)
-            .exec(dam.db_pool())
-            .await?;
+        timed(
+            DBCS::TABLE,
+            "update_in_transaction",
+            slow_query_threshold(),
+            async { update_with::<DBCS, _, _>(&mut **tx, id, data).await },
+        )
+        .await
+    }
 
-        if delete_count == 0 {
-            Err(Error::EntityNotFound {
-                entity: DBCS::TABLE,
-                id,
-            })
-        } else {
-            Ok(())
-        }
+    /// Compare-and-swap update: only applied if the row's `updated_at` still equals
+    /// `expected_updated_at` (`None` meaning "never updated"), otherwise `StaleWrite`.
+    /// The new stamp is always later than the old one, so two writes within the
+    /// same second cannot both match.
+    pub async fn update_if_unchanged<DBCS, E>(
+        _ctx: &RequestContext,
+        dam: &DataAccessManager,
+        id: i64,
+        expected_updated_at: Option<i64>,
+        data: E,
+    ) -> Result<()>
+    where
+        DBCS: DbCrudServer,
+        E: HasFields,
+    {
+        debug_assert!(
+            DBCS::TIMESTAMPED,
+            "{} has no updated_at column",
+            DBCS::TABLE
+        );
+
+        timed(
+            DBCS::TABLE,
+            "update_if_unchanged",
+            slow_query_threshold(),
+            async {
+                // NOTE: the check is part of the `UPDATE` itself, so no other write can
+                //       land between them. Only if nothing matched is the row read again,
+                //       to tell a missing row from a changed one.
+                let stamp = now_utc()
+                    .timestamp()
+                    .max(expected_updated_at.unwrap_or(0) + 1);
+                let mut fields = data.not_none_fields();
+                fields.push(("updated_at", stamp).into());
+                let mut update = sqlb::update()
+                    .table(DBCS::TABLE)
+                    .and_where("id", "=", id)
+                    .and_where("updated_at", "IS", expected_updated_at);
+                if DBCS::SOFT_DELETE {
+                    update = update.and_where("deleted_at", "IS", None::<i64>);
+                }
+                let update_count = update.data(fields).exec(&mut *acquire(dam).await?).await?;
+                if update_count > 0 {
+                    Ok(())
+                } else if exists_with::<DBCS, _>(&mut *acquire(dam).await?, id).await? {
+                    Err(Error::StaleWrite {
+                        entity: DBCS::TABLE,
+                        id,
+                    })
+                } else {
+                    Err(Error::EntityNotFound {
+                        entity: DBCS::TABLE,
+                        id,
+                    })
+                }
+            },
+        )
+        .await?;
+        dam.publish(DBCS::TABLE, Op::Update, id);
+
+        Ok(())
+    }
+
+    pub async fn delete<DBCS>(_ctx: &RequestContext, dam: &DataAccessManager, id: i64) -> Result<()>
+    where
+        DBCS: DbCrudServer,
+    {
+        timed(DBCS::TABLE, "delete", slow_query_threshold(), async {
+            delete_with::<DBCS, _>(&mut *acquire(dam).await?, id).await
+        })
+        .await?;
+        dam.publish(DBCS::TABLE, Op::Delete, id);
+
+        Ok(())
+    }
+
+    /// As `delete`, returning the row as it was just before it was deleted. A
+    /// client retrying a delete can then tell the delete that succeeded from the
+    /// retry, which gets `EntityNotFound` (as does a genuine miss).
+    pub async fn delete_returning<DBCS, E>(
+        _ctx: &RequestContext,
+        dam: &DataAccessManager,
+        id: i64,
+    ) -> Result<E>
+    where
+        DBCS: DbCrudServer,
+        E: for<'r> FromRow<'r, SqliteRow> + Unpin + Send,
+        E: HasFields,
+    {
+        let entity = timed(
+            DBCS::TABLE,
+            "delete_returning",
+            slow_query_threshold(),
+            async {
+                // NOTE: as in `delete_with`, an already-deleted row is not matched.
+                let sql = if DBCS::SOFT_DELETE {
+                    format!(
+                        "UPDATE {} SET deleted_at = strftime('%s', 'now') {} RETURNING {}",
+                        DBCS::TABLE,
+                        where_clause::<DBCS>(&["id = ?1"]),
+                        select_columns::<E>()
+                    )
+                } else {
+                    format!(
+                        "DELETE FROM {} WHERE id = ?1 RETURNING {}",
+                        DBCS::TABLE,
+                        select_columns::<E>()
+                    )
+                };
+                let entity: E = sqlx::query_as(&sql)
+                    .bind(id)
+                    .fetch_optional(&mut *acquire(dam).await?)
+                    .await?
+                    .ok_or(Error::EntityNotFound {
+                        entity: DBCS::TABLE,
+                        id,
+                    })?;
+
+                Ok(entity)
+            },
+        )
+        .await?;
+        dam.publish(DBCS::TABLE, Op::Delete, id);
+
+        Ok(entity)
+    }
+
+    /// Delete every row with one of the given ids, in a single statement, returning
+    /// how many were deleted. Ids with no row (or, for soft deletes, an already
+    /// deleted row) are not an error; they are just not counted. As with
+    /// `read_many`, more than `ID_BATCH_LIMIT` distinct ids is an
+    /// `Error::TooManyIds`.
+    pub async fn delete_many<DBCS>(
+        _ctx: &RequestContext,
+        dam: &DataAccessManager,
+        ids: &[i64],
+    ) -> Result<u64>
+    where
+        DBCS: DbCrudServer,
+    {
+        let mut seen = HashSet::new();
+        let ids: Vec<i64> = ids.iter().copied().filter(|id| seen.insert(*id)).collect();
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        if ids.len() > ID_BATCH_LIMIT {
+            return Err(Error::TooManyIds {
+                limit: ID_BATCH_LIMIT,
+            });
+        }
+
+        let deleted = timed(DBCS::TABLE, "delete_many", slow_query_threshold(), async {
+            let condition = id_in(ids.len());
+            let sql = if DBCS::SOFT_DELETE {
+                format!(
+                    "UPDATE {} SET deleted_at = strftime('%s', 'now') {} RETURNING id",
+                    DBCS::TABLE,
+                    where_clause::<DBCS>(&[&condition])
+                )
+            } else {
+                format!(
+                    "DELETE FROM {} {} RETURNING id",
+                    DBCS::TABLE,
+                    where_clause::<DBCS>(&[&condition])
+                )
+            };
+            let mut query = sqlx::query_scalar::<_, i64>(&sql);
+            for id in &ids {
+                query = query.bind(*id);
+            }
+
+            let mut tx = dam.begin().await?;
+            let deleted = query.fetch_all(&mut *tx).await?;
+            tx.commit().await?;
+
+            Ok(deleted)
+        })
+        .await?;
+        for id in &deleted {
+            dam.publish(DBCS::TABLE, Op::Delete, *id);
+        }
+
+        Ok(deleted.len() as u64)
+    }
+
+    pub async fn delete_in_transaction<DBCS>(
+        _ctx: &RequestContext,
+        tx: &mut DbTransaction,
+        id: i64,
+    ) -> Result<()>
+    where
+        DBCS: DbCrudServer,
+    {
+        timed(
+            DBCS::TABLE,
+            "delete_in_transaction",
+            slow_query_threshold(),
+            async { delete_with::<DBCS, _>(&mut **tx, id).await },
+        )
+        .await
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Raw SQL helpers
+// -----------------------------------------------------------------------------
+
+const COMPARISON_OPERATORS: [&str; 7] = ["=", "!=", "<", "<=", ">", ">=", "LIKE"];
+
+/// Quote a column name so it can be safely interpolated into a statement.
+pub(in crate::data_access) fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Escape the `LIKE` wildcards (and the escape character itself) in user input.
+fn escape_like(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for c in input.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+/// `Error::UnknownColumn` unless `column` is one of `E`'s fields.
+fn check_column<E: HasFields>(column: &str) -> Result<()> {
+    if !E::field_names().contains(&column) {
+        return Err(Error::UnknownColumn(column.to_string()));
+    }
+
+    Ok(())
+}
+
+/// The quoted, comma-separated column list for `E`.
+pub(in crate::data_access) fn select_columns<E: HasFields>() -> String {
+    E::field_names()
+        .iter()
+        .map(|name| quote_identifier(name))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A row as a JSON object, each value typed by what SQLite actually stored in it.
+fn row_to_json(row: &SqliteRow) -> Result<Value> {
+    let mut object = Map::new();
+    for column in row.columns() {
+        let index = column.ordinal();
+        let raw = row.try_get_raw(index)?;
+        let value = if raw.is_null() {
+            Value::Null
+        } else {
+            match raw.type_info().name() {
+                "INTEGER" => row.try_get::<i64, _>(index)?.into(),
+                "REAL" => row.try_get::<f64, _>(index)?.into(),
+                _ => row.try_get::<String, _>(index)?.into(),
+            }
+        };
+        object.insert(column.name().to_string(), value);
+    }
+
+    Ok(Value::Object(object))
+}
+
+/// `id IN (?1, ..., ?n)`, for binding `count` ids to.
+fn id_in(count: usize) -> String {
+    let placeholders = (1..=count)
+        .map(|index| format!("?{index}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!("id IN ({placeholders})")
+}
+
+/// Join `conditions` into a `WHERE` clause (or nothing, if there are none),
+/// adding the soft-delete filter for tables that use it.
+pub(in crate::data_access) fn where_clause<DBCS: DbCrudServer>(conditions: &[&str]) -> String {
+    let mut conditions = conditions.to_vec();
+    if DBCS::SOFT_DELETE {
+        conditions.push("deleted_at IS NULL");
+    }
+
+    if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Pool acquire retries
+// -----------------------------------------------------------------------------
+
+/// How long to wait before the first retry of a timed-out pool acquire. Each
+/// further retry waits twice as long as the one before.
+const ACQUIRE_RETRY_BASE_DELAY: Duration = Duration::from_millis(25);
+
+/// A connection from the pool, for a single query (see `with_retry`).
+pub(in crate::data_access) async fn acquire(
+    dam: &DataAccessManager,
+) -> Result<PoolConnection<Sqlite>> {
+    with_retry(|| dam.db_pool().acquire()).await
+}
+
+/// Run `acquire`, which takes a connection from the pool (*eg* `Pool::acquire`
+/// or `Pool::begin`), retrying with exponential backoff if the pool times out,
+/// up to `DATABASE_POOL_ACQUIRE_MAX_ATTEMPTS` attempts in all.
+///
+/// NOTE: only the acquire is retried, never the query itself, so a write cannot
+///       be applied twice; and any other error (*eg* a constraint violation) is
+///       returned straight away, as trying again would not change the outcome.
+pub(in crate::data_access) async fn with_retry<T, F, Fut>(acquire: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = core::result::Result<T, sqlx::Error>>,
+{
+    let max_attempts = get_config().DATABASE_POOL_ACQUIRE_MAX_ATTEMPTS;
+
+    retry_with_backoff(max_attempts, ACQUIRE_RETRY_BASE_DELAY, acquire).await
+}
+
+async fn retry_with_backoff<T, F, Fut>(
+    max_attempts: u32,
+    base_delay: Duration,
+    mut acquire: F,
+) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = core::result::Result<T, sqlx::Error>>,
+{
+    let mut attempt = 1;
+    loop {
+        match acquire().await {
+            Err(sqlx::Error::PoolTimedOut) if attempt < max_attempts => {
+                let delay = base_delay * 2u32.saturating_pow(attempt - 1);
+                let delay_ms = delay.as_millis() as u64;
+                tracing::warn!(attempt, delay_ms, "pool acquire timed out, retrying");
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            result => return result.map_err(Error::from),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Query timing
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+thread_local! {
+    // NOTE: per thread, as with the clock in `generic_utils`, so a test's threshold
+    //       cannot leak into tests running alongside.
+    static SLOW_QUERY_THRESHOLD: std::cell::Cell<Option<Duration>> = Default::default();
+}
+
+pub(in crate::data_access) fn slow_query_threshold() -> Duration {
+    #[cfg(test)]
+    if let Some(threshold) = SLOW_QUERY_THRESHOLD.with(|threshold| threshold.get()) {
+        return threshold;
+    }
+
+    Duration::from_millis(get_config().SLOW_QUERY_THRESHOLD_MS)
+}
+
+/// Use `threshold` rather than the configured one on this thread until the guard
+/// is dropped, when the previous threshold is restored.
+#[cfg(test)]
+fn set_slow_query_threshold(threshold: Duration) -> SlowQueryThresholdGuard {
+    let previous = SLOW_QUERY_THRESHOLD.with(|current| current.replace(Some(threshold)));
+
+    SlowQueryThresholdGuard { previous }
+}
+
+#[cfg(test)]
+struct SlowQueryThresholdGuard {
+    previous: Option<Duration>,
+}
+
+#[cfg(test)]
+impl Drop for SlowQueryThresholdGuard {
+    fn drop(&mut self) {
+        SLOW_QUERY_THRESHOLD.with(|current| current.set(self.previous));
+    }
+}
+
+/// Await `query`, logging how long it took: at `warn` if it exceeded `threshold`,
+/// otherwise at `debug`. The output is passed through untouched.
+pub(in crate::data_access) async fn timed<F: Future>(
+    table: &str,
+    operation: &str,
+    threshold: Duration,
+    query: F,
+) -> F::Output {
+    let started = Instant::now();
+    let output = query.await;
+    let elapsed = started.elapsed();
+    let elapsed_ms = elapsed.as_millis() as u64;
+
+    if elapsed > threshold {
+        tracing::warn!(table, operation, elapsed_ms, "slow query");
+    } else {
+        tracing::debug!(table, operation, elapsed_ms, "query");
+    }
+
+    output
+}
+
+// -----------------------------------------------------------------------------
+// Executor-generic implementations
+//
+// The write methods on `DbCrudAction` run either on a pooled connection or inside
+// a caller-owned transaction; both variants delegate to these.
+// -----------------------------------------------------------------------------
+
+async fn create_with<'e, DBCS, E, X>(db: X, id: Option<i64>, data: E) -> Result<i64>
+where
+    DBCS: DbCrudServer,
+    E: HasFields,
+    X: Executor<'e, Database = Sqlite>,
+{
+    let mut fields = data.not_none_fields();
+    if let Some(id) = id {
+        fields.push(("id", id).into());
+    }
+    let created = sqlb::insert()
+        .table(DBCS::TABLE)
+        .data(fields)
+        .returning(&["id"])
+        .fetch_one::<_, (i64,)>(db)
+        .await;
+
+    match (created, id) {
+        (Ok((id,)), _) => Ok(id),
+        // NOTE: SQLite reports a taken primary key as "UNIQUE constraint failed:
+        //       <table>.id"; any other unique column is left as a `UniqueViolation`.
+        (Err(sqlx::Error::Database(db_err)), Some(id))
+            if db_err.is_unique_violation()
+                && db_err.message().ends_with(&format!("{}.id", DBCS::TABLE)) =>
+        {
+            Err(Error::DuplicateId {
+                entity: DBCS::TABLE,
+                id,
+            })
+        }
+        (Err(err), _) => Err(err.into()),
+    }
+}
+
+async fn read_optional_with<'e, DBCS, E, X>(db: X, id: i64) -> Result<Option<E>>
+where
+    DBCS: DbCrudServer,
+    E: for<'r> FromRow<'r, SqliteRow> + Unpin + Send,
+    E: HasFields,
+    X: Executor<'e, Database = Sqlite>,
+{
+    let sql = format!(
+        "SELECT {} FROM {} {}",
+        select_columns::<E>(),
+        DBCS::TABLE,
+        where_clause::<DBCS>(&["id = ?1"])
+    );
+    let entity: Option<E> = sqlx::query_as(&sql).bind(id).fetch_optional(db).await?;
+
+    Ok(entity)
+}
+
+async fn exists_with<'e, DBCS, X>(db: X, id: i64) -> Result<bool>
+where
+    DBCS: DbCrudServer,
+    X: Executor<'e, Database = Sqlite>,
+{
+    let found: Option<i64> = sqlx::query_scalar(&format!(
+        "SELECT 1 FROM {} {} LIMIT 1",
+        DBCS::TABLE,
+        where_clause::<DBCS>(&["id = ?1"])
+    ))
+    .bind(id)
+    .fetch_optional(db)
+    .await?;
+
+    Ok(found.is_some())
+}
+
+async fn update_with<'e, DBCS, E, X>(db: X, id: i64, data: E) -> Result<()>
+where
+    DBCS: DbCrudServer,
+    E: HasFields,
+    X: Executor<'e, Database = Sqlite>,
+{
+    let mut fields = data.not_none_fields();
+    if DBCS::TIMESTAMPED {
+        fields.push(("updated_at", now_utc().timestamp()).into());
+    }
+
+    // NOTE: with nothing to set there is no statement to run, but the row must
+    //       still exist.
+    if fields.is_empty() {
+        return if exists_with::<DBCS, _>(db, id).await? {
+            Ok(())
+        } else {
+            Err(Error::EntityNotFound {
+                entity: DBCS::TABLE,
+                id,
+            })
+        };
+    }
+    // NOTE: as with `delete_with`, a soft-deleted row is not matched, so it is
+    //       reported as missing.
+    let mut update = sqlb::update().table(DBCS::TABLE).and_where("id", "=", id);
+    if DBCS::SOFT_DELETE {
+        update = update.and_where("deleted_at", "IS", None::<i64>);
+    }
+    let update_count = update.data(fields).exec(db).await?;
+
+    if update_count == 0 {
+        Err(Error::EntityNotFound {
+            entity: DBCS::TABLE,
+            id,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+async fn delete_with<'e, DBCS, X>(db: X, id: i64) -> Result<()>
+where
+    DBCS: DbCrudServer,
+    X: Executor<'e, Database = Sqlite>,
+{
+    let delete_count = if DBCS::SOFT_DELETE {
+        // NOTE: an already-deleted row is not matched, so it is reported as missing.
+        sqlx::query(&format!(
+            "UPDATE {} SET deleted_at = strftime('%s', 'now') {}",
+            DBCS::TABLE,
+            where_clause::<DBCS>(&["id = ?1"])
+        ))
+        .bind(id)
+        .execute(db)
+        .await?
+        .rows_affected()
+    } else {
+        sqlb::delete()
+            .table(DBCS::TABLE)
+            .and_where("id", "=", id)
+            .exec(db)
+            .await?
+    };
+
+    if delete_count == 0 {
+        Err(Error::EntityNotFound {
+            entity: DBCS::TABLE,
+            id,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils::{body_create, initialise_test_environment, CapturedLogs};
+    use crate::data_access::model::celestial_body::{self, CelestialBody, CelestialBodyCreate};
+    use crate::data_access::EntityEvent;
+    use anyhow::Result;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_transaction_rolls_back_on_failure() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+
+        let mut tx = dam.begin().await?;
+        let batch = [
+            "test_tx_rollback_a",
+            "test_tx_rollback_b",
+            "test_tx_rollback_a",
+        ];
+        let mut outcome = Ok(0);
+        for name in batch {
+            outcome = DbCrudAction::create_in_transaction::<celestial_body::Client, _>(
+                &ctx,
+                &mut tx,
+                body_create(name),
+            )
+            .await;
+            if outcome.is_err() {
+                break;
+            }
+        }
+        assert!(outcome.is_err());
+        tx.rollback().await?;
+
+        let bodies: Vec<CelestialBody> =
+            DbCrudAction::read_all::<celestial_body::Client, _>(&ctx, &dam).await?;
+        assert!(!bodies
+            .iter()
+            .any(|body| body.name.starts_with("test_tx_rollback")));
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_transaction_commit_persists() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+
+        let mut tx = dam.begin().await?;
+        let id = DbCrudAction::create_in_transaction::<celestial_body::Client, _>(
+            &ctx,
+            &mut tx,
+            body_create("test_tx_commit"),
+        )
+        .await?;
+        tx.commit().await?;
+
+        let body: CelestialBody =
+            DbCrudAction::read::<celestial_body::Client, _>(&ctx, &dam, id).await?;
+        assert_eq!(body.name, "test_tx_commit");
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_count_and_count_where() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let before = DbCrudAction::count::<celestial_body::Client>(&ctx, &dam).await?;
+
+        for name in ["test_count_a", "test_count_b", "test_count_c"] {
+            DbCrudAction::create::<celestial_body::Client, _>(&ctx, &dam, body_create(name))
+                .await?;
+        }
+
+        let after = DbCrudAction::count::<celestial_body::Client>(&ctx, &dam).await?;
+        assert_eq!(after, before + 3);
+
+        let filtered = DbCrudAction::count_where::<celestial_body::Client, CelestialBody, _>(
+            &ctx,
+            &dam,
+            ("name", "LIKE", "test_count_%"),
+        )
+        .await?;
+        assert_eq!(filtered, 3);
+
+        let rejected = DbCrudAction::count_where::<celestial_body::Client, CelestialBody, _>(
+            &ctx,
+            &dam,
+            ("name", "; DROP TABLE", "x"),
+        )
+        .await;
+        assert!(matches!(rejected, Err(Error::UnsupportedOperator(_))));
+
+        let unknown = DbCrudAction::count_where::<celestial_body::Client, CelestialBody, _>(
+            &ctx,
+            &dam,
+            ("colour", "=", "red"),
+        )
+        .await;
+        assert!(matches!(unknown, Err(Error::UnknownColumn(column)) if column == "colour"));
+        let unknown = DbCrudAction::read_all_where::<celestial_body::Client, CelestialBody, _>(
+            &ctx,
+            &dam,
+            ("colour", "=", "red"),
+        )
+        .await;
+        assert!(matches!(unknown, Err(Error::UnknownColumn(column)) if column == "colour"));
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_soft_delete_lifecycle() -> Result<()> {
+        use crate::data_access::model::celestial_region::{self, Region, RegionCreate};
+
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let data = RegionCreate {
+            name: "test_soft_delete_lifecycle".to_string(),
+            description: None,
+        };
+        let id = DbCrudAction::create::<celestial_region::Client, _>(&ctx, &dam, data).await?;
+        let before = DbCrudAction::count::<celestial_region::Client>(&ctx, &dam).await?;
+
+        DbCrudAction::delete::<celestial_region::Client>(&ctx, &dam, id).await?;
+
+        let read = DbCrudAction::read::<celestial_region::Client, Region>(&ctx, &dam, id).await;
+        assert!(matches!(read, Err(Error::EntityNotFound { .. })));
+        assert!(!DbCrudAction::exists::<celestial_region::Client>(&ctx, &dam, id).await?);
+        assert_eq!(
+            DbCrudAction::count::<celestial_region::Client>(&ctx, &dam).await?,
+            before - 1
+        );
+        let live: Vec<Region> =
+            DbCrudAction::read_all::<celestial_region::Client, _>(&ctx, &dam).await?;
+        assert!(!live.iter().any(|region| region.id == id));
+        let all: Vec<Region> =
+            DbCrudAction::read_all_including_deleted::<celestial_region::Client, _>(&ctx, &dam)
+                .await?;
+        assert!(all.iter().any(|region| region.id == id));
+
+        let again = DbCrudAction::delete::<celestial_region::Client>(&ctx, &dam, id).await;
+        assert!(matches!(again, Err(Error::EntityNotFound { .. })));
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_update_skips_soft_deleted_rows() -> Result<()> {
+        use crate::data_access::model::celestial_region::{
+            self, Region, RegionCreate, RegionUpdate,
+        };
+
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let data = RegionCreate {
+            name: "test_update_skips_soft_deleted".to_string(),
+            description: None,
+        };
+        let id = DbCrudAction::create::<celestial_region::Client, _>(&ctx, &dam, data).await?;
+        DbCrudAction::delete::<celestial_region::Client>(&ctx, &dam, id).await?;
+        let mut events = dam.subscribe();
+
+        let update = || RegionUpdate {
+            name: None,
+            description: Some("Changed after deletion.".to_string()),
+        };
+        let updated =
+            DbCrudAction::update::<celestial_region::Client, _>(&ctx, &dam, id, update()).await;
+        assert!(matches!(updated, Err(Error::EntityNotFound { .. })));
+        let mut tx = dam.begin().await?;
+        let updated = DbCrudAction::update_in_transaction::<celestial_region::Client, _>(
+            &ctx,
+            &mut tx,
+            id,
+            update(),
+        )
+        .await;
+        assert!(matches!(updated, Err(Error::EntityNotFound { .. })));
+        drop(tx);
+
+        // The row is untouched, and nobody was told it changed.
+        let all: Vec<Region> =
+            DbCrudAction::read_all_including_deleted::<celestial_region::Client, _>(&ctx, &dam)
+                .await?;
+        let region = all.iter().find(|region| region.id == id).unwrap();
+        assert_eq!(region.description, None);
+        assert!(events.try_recv().is_err());
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_update_if_unchanged_rejects_stale_writes() -> Result<()> {
+        use crate::data_access::model::celestial_region::{
+            self, Region, RegionCreate, RegionUpdate,
+        };
+
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let data = RegionCreate {
+            name: "test_cas_before".to_string(),
+            description: None,
+        };
+        let id = DbCrudAction::create::<celestial_region::Client, _>(&ctx, &dam, data).await?;
+        let rename = |name: &str| RegionUpdate {
+            name: Some(name.to_string()),
+            ..Default::default()
+        };
+
+        // Both "clients" read the never-updated row; only the first write wins.
+        DbCrudAction::update_if_unchanged::<celestial_region::Client, _>(
+            &ctx,
+            &dam,
+            id,
+            None,
+            rename("test_cas_first"),
+        )
+        .await?;
+        let stale = DbCrudAction::update_if_unchanged::<celestial_region::Client, _>(
+            &ctx,
+            &dam,
+            id,
+            None,
+            rename("test_cas_second"),
+        )
+        .await;
+        assert!(matches!(stale, Err(Error::StaleWrite { .. })));
+
+        let region: Region =
+            DbCrudAction::read::<celestial_region::Client, _>(&ctx, &dam, id).await?;
+        assert_eq!(region.name, "test_cas_first");
+        DbCrudAction::update_if_unchanged::<celestial_region::Client, _>(
+            &ctx,
+            &dam,
+            id,
+            region.updated_at,
+            rename("test_cas_third"),
+        )
+        .await?;
+
+        // A deleted row is missing, whatever the expected stamp.
+        let region: Region =
+            DbCrudAction::read::<celestial_region::Client, _>(&ctx, &dam, id).await?;
+        DbCrudAction::delete::<celestial_region::Client>(&ctx, &dam, id).await?;
+        let deleted = DbCrudAction::update_if_unchanged::<celestial_region::Client, _>(
+            &ctx,
+            &dam,
+            id,
+            region.updated_at,
+            rename("test_cas_deleted"),
+        )
+        .await;
+        assert!(matches!(deleted, Err(Error::EntityNotFound { .. })));
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_slow_query_is_logged_at_warn() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        // Any query at all takes longer than a zero threshold.
+        let _threshold = set_slow_query_threshold(Duration::ZERO);
+
+        DbCrudAction::count::<celestial_body::Client>(&ctx, &dam).await?;
+
+        let output = logs.contents();
+        let warning = output
+            .lines()
+            .find(|line| line.contains("WARN"))
+            .expect("no slow query warning was logged");
+        assert!(warning.contains("table=\"celestial_body\""));
+        assert!(warning.contains("operation=\"count\""));
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_foreign_keys_are_enforced() -> Result<()> {
+        use crate::data_access::model::celestial_region::{self, RegionCreate};
+
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let enabled: bool = sqlx::query_scalar("PRAGMA foreign_keys")
+            .fetch_one(dam.db_pool())
+            .await?;
+        assert!(enabled);
+
+        let data = RegionCreate {
+            name: "test_foreign_keys_region".to_string(),
+            description: None,
+        };
+        let region = DbCrudAction::create::<celestial_region::Client, _>(&ctx, &dam, data).await?;
+        let data = CelestialBodyCreate {
+            region: Some(region),
+            ..body_create("test_foreign_keys_body")
+        };
+        DbCrudAction::create::<celestial_body::Client, _>(&ctx, &dam, data).await?;
+
+        // NOTE: `delete` only stamps `deleted_at`, so a hard delete is needed here.
+        let deleted = sqlx::query("DELETE FROM celestial_region WHERE id = ?1")
+            .bind(region)
+            .execute(dam.db_pool())
+            .await
+            .map_err(Error::from);
+        assert!(matches!(deleted, Err(Error::ForeignKeyViolation(_))));
+
+        let data = CelestialBodyCreate {
+            region: Some(999_999),
+            ..body_create("test_foreign_keys_dangling")
+        };
+        let created = DbCrudAction::create::<celestial_body::Client, _>(&ctx, &dam, data).await;
+        assert!(matches!(created, Err(Error::ForeignKeyViolation(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_escape_like() {
+        assert_eq!(escape_like("Mars"), "Mars");
+        assert_eq!(escape_like("100%_sure"), "100\\%\\_sure");
+        assert_eq!(escape_like("a\\b"), "a\\\\b");
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_read_optional() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let id = DbCrudAction::create::<celestial_body::Client, _>(
+            &ctx,
+            &dam,
+            body_create("test_read_optional"),
+        )
+        .await?;
+
+        let present: Option<CelestialBody> =
+            DbCrudAction::read_optional::<celestial_body::Client, _>(&ctx, &dam, id).await?;
+        assert_eq!(
+            present.map(|body| body.name).as_deref(),
+            Some("test_read_optional")
+        );
+
+        DbCrudAction::delete::<celestial_body::Client>(&ctx, &dam, id).await?;
+        let absent: Option<CelestialBody> =
+            DbCrudAction::read_optional::<celestial_body::Client, _>(&ctx, &dam, id).await?;
+        assert!(absent.is_none());
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_delete_returning() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let id = DbCrudAction::create::<celestial_body::Client, _>(
+            &ctx,
+            &dam,
+            body_create("test_delete_returning"),
+        )
+        .await?;
+
+        let deleted: CelestialBody =
+            DbCrudAction::delete_returning::<celestial_body::Client, _>(&ctx, &dam, id).await?;
+        assert_eq!(deleted.id, id);
+        assert_eq!(deleted.name, "test_delete_returning");
+        let read =
+            DbCrudAction::read::<celestial_body::Client, CelestialBody>(&ctx, &dam, id).await;
+        assert!(matches!(read, Err(Error::EntityNotFound { .. })));
+
+        // Retried, or never there: either way, a miss.
+        for id in [id, 999_999] {
+            let missing = DbCrudAction::delete_returning::<celestial_body::Client, CelestialBody>(
+                &ctx, &dam, id,
+            )
+            .await;
+            assert!(matches!(missing, Err(Error::EntityNotFound { .. })), "{id}");
+        }
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_with_explicit_and_auto_ids() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let pinned = 90_001;
+
+        let id = DbCrudAction::create_with_id::<celestial_body::Client, _>(
+            &ctx,
+            &dam,
+            pinned,
+            body_create("test_create_with_id"),
+        )
+        .await?;
+        assert_eq!(id, pinned);
+        let read =
+            DbCrudAction::read::<celestial_body::Client, CelestialBody>(&ctx, &dam, pinned).await?;
+        assert_eq!(read.name, "test_create_with_id");
+
+        // Without an id, the next one is still picked by SQLite: past any pinned.
+        let auto = DbCrudAction::create::<celestial_body::Client, _>(
+            &ctx,
+            &dam,
+            body_create("test_create_with_auto_id"),
+        )
+        .await?;
+        assert!(auto > pinned);
+
+        let duplicate = DbCrudAction::create_with_id::<celestial_body::Client, _>(
+            &ctx,
+            &dam,
+            pinned,
+            body_create("test_create_with_duplicate_id"),
+        )
+        .await;
+        assert!(matches!(duplicate, Err(Error::DuplicateId { id, .. }) if id == pinned));
+        // A clash on another unique column is not mistaken for one on the id.
+        let same_name = DbCrudAction::create_with_id::<celestial_body::Client, _>(
+            &ctx,
+            &dam,
+            pinned + 1_000,
+            body_create("test_create_with_id"),
+        )
+        .await;
+        assert!(matches!(same_name, Err(Error::UniqueViolation(_))));
+
+        for id in [pinned, auto] {
+            DbCrudAction::delete::<celestial_body::Client>(&ctx, &dam, id).await?;
+        }
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_read_many_keeps_order_and_skips_missing() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let mut ids = Vec::new();
+        for name in ["test_read_many_a", "test_read_many_b", "test_read_many_c"] {
+            let id =
+                DbCrudAction::create::<celestial_body::Client, _>(&ctx, &dam, body_create(name))
+                    .await?;
+            ids.push(id);
+        }
+        let (a, b, c) = (ids[0], ids[1], ids[2]);
+
+        // Out of id order, with a repeat and an id that has no row.
+        let bodies = celestial_body::Client::read_many(&ctx, &dam, &[c, 999_999, a, c, b]).await?;
+        let read: Vec<i64> = bodies.iter().map(|body| body.id).collect();
+        assert_eq!(read, [c, a, b]);
+        assert_eq!(bodies[0].name, "test_read_many_c");
+
+        assert!(celestial_body::Client::read_many(&ctx, &dam, &[999_999])
+            .await?
+            .is_empty());
+        let too_many: Vec<i64> = (1..=ID_BATCH_LIMIT as i64 + 1).collect();
+        let result = celestial_body::Client::read_many(&ctx, &dam, &too_many).await;
+        assert!(matches!(
+            result,
+            Err(Error::TooManyIds {
+                limit: ID_BATCH_LIMIT
+            })
+        ));
+        // Repeats do not count towards the limit.
+        let repeated = vec![a; ID_BATCH_LIMIT + 1];
+        assert_eq!(
+            celestial_body::Client::read_many(&ctx, &dam, &repeated)
+                .await?
+                .len(),
+            1
+        );
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_writes_publish_events() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let mut events = dam.subscribe();
+        // A subscriber that has gone away does not get in the way of writes.
+        drop(dam.subscribe());
+
+        let id = DbCrudAction::create::<celestial_body::Client, _>(
+            &ctx,
+            &dam,
+            body_create("test_writes_publish_events"),
+        )
+        .await?;
+        DbCrudAction::delete::<celestial_body::Client>(&ctx, &dam, id).await?;
+        // A failed write publishes nothing.
+        let missing = DbCrudAction::delete::<celestial_body::Client>(&ctx, &dam, id).await;
+        assert!(matches!(missing, Err(Error::EntityNotFound { .. })));
+
+        let table = celestial_body::Client::TABLE;
+        assert_eq!(
+            events.recv().await?,
+            EntityEvent {
+                table,
+                op: Op::Create,
+                id
+            }
+        );
+        assert_eq!(
+            events.recv().await?,
+            EntityEvent {
+                table,
+                op: Op::Delete,
+                id
+            }
+        );
+        assert!(events.try_recv().is_err());
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_read_all_sorted() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        for name in ["test_sorted_b", "test_sorted_a"] {
+            DbCrudAction::create::<celestial_body::Client, _>(&ctx, &dam, body_create(name))
+                .await?;
+        }
+        let names = |bodies: Vec<CelestialBody>| -> Vec<String> {
+            bodies
+                .into_iter()
+                .map(|body| body.name)
+                .filter(|name| name.starts_with("test_sorted_"))
+                .collect()
+        };
+
+        let sort = SortSpec {
+            column: "name".to_string(),
+            descending: true,
+        };
+        let bodies =
+            DbCrudAction::read_all_sorted::<celestial_body::Client, _>(&ctx, &dam, sort).await?;
+        assert_eq!(names(bodies), ["test_sorted_b", "test_sorted_a"]);
+
+        // Unknown columns fall back to `id`, so insertion order.
+        let sort = SortSpec {
+            column: "name; DROP TABLE celestial_body".to_string(),
+            descending: false,
+        };
+        let bodies =
+            DbCrudAction::read_all_sorted::<celestial_body::Client, _>(&ctx, &dam, sort).await?;
+        assert_eq!(names(bodies), ["test_sorted_b", "test_sorted_a"]);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_delete_many() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let mut ids = Vec::new();
+        for name in ["test_delete_many_a", "test_delete_many_b"] {
+            let id =
+                DbCrudAction::create::<celestial_body::Client, _>(&ctx, &dam, body_create(name))
+                    .await?;
+            ids.push(id);
+        }
+        let before = DbCrudAction::count::<celestial_body::Client>(&ctx, &dam).await?;
+
+        // An id with no row is skipped, not an error.
+        let with_missing = [ids[0], ids[1], ids[1] + 1_000];
+        let deleted =
+            DbCrudAction::delete_many::<celestial_body::Client>(&ctx, &dam, &with_missing).await?;
+        assert_eq!(deleted, 2);
+        let after = DbCrudAction::count::<celestial_body::Client>(&ctx, &dam).await?;
+        assert_eq!(after, before - 2);
+
+        // Already (soft) deleted, so nothing left to delete.
+        let deleted = DbCrudAction::delete_many::<celestial_body::Client>(&ctx, &dam, &ids).await?;
+        assert_eq!(deleted, 0);
+        assert_eq!(
+            DbCrudAction::delete_many::<celestial_body::Client>(&ctx, &dam, &[]).await?,
+            0
+        );
+        let too_many: Vec<i64> = (1..=ID_BATCH_LIMIT as i64 + 1).collect();
+        assert!(matches!(
+            DbCrudAction::delete_many::<celestial_body::Client>(&ctx, &dam, &too_many).await,
+            Err(Error::TooManyIds {
+                limit: ID_BATCH_LIMIT
+            })
+        ));
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_read_updated_since() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let mut ids = Vec::new();
+        for name in ["test_since_old", "test_since_updated", "test_since_created"] {
+            let id =
+                DbCrudAction::create::<celestial_body::Client, _>(&ctx, &dam, body_create(name))
+                    .await?;
+            ids.push(id);
+        }
+        // Far enough in the future that no other test's rows are included.
+        let stamp = |id: i64, created_at: i64, updated_at: Option<i64>| {
+            sqlx::query("UPDATE celestial_body SET created_at = ?1, updated_at = ?2 WHERE id = ?3")
+                .bind(created_at)
+                .bind(updated_at)
+                .bind(id)
+                .execute(dam.db_pool())
+        };
+        stamp(ids[0], 4_000_000_000, None).await?;
+        stamp(ids[1], 4_000_000_000, Some(4_000_000_100)).await?;
+        stamp(ids[2], 4_000_000_200, None).await?;
+
+        let bodies: Vec<CelestialBody> = DbCrudAction::read_updated_since::<
+            celestial_body::Client,
+            _,
+        >(&ctx, &dam, 4_000_000_050)
+        .await?;
+        let names: Vec<_> = bodies.iter().map(|body| body.name.as_str()).collect();
+        assert_eq!(names, ["test_since_updated", "test_since_created"]);
+
+        DbCrudAction::delete_many::<celestial_body::Client>(&ctx, &dam, &ids).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pool_acquire_is_retried_under_contention() -> Result<()> {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .acquire_timeout(Duration::from_millis(50))
+            .connect("sqlite::memory:")
+            .await?;
+        // Hold the only connection for long enough that the first attempts time out.
+        let held = pool.acquire().await?;
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(150)).await;
+            drop(held);
+        });
+
+        let mut attempts = 0;
+        let mut conn = retry_with_backoff(5, Duration::from_millis(20), || {
+            attempts += 1;
+            pool.acquire()
+        })
+        .await?;
+        assert!(attempts > 1, "{attempts}");
+        let one: i64 = sqlx::query_scalar("SELECT 1").fetch_one(&mut *conn).await?;
+        assert_eq!(one, 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_only_pool_timeouts_are_retried() {
+        let mut attempts = 0;
+        let result: super::Result<()> = retry_with_backoff(3, Duration::ZERO, || {
+            attempts += 1;
+            async { Err(sqlx::Error::PoolTimedOut) }
+        })
+        .await;
+        assert!(matches!(
+            result,
+            Err(Error::Sqlx(sqlx::Error::PoolTimedOut))
+        ));
+        assert_eq!(attempts, 3);
+
+        let mut attempts = 0;
+        let result: super::Result<()> = retry_with_backoff(3, Duration::ZERO, || {
+            attempts += 1;
+            async { Err(sqlx::Error::RowNotFound) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
     }
 }
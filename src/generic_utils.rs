@@ -1,5 +1,7 @@
 use base64ct::Encoding;
 use serde::Serialize;
+use std::cell::RefCell;
+use std::rc::Rc;
 
 // -----------------------------------------------------------------------------
 // Error handling
@@ -21,12 +23,83 @@ impl core::fmt::Display for Error {
 
 impl std::error::Error for Error {}
 
+// -----------------------------------------------------------------------------
+// Clock
+// -----------------------------------------------------------------------------
+
+/// A source of the current time, so that tests can control it (see `MockClock`).
+pub trait Clock {
+    fn now(&self) -> chrono::DateTime<chrono::Utc>;
+}
+
+/// The real clock, used unless a test says otherwise.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> chrono::DateTime<chrono::Utc> {
+        chrono::Utc::now()
+    }
+}
+
+thread_local! {
+    // NOTE: per thread, so a test's clock cannot leak into tests running alongside.
+    //       `#[tokio::test]` runs on a single thread, so this covers async tests too.
+    static CLOCK: RefCell<Rc<dyn Clock>> = RefCell::new(Rc::new(SystemClock));
+}
+
+/// Read the current time from this thread's clock until the guard is dropped,
+/// when the previous clock is restored.
+#[cfg(test)]
+pub fn set_clock(clock: impl Clock + 'static) -> ClockGuard {
+    let previous = CLOCK.with(|current| current.replace(Rc::new(clock)));
+
+    ClockGuard { previous }
+}
+
+#[cfg(test)]
+pub struct ClockGuard {
+    previous: Rc<dyn Clock>,
+}
+
+#[cfg(test)]
+impl Drop for ClockGuard {
+    fn drop(&mut self) {
+        CLOCK.with(|current| *current.borrow_mut() = self.previous.clone());
+    }
+}
+
+/// A clock that only moves when told to. Clones share the same time, so a test
+/// can keep one to `advance` after handing another to `set_clock`.
+#[cfg(test)]
+#[derive(Clone)]
+pub struct MockClock(Rc<std::cell::Cell<chrono::DateTime<chrono::Utc>>>);
+
+#[cfg(test)]
+impl MockClock {
+    pub fn frozen_at(time: chrono::DateTime<chrono::Utc>) -> Self {
+        Self(Rc::new(std::cell::Cell::new(time)))
+    }
+
+    pub fn advance(&self, duration: chrono::Duration) {
+        self.0.set(self.0.get() + duration);
+    }
+}
+
+#[cfg(test)]
+impl Clock for MockClock {
+    fn now(&self) -> chrono::DateTime<chrono::Utc> {
+        self.0.get()
+    }
+}
+
 // -----------------------------------------------------------------------------
 // Timing
 // -----------------------------------------------------------------------------
 
+/// The current time, from this thread's clock: the system clock, unless a test
+/// has set another (see `set_clock`).
 pub fn now_utc() -> chrono::DateTime<chrono::Utc> {
-    chrono::Utc::now()
+    CLOCK.with(|clock| clock.borrow().now())
 }
 
 pub fn format_utc_time(time: chrono::DateTime<chrono::Utc>) -> String {
@@ -40,3 +113,203 @@ pub fn now_utc_plus_seconds_string(seconds: f64) -> String {
 pub fn parse_utc(moment: &str) -> Result<chrono::DateTime<chrono::FixedOffset>> {
     chrono::DateTime::parse_from_rfc3339(moment).map_err(|_| Error::ParseError(moment.to_string()))
 }
+
+/// Parse a timestamp in any of the formats seen in imported data, trying each in
+/// turn: RFC3339, `YYYY-MM-DD HH:MM:SS` and `YYYY-MM-DD` (both taken as UTC, the
+/// latter at midnight), then a bare integer as seconds since the UNIX epoch.
+pub fn parse_flexible(input: &str) -> Result<chrono::DateTime<chrono::Utc>> {
+    use chrono::{NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+    let moment = input.trim();
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(moment) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    if let Ok(time) = NaiveDateTime::parse_from_str(moment, "%Y-%m-%d %H:%M:%S") {
+        return Ok(Utc.from_utc_datetime(&time));
+    }
+    if let Some(time) = NaiveDate::parse_from_str(moment, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+    {
+        return Ok(Utc.from_utc_datetime(&time));
+    }
+    if let Some(time) = moment
+        .parse::<i64>()
+        .ok()
+        .and_then(|seconds| Utc.timestamp_opt(seconds, 0).single())
+    {
+        return Ok(time);
+    }
+
+    Err(Error::ParseError(input.to_string()))
+}
+
+// -----------------------------------------------------------------------------
+// Durations
+// -----------------------------------------------------------------------------
+
+/// The Julian year, in days: the standard unit for astronomical periods.
+pub const DAYS_PER_JULIAN_YEAR: f64 = 365.25;
+
+/// Periods of a thousand years or more are given in centuries.
+const YEARS_BEFORE_CENTURIES: f64 = 1_000.0;
+
+pub fn days_to_years(days: f64) -> f64 {
+    days / DAYS_PER_JULIAN_YEAR
+}
+
+/// Render a period for display in the largest sensible unit, to one decimal place:
+/// days under a year, then years (*eg* `164.8 years` for Neptune), then centuries
+/// for a thousand years or more.
+pub fn format_days_as_human(days: f64) -> String {
+    let years = days_to_years(days);
+
+    if years.abs() < 1.0 {
+        format!("{days:.1} days")
+    } else if years.abs() < YEARS_BEFORE_CENTURIES {
+        format!("{years:.1} years")
+    } else {
+        format!("{:.1} centuries", years / 100.0)
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Base64
+// -----------------------------------------------------------------------------
+
+/// URL-safe base64, without padding, so the result can go in a query string as is.
+pub fn b64u_encode(content: &str) -> String {
+    base64ct::Base64UrlUnpadded::encode_string(content.as_bytes())
+}
+
+pub fn b64u_decode(b64u: &str) -> Result<String> {
+    base64ct::Base64UrlUnpadded::decode_vec(b64u)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .ok_or(Error::B64DecodingFailure)
+}
+
+// -----------------------------------------------------------------------------
+// Text
+// -----------------------------------------------------------------------------
+
+/// The Levenshtein (edit) distance between `a` and `b`, in characters, or `None`
+/// if it is more than `max`. Giving up once every entry in a row exceeds `max`
+/// keeps the cost down when the strings are very different.
+pub fn levenshtein_within(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, a_char) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        if current.iter().all(|&distance| distance > max) {
+            return None;
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    Some(previous[b.len()]).filter(|&distance| distance <= max)
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_parse_flexible_formats() {
+        let expected = Utc.with_ymd_and_hms(2023, 9, 1, 12, 30, 0).unwrap();
+
+        assert_eq!(parse_flexible("2023-09-01T12:30:00Z").unwrap(), expected);
+        assert_eq!(
+            parse_flexible("2023-09-01T14:30:00+02:00").unwrap(),
+            expected
+        );
+        assert_eq!(parse_flexible("2023-09-01 12:30:00").unwrap(), expected);
+        assert_eq!(parse_flexible("1693571400").unwrap(), expected);
+        assert_eq!(
+            parse_flexible("2023-09-01").unwrap(),
+            Utc.with_ymd_and_hms(2023, 9, 1, 0, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_frozen_clock() {
+        let frozen = Utc.with_ymd_and_hms(2023, 9, 1, 12, 30, 0).unwrap();
+        let clock = MockClock::frozen_at(frozen);
+        let guard = set_clock(clock.clone());
+
+        assert_eq!(now_utc(), frozen);
+        assert_eq!(
+            now_utc_plus_seconds_string(60.0),
+            format_utc_time(frozen + chrono::Duration::seconds(60))
+        );
+        clock.advance(chrono::Duration::minutes(5));
+        assert_eq!(now_utc(), frozen + chrono::Duration::minutes(5));
+
+        // The real clock is back once the guard is dropped.
+        drop(guard);
+        assert!(now_utc() > frozen);
+    }
+
+    #[test]
+    fn test_days_to_years() {
+        assert_eq!(days_to_years(365.25), 1.0);
+        assert_eq!(days_to_years(36_525.0), 100.0);
+    }
+
+    #[test]
+    fn test_format_days_as_human() {
+        // Sub-year: Mercury.
+        assert_eq!(format_days_as_human(87.969), "88.0 days");
+        // Multi-year: Jupiter and Neptune.
+        assert_eq!(format_days_as_human(4_332.59), "11.9 years");
+        assert_eq!(format_days_as_human(60_190.0), "164.8 years");
+        // Multi-century: Sedna, at roughly 11,400 years.
+        assert_eq!(format_days_as_human(4_163_850.0), "114.0 centuries");
+    }
+
+    #[test]
+    fn test_b64u_round_trip() {
+        let encoded = b64u_encode("42?&=");
+
+        assert!(!encoded.contains(['+', '/', '=']), "{encoded}");
+        assert_eq!(b64u_decode(&encoded).unwrap(), "42?&=");
+        assert!(matches!(
+            b64u_decode("not base64!"),
+            Err(Error::B64DecodingFailure)
+        ));
+    }
+
+    #[test]
+    fn test_levenshtein_within() {
+        assert_eq!(levenshtein_within("jupiter", "jupiter", 2), Some(0));
+        assert_eq!(levenshtein_within("jupter", "jupiter", 2), Some(1));
+        assert_eq!(levenshtein_within("kitten", "sitting", 3), Some(3));
+        assert_eq!(levenshtein_within("", "io", 2), Some(2));
+        // Over the bound, whether the lengths differ or not.
+        assert_eq!(levenshtein_within("kitten", "sitting", 2), None);
+        assert_eq!(levenshtein_within("mars", "venus", 1), None);
+        assert_eq!(levenshtein_within("io", "ganymede", 3), None);
+    }
+
+    #[test]
+    fn test_parse_flexible_rejects_garbage() {
+        let result = parse_flexible("next tuesday");
+
+        assert!(matches!(result, Err(Error::ParseError(input)) if input == "next tuesday"));
+    }
+}
@@ -4,13 +4,18 @@ pub mod data_access;
 pub mod generic_utils;
 mod request_context;
 pub mod security;
+mod server;
+pub mod web;
 
 // -----------------------------------------------------------------------------
 // Re-exports
 // -----------------------------------------------------------------------------
 
 pub use _dev_utils::initialise_development_environment;
-pub use request_context::RequestContext;
+#[cfg(feature = "test-util")]
+pub use _dev_utils::{spawn_test_app, TestApp};
+pub use request_context::{RequestContext, Role, SystemContext, UserId};
+pub use server::run;
 
 // -----------------------------------------------------------------------------
 // Top-level errors
@@ -29,7 +34,29 @@ pub use request_context::RequestContext;
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug)]
-pub enum Error {}
+pub enum Error {
+    FailedToBind(String),
+    PortInUse(u16),
+    Server(String),
+    DataAccess(data_access::Error),
+}
+
+impl From<data_access::Error> for Error {
+    fn from(err: data_access::Error) -> Self {
+        Self::DataAccess(err)
+    }
+}
+
+impl Error {
+    /// The process exit code for a failed startup. A port conflict gets its own
+    /// code, so supervisors and scripts can tell it apart from other failures.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::PortInUse(_) => 2,
+            _ => 1,
+        }
+    }
+}
 
 impl core::fmt::Display for Error {
     fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
@@ -2,8 +2,13 @@
 async fn main() {
     tracing_subscriber::fmt()
         .with_target(false)
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_env_filter(tracing_subscriber::EnvFilter::new(
+            orrery::config::get_config()
+                .RUST_LOG
+                .to_env_filter_directive(),
+        ))
         .init();
+    orrery::config::get_config().log_effective();
 
     // -----------------------------------------------------------------------------
     // Development-only
@@ -13,5 +18,10 @@ async fn main() {
     orrery::initialise_development_environment().await;
     // -----------------------------------------------------------------------------
 
-    println!("Hello, World!");
+    // NOTE: as above, the application cannot do anything useful if the server fails;
+    //       but a clean exit code (and no panic) makes the cause easier to spot.
+    if let Err(err) = orrery::run().await {
+        tracing::error!("server failed: {err}");
+        std::process::exit(err.exit_code());
+    }
 }
@@ -7,6 +7,8 @@ pub type Result<T> = core::result::Result<T, Error>;
 #[derive(Debug, Serialize)]
 pub enum Error {
     CannotUseRootContext,
+    InsufficientPrivilege { required: Role, actual: Role },
+    UnknownRole(String),
 }
 
 impl core::fmt::Display for Error {
@@ -17,11 +19,90 @@ impl core::fmt::Display for Error {
 
 impl std::error::Error for Error {}
 
+// -----------------------------------------------------------------------------
+// Roles
+// -----------------------------------------------------------------------------
+
+/// NOTE: variants are declared from least to most privileged, so the derived
+///       `Ord` can be used to compare them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum Role {
+    Viewer,
+    Editor,
+    Admin,
+}
+
+impl Role {
+    /// The representation stored in the `user.role` column.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Viewer => "viewer",
+            Self::Editor => "editor",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+impl core::str::FromStr for Role {
+    type Err = Error;
+
+    fn from_str(role: &str) -> Result<Self> {
+        match role {
+            "viewer" => Ok(Self::Viewer),
+            "editor" => Ok(Self::Editor),
+            "admin" => Ok(Self::Admin),
+            _ => Err(Error::UnknownRole(role.to_string())),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Identities
+// -----------------------------------------------------------------------------
+
+/// The id of a real user. Always positive: `0` is the root id, reserved for the
+/// system (see `SystemContext`), so `new` refuses it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+pub struct UserId(i64);
+
+impl UserId {
+    pub fn new(id: i64) -> Result<Self> {
+        if id > 0 {
+            Ok(Self(id))
+        } else {
+            Err(Error::CannotUseRootContext)
+        }
+    }
+
+    /// The raw id, as stored in the database.
+    pub fn get(self) -> i64 {
+        self.0
+    }
+}
+
+/// Operations the system performs on its own behalf rather than for a user, *eg*
+/// looking the user up while authenticating, or serving public reads. This is
+/// the only way to obtain the root `RequestContext`.
+#[derive(Clone, Copy, Debug)]
+pub struct SystemContext;
+
 // -----------------------------------------------------------------------------
 // Implementation
 // -----------------------------------------------------------------------------
+#[derive(Clone, Debug)]
 pub struct RequestContext {
-    user_id: i64,
+    /// `None` for the root context, which acts for the system rather than a user.
+    user_id: Option<UserId>,
+    role: Role,
+}
+
+impl From<SystemContext> for RequestContext {
+    fn from(_: SystemContext) -> Self {
+        RequestContext {
+            user_id: None,
+            role: Role::Admin,
+        }
+    }
 }
 
 impl RequestContext {
@@ -29,25 +110,107 @@ impl RequestContext {
     // Constructors
     // -------------------------------------------------------------------------
 
-    /// The *root* context is used internally by the system.
-    /// There can only be one root contest, the id *must* be 0, and no other
-    /// user can have this id.
-    pub fn root_context() -> Self {
-        RequestContext { user_id: 0 }
+    /// The *root* context, as `RequestContext::from(SystemContext)`, for tests. It
+    /// has no user id, as no user can act as root, and always has the `Admin` role.
+    #[cfg(test)]
+    pub(crate) fn root_context() -> Self {
+        SystemContext.into()
     }
 
-    pub fn new(user_id: i64) -> Result<Self> {
-        if user_id == 0 {
-            Err(Error::CannotUseRootContext)
-        } else {
-            Ok(Self { user_id })
+    /// A context acting for a real user. The root context cannot be built this
+    /// way, as a `UserId` is never the root id.
+    pub fn new(user_id: UserId, role: Role) -> Self {
+        Self {
+            user_id: Some(user_id),
+            role,
         }
     }
 
     // -------------------------------------------------------------------------
     // Property accessors
     // -------------------------------------------------------------------------
-    pub fn user_id(&self) -> i64 {
-        self.user_id
+    /// The user the context acts for; `CannotUseRootContext` for the root context.
+    pub fn user_id(&self) -> Result<UserId> {
+        self.user_id.ok_or(Error::CannotUseRootContext)
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    // -------------------------------------------------------------------------
+    // Authorization
+    // -------------------------------------------------------------------------
+
+    /// Succeeds if the context's role is at least `min`.
+    pub fn require(&self, min: Role) -> Result<()> {
+        if self.role >= min {
+            Ok(())
+        } else {
+            Err(Error::InsufficientPrivilege {
+                required: min,
+                actual: self.role,
+            })
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_context_is_admin() {
+        let ctx = RequestContext::root_context();
+        assert_eq!(ctx.role(), Role::Admin);
+        assert!(ctx.require(Role::Admin).is_ok());
+    }
+
+    #[test]
+    fn test_cannot_create_root_user_id() {
+        assert!(matches!(UserId::new(0), Err(Error::CannotUseRootContext)));
+        assert!(matches!(UserId::new(-1), Err(Error::CannotUseRootContext)));
+        assert_eq!(UserId::new(1).unwrap().get(), 1);
+    }
+
+    #[test]
+    fn test_root_context_has_no_user_id() {
+        let ctx = RequestContext::root_context();
+        assert!(matches!(ctx.user_id(), Err(Error::CannotUseRootContext)));
+
+        let user_id = UserId::new(1).unwrap();
+        let ctx = RequestContext::new(user_id, Role::Viewer);
+        assert_eq!(ctx.user_id().unwrap(), user_id);
+    }
+
+    #[test]
+    fn test_require_role_boundaries() {
+        let user_id = UserId::new(1).unwrap();
+        let viewer = RequestContext::new(user_id, Role::Viewer);
+        assert!(viewer.require(Role::Viewer).is_ok());
+        assert!(viewer.require(Role::Editor).is_err());
+        assert!(viewer.require(Role::Admin).is_err());
+
+        let editor = RequestContext::new(user_id, Role::Editor);
+        assert!(editor.require(Role::Viewer).is_ok());
+        assert!(editor.require(Role::Editor).is_ok());
+        assert!(editor.require(Role::Admin).is_err());
+
+        let admin = RequestContext::new(user_id, Role::Admin);
+        assert!(admin.require(Role::Viewer).is_ok());
+        assert!(admin.require(Role::Editor).is_ok());
+        assert!(admin.require(Role::Admin).is_ok());
+    }
+
+    #[test]
+    fn test_role_round_trip() {
+        for role in [Role::Viewer, Role::Editor, Role::Admin] {
+            assert_eq!(role.as_str().parse::<Role>().unwrap(), role);
+        }
+        assert!("superuser".parse::<Role>().is_err());
     }
 }
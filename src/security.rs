@@ -1,8 +1,11 @@
 use crate::config;
+use argon2::password_hash::{self, PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
 use serde::Serialize;
-use std::{fmt::Display, str::FromStr};
 use sutorio_axum_utils_crypto as crypto;
 
+pub use crypto::Token;
+
 // -----------------------------------------------------------------------------
 // Error handling
 // -----------------------------------------------------------------------------
@@ -12,13 +15,18 @@ pub type Result<T> = core::result::Result<T, Error>;
 pub enum Error {
     // Format errors
     Crypto(crypto::Error),
+    PwdWithoutScheme,
+    PwdSchemeUnknown(String),
+    PwdHashFail(String),
+    TokenExpParseFail,
+    TokenRefreshWindowClosed,
 }
 
 impl From<crypto::Error> for Error {
-	fn from(val: crypto::Error) -> Self {
-		Self::Crypto(val)
-	}
-} 
+    fn from(val: crypto::Error) -> Self {
+        Self::Crypto(val)
+    }
+}
 
 impl core::fmt::Display for Error {
     fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
@@ -28,6 +36,152 @@ impl core::fmt::Display for Error {
 
 impl std::error::Error for Error {}
 
+// -----------------------------------------------------------------------------
+// Passwords
+// -----------------------------------------------------------------------------
+
+/// Cleartext content plus the salt it should be encrypted with.
+pub struct EncryptedContent {
+    pub content: String,
+    pub salt: String,
+}
+
+/// The scheme new passwords are hashed with. Stored hashes are prefixed with it
+/// (e.g. `#02#...`), so the algorithm can change without invalidating them:
+/// - `01`: a hash of the password and salt keyed with `PASSWORD_KEY`. It is
+///   fast to compute, so is upgraded on the next login (see `needs_rehash`).
+/// - `02`: argon2id, with `PASSWORD_KEY` as its secret. The costs are stored in
+///   the hash, so `ARGON2_COSTS` can be raised without a new scheme.
+const PWD_SCHEME: &str = "02";
+
+/// The argon2id costs for new hashes: memory (KiB), iterations and lanes, as
+/// recommended by OWASP.
+const ARGON2_COSTS: (u32, u32, u32) = (19_456, 2, 1);
+
+/// A well-formed `02` hash, with `ARGON2_COSTS`, that no password matches.
+const DUMMY_PWD_HASH: &str = "#02#$argon2id$v=19$m=19456,t=2,p=1$b3JyZXJ5LWR1bW15LXNhbHQ\
+                              $AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8";
+
+pub fn encrypt_password(enc_content: &EncryptedContent) -> Result<String> {
+    encrypt_password_with_scheme(PWD_SCHEME, enc_content)
+}
+
+/// As `encrypt_password`, with the given scheme rather than the current one.
+pub(crate) fn encrypt_password_with_scheme(
+    scheme: &str,
+    enc_content: &EncryptedContent,
+) -> Result<String> {
+    let hash = match scheme {
+        "01" => hash_01(enc_content)?,
+        "02" => hash_02(enc_content)?,
+        _ => return Err(Error::PwdSchemeUnknown(scheme.to_string())),
+    };
+
+    Ok(format!("#{scheme}#{hash}"))
+}
+
+/// Check `enc_content` against a hash produced by `encrypt_password`, using the
+/// scheme the hash was stored with. The comparison is constant-time.
+pub fn verify_password(enc_content: &EncryptedContent, expected_hash: &str) -> Result<bool> {
+    let (scheme, expected) = split_pwd_scheme(expected_hash)?;
+    match scheme {
+        "01" => {
+            let hash = hash_01(enc_content)?;
+            Ok(constant_time_eq(hash.as_bytes(), expected.as_bytes()))
+        }
+        "02" => verify_02(enc_content, expected),
+        _ => Err(Error::PwdSchemeUnknown(scheme.to_string())),
+    }
+}
+
+/// Verify `enc_content` against `DUMMY_PWD_HASH` and discard the result, so a
+/// login for an unknown user costs as much as one with a wrong password.
+pub fn verify_dummy_password(enc_content: &EncryptedContent) {
+    let _ = verify_password(enc_content, DUMMY_PWD_HASH);
+}
+
+/// Whether a hash should be replaced, once the password has been verified against
+/// it: it is from an older scheme than `PWD_SCHEME`, or has other `ARGON2_COSTS`.
+pub fn needs_rehash(hash: &str) -> bool {
+    let Ok(("02", phc)) = split_pwd_scheme(hash) else {
+        return true;
+    };
+
+    PasswordHash::new(phc)
+        .and_then(|phc| Params::try_from(&phc))
+        .map_or(true, |params| {
+            (params.m_cost(), params.t_cost(), params.p_cost()) != ARGON2_COSTS
+        })
+}
+
+fn hash_01(enc_content: &EncryptedContent) -> Result<String> {
+    let config = config::get_config();
+    let content = crypto::EncryptContent {
+        content: enc_content.content.to_string(),
+        salt: enc_content.salt.to_string(),
+    };
+
+    Ok(crypto::encrypt_into_b64u(
+        config.PASSWORD_KEY.as_bytes(),
+        &content,
+    )?)
+}
+
+/// The hash in the PHC string format (`$argon2id$v=19$m=...`), which records the
+/// costs and salt along with it.
+fn hash_02(enc_content: &EncryptedContent) -> Result<String> {
+    let config = config::get_config();
+    let salt = SaltString::encode_b64(enc_content.salt.as_bytes()).map_err(pwd_hash_fail)?;
+    let hash = argon2(config.PASSWORD_KEY.as_bytes())?
+        .hash_password(enc_content.content.as_bytes(), &salt)
+        .map_err(pwd_hash_fail)?;
+
+    Ok(hash.to_string())
+}
+
+/// NOTE: the costs and salt are those recorded in `expected`, not the current ones.
+fn verify_02(enc_content: &EncryptedContent, expected: &str) -> Result<bool> {
+    let config = config::get_config();
+    let expected = PasswordHash::new(expected).map_err(pwd_hash_fail)?;
+    let verified = argon2(config.PASSWORD_KEY.as_bytes())?
+        .verify_password(enc_content.content.as_bytes(), &expected);
+
+    match verified {
+        Ok(()) => Ok(true),
+        Err(password_hash::Error::Password) => Ok(false),
+        Err(err) => Err(pwd_hash_fail(err)),
+    }
+}
+
+fn argon2(secret: &[u8]) -> Result<Argon2<'_>> {
+    let (m_cost, t_cost, p_cost) = ARGON2_COSTS;
+    let params = Params::new(m_cost, t_cost, p_cost, None).map_err(pwd_hash_fail)?;
+
+    Argon2::new_with_secret(secret, Algorithm::Argon2id, Version::V0x13, params)
+        .map_err(pwd_hash_fail)
+}
+
+fn pwd_hash_fail(err: impl core::fmt::Display) -> Error {
+    Error::PwdHashFail(err.to_string())
+}
+
+/// Split `#<scheme>#<hash>` into its scheme and hash.
+fn split_pwd_scheme(pwd_with_scheme: &str) -> Result<(&str, &str)> {
+    pwd_with_scheme
+        .strip_prefix('#')
+        .and_then(|rest| rest.split_once('#'))
+        .ok_or(Error::PwdWithoutScheme)
+}
+
+/// NOTE: the running time depends only on the lengths, never on where the first
+///       differing byte is, so a mismatch leaks nothing about the stored hash.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
 
 // -----------------------------------------------------------------------------
 // Tokens
@@ -35,17 +189,168 @@ impl std::error::Error for Error {}
 
 pub fn generate_web_token(username: &str, salt: &str) -> Result<Token> {
     let config = config::get_config();
-    crypto::generate_token(
-        username,
-        config.TOKEN_DURATION_IN_SECONDS,
+
+    generate_web_token_with_duration(username, salt, config.TOKEN_DURATION_IN_SECONDS)
+}
+
+/// As `generate_web_token`, with an explicit lifetime (negative for an already expired token).
+pub(crate) fn generate_web_token_with_duration(
+    username: &str,
+    salt: &str,
+    duration: f64,
+) -> Result<Token> {
+    let config = config::get_config();
+    let token = crypto::generate_token(username, duration, salt, config.TOKEN_KEY.as_bytes())?;
+
+    Ok(token)
+}
+
+pub fn validate_web_token(original_token: &Token, salt: &str) -> Result<()> {
+    let config = config::get_config();
+    crypto::validate_token_signature_and_expiration(
+        original_token,
         salt,
         config.TOKEN_KEY.as_bytes(),
-    )
+    )?;
+
+    Ok(())
 }
 
-pub fn validate_web_token(original_token: &Token, salt: &str) -> Result<()> {
+/// As `validate_web_token`, but a correctly signed token that expired less than
+/// `TOKEN_REFRESH_GRACE_SECONDS` ago is also accepted, so it can be refreshed.
+pub fn validate_web_token_for_refresh(original_token: &Token, salt: &str) -> Result<()> {
     let config = config::get_config();
-    crypto::validate_token_signature_and_expiration(original_token, salt, config.TOKEN_KEY.as_bytes())
+    // NOTE: the signature is checked before the expiration, so an expired token
+    //       is known to be genuine.
+    match crypto::validate_token_signature_and_expiration(
+        original_token,
+        salt,
+        config.TOKEN_KEY.as_bytes(),
+    ) {
+        Err(crypto::Error::TokenExpired) => {
+            let expired_at = chrono::DateTime::parse_from_rfc3339(&original_token.exp)
+                .map_err(|_| Error::TokenExpParseFail)?;
+            let expired_for = chrono::Utc::now().signed_duration_since(expired_at);
+            if expired_for.num_milliseconds() as f64 / 1000.0 > config.TOKEN_REFRESH_GRACE_SECONDS {
+                return Err(Error::TokenRefreshWindowClosed);
+            }
+
+            Ok(())
+        }
+        result => Ok(result?),
+    }
 }
 
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn content(clear: &str, salt: &str) -> EncryptedContent {
+        EncryptedContent {
+            content: clear.to_string(),
+            salt: salt.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_password_round_trip() -> Result<()> {
+        let hash = encrypt_password(&content("welcome", "some-salt"))?;
+
+        assert!(hash.starts_with("#02#$argon2id$"));
+        assert!(!needs_rehash(&hash));
+        assert!(verify_password(&content("welcome", "some-salt"), &hash)?);
+        assert!(!verify_password(&content("wrong", "some-salt"), &hash)?);
+        assert!(!verify_password(&content("welcome", "other-salt"), &hash)?);
 
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_password_rejects_bad_schemes() -> Result<()> {
+        let hash = encrypt_password(&content("welcome", "some-salt"))?;
+        let unprefixed = hash.trim_start_matches("#02#");
+
+        assert!(matches!(
+            verify_password(&content("welcome", "some-salt"), unprefixed),
+            Err(Error::PwdWithoutScheme)
+        ));
+        assert!(matches!(
+            verify_password(&content("welcome", "some-salt"), &format!("#99#{unprefixed}")),
+            Err(Error::PwdSchemeUnknown(scheme)) if scheme == "99"
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_old_schemes_verify_and_need_rehash() -> Result<()> {
+        let old = encrypt_password_with_scheme("01", &content("welcome", "some-salt"))?;
+
+        assert!(old.starts_with("#01#"));
+        assert!(verify_password(&content("welcome", "some-salt"), &old)?);
+        assert!(!verify_password(&content("wrong", "some-salt"), &old)?);
+        assert!(needs_rehash(&old));
+
+        // The current scheme, but with weaker costs than `ARGON2_COSTS`.
+        let params = Params::new(8, 1, 1, None).map_err(pwd_hash_fail)?;
+        let salt = SaltString::encode_b64(b"some-salt").map_err(pwd_hash_fail)?;
+        let weak = Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password(b"welcome", &salt)
+            .map_err(pwd_hash_fail)?;
+        assert!(needs_rehash(&format!("#02#{weak}")));
+        assert!(needs_rehash("not a hash"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dummy_pwd_hash_has_current_costs() -> Result<()> {
+        assert!(!needs_rehash(DUMMY_PWD_HASH));
+        assert!(!verify_password(
+            &content("welcome", "some-salt"),
+            DUMMY_PWD_HASH
+        )?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_web_token_for_refresh() -> Result<()> {
+        let grace = config::get_config().TOKEN_REFRESH_GRACE_SECONDS;
+
+        // Still valid.
+        let token = generate_web_token("test_refresh", "some-salt")?;
+        validate_web_token_for_refresh(&token, "some-salt")?;
+
+        // Expired, but within the grace window.
+        let token = generate_web_token_with_duration("test_refresh", "some-salt", -10.0)?;
+        assert!(validate_web_token(&token, "some-salt").is_err());
+        validate_web_token_for_refresh(&token, "some-salt")?;
+
+        // Expired, and past the grace window.
+        let token = generate_web_token_with_duration("test_refresh", "some-salt", -(grace + 60.0))?;
+        assert!(matches!(
+            validate_web_token_for_refresh(&token, "some-salt"),
+            Err(Error::TokenRefreshWindowClosed)
+        ));
+
+        // Within the grace window, but signed with another salt.
+        let token = generate_web_token_with_duration("test_refresh", "other-salt", -10.0)?;
+        assert!(validate_web_token_for_refresh(&token, "some-salt").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"abcd"));
+        assert!(constant_time_eq(b"", b""));
+    }
+}
@@ -0,0 +1,174 @@
+//! Server bootstrap: bind, serve, and shut down gracefully.
+//!
+//! On shutdown the listener stops accepting new connections, but requests that are
+//! already in flight are allowed to complete before `run` returns.
+use crate::config::get_config;
+use crate::data_access::DataAccessManager;
+use crate::web::{construct_routes, AppState};
+use crate::{Error, Result};
+use std::future::Future;
+use std::io::ErrorKind;
+use std::net::{SocketAddr, TcpListener};
+use std::time::Duration;
+
+/// How many ports after `SERVER_PORT` are tried when `SERVER_PORT_FALLBACK` is set.
+const FALLBACK_ATTEMPTS: u16 = 10;
+
+// -----------------------------------------------------------------------------
+// Bootstrap
+// -----------------------------------------------------------------------------
+
+/// Build the application, bind to the configured port, and serve until a
+/// shutdown signal (Ctrl+C, or SIGTERM on Unix) is received.
+pub async fn run() -> Result<()> {
+    let dam = DataAccessManager::new().await?;
+    let addr = SocketAddr::from(([0, 0, 0, 0], get_config().SERVER_PORT));
+    let listener = bind(addr, get_config().SERVER_PORT_FALLBACK)?;
+    tracing::info!("listening on {}", listener.local_addr().unwrap_or(addr));
+
+    serve(listener, AppState::new(dam), shutdown_signal()).await
+}
+
+/// Bind to `addr`. If the port is taken, either fail with `PortInUse` or, with
+/// `fallback`, move on to the next few ports and use the first free one.
+pub fn bind(addr: SocketAddr, fallback: bool) -> Result<TcpListener> {
+    let attempts = if fallback { FALLBACK_ATTEMPTS + 1 } else { 1 };
+
+    for offset in 0..attempts {
+        let Some(port) = addr.port().checked_add(offset) else {
+            break;
+        };
+        match TcpListener::bind(SocketAddr::new(addr.ip(), port)) {
+            Ok(listener) => {
+                if offset > 0 {
+                    tracing::warn!("port {} is in use, falling back to {port}", addr.port());
+                }
+                return Ok(listener);
+            }
+            Err(err) if err.kind() == ErrorKind::AddrInUse => continue,
+            Err(err) => return Err(Error::FailedToBind(err.to_string())),
+        }
+    }
+
+    tracing::error!(
+        "port {} is already in use: stop whatever is listening on it, change SERVER_PORT, \
+         or set SERVER_PORT_FALLBACK",
+        addr.port()
+    );
+    Err(Error::PortInUse(addr.port()))
+}
+
+/// Serve the application on an already-bound listener until `shutdown` resolves.
+/// Split out from `run` so tests can bind to an ephemeral port and control shutdown.
+///
+/// The state is marked ready for traffic here. Once `shutdown` resolves it is
+/// marked not ready, and new connections are still accepted for
+/// `SHUTDOWN_DRAIN_SECONDS`, so load balancers see `/ready` fail before the
+/// listener goes away.
+pub async fn serve(
+    listener: TcpListener,
+    state: AppState,
+    shutdown: impl Future<Output = ()>,
+) -> Result<()> {
+    let readiness = state.readiness.clone();
+    let drain = Duration::from_secs(get_config().SHUTDOWN_DRAIN_SECONDS);
+    let shutdown = async move {
+        shutdown.await;
+        readiness.set_ready(false);
+        tokio::time::sleep(drain).await;
+    };
+    state.readiness.set_ready(true);
+
+    axum::Server::from_tcp(listener)
+        .map_err(|err| Error::FailedToBind(err.to_string()))?
+        // NOTE: the connection info gives the rate limiter the client's IP address.
+        .serve(construct_routes(state).into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown)
+        .await
+        .map_err(|err| Error::Server(err.to_string()))
+}
+
+/// Resolves on whichever comes first: Ctrl+C, or (on Unix) SIGTERM, which is
+/// what container orchestrators send.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install the Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install the SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    tracing::info!("shutdown signal received, draining in-flight requests");
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils::initialise_test_environment;
+    use serial_test::serial;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::sync::oneshot;
+
+    #[test]
+    fn test_bind_reports_port_in_use() -> anyhow::Result<()> {
+        let taken = TcpListener::bind("127.0.0.1:0")?;
+        let addr = taken.local_addr()?;
+
+        let result = bind(addr, false);
+        assert!(matches!(result, Err(Error::PortInUse(port)) if port == addr.port()));
+        assert_eq!(result.unwrap_err().exit_code(), 2);
+
+        // With the fallback, a later port is chosen instead.
+        let listener = bind(addr, true)?;
+        assert!(listener.local_addr()?.port() > addr.port());
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_serve_shuts_down_gracefully() -> anyhow::Result<()> {
+        let dam = initialise_test_environment().await;
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+        let state = AppState::new(dam);
+        let readiness = state.readiness.clone();
+        let server = tokio::spawn(serve(listener, state, async {
+            shutdown_rx.await.ok();
+        }));
+
+        let mut stream = tokio::net::TcpStream::connect(addr).await?;
+        stream
+            .write_all(b"GET /ready HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await?;
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+
+        shutdown_tx.send(()).unwrap();
+        server.await??;
+        assert!(!readiness.is_ready());
+
+        Ok(())
+    }
+}
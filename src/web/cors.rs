@@ -0,0 +1,80 @@
+use axum::http::header::{AUTHORIZATION, CONTENT_TYPE};
+use axum::http::{HeaderValue, Method};
+use tower_http::cors::CorsLayer;
+
+// -----------------------------------------------------------------------------
+// Layer
+// -----------------------------------------------------------------------------
+
+/// A CORS layer allowing the comma-separated `allowed_origins` to call the API
+/// with any of its methods and the headers it reads. Returns `None` if there
+/// are no origins, in which case only same-origin requests will work.
+///
+/// NOTE: origins that are not valid header values are skipped with a warning,
+///       rather than failing startup.
+pub fn cors_layer(allowed_origins: &str) -> Option<CorsLayer> {
+    let origins: Vec<HeaderValue> = allowed_origins
+        .split(',')
+        .map(str::trim)
+        .filter(|origin| !origin.is_empty())
+        .filter_map(|origin| match HeaderValue::from_str(origin) {
+            Ok(value) => Some(value),
+            Err(_) => {
+                tracing::warn!("ignoring invalid allowed origin: {origin:?}");
+                None
+            }
+        })
+        .collect();
+    if origins.is_empty() {
+        return None;
+    }
+
+    let layer = CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods([Method::GET, Method::POST, Method::PATCH, Method::DELETE])
+        .allow_headers([AUTHORIZATION, CONTENT_TYPE]);
+
+    Some(layer)
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::header::{ACCESS_CONTROL_ALLOW_ORIGIN, ORIGIN};
+    use axum::routing::get;
+    use axum::Router;
+    use axum_test_helper::TestClient;
+
+    #[test]
+    fn test_no_origins_means_no_layer() {
+        assert!(cors_layer("").is_none());
+        assert!(cors_layer(" , ").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_allow_origin_header() {
+        let layer = cors_layer("https://app.example, https://admin.example").unwrap();
+        let client = TestClient::new(Router::new().route("/", get(|| async {})).layer(layer));
+
+        let res = client
+            .get("/")
+            .header(ORIGIN, "https://admin.example")
+            .send()
+            .await;
+        assert_eq!(
+            res.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+            "https://admin.example"
+        );
+
+        let res = client
+            .get("/")
+            .header(ORIGIN, "https://evil.example")
+            .send()
+            .await;
+        assert!(res.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+    }
+}
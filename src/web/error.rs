@@ -0,0 +1,612 @@
+use crate::web::mw_request_id::current_request_id;
+use crate::web::validation::FieldError;
+use crate::{data_access, request_context, security};
+use axum::http::header::RETRY_AFTER;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+// -----------------------------------------------------------------------------
+// Error handling
+// -----------------------------------------------------------------------------
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, Serialize)]
+pub enum Error {
+    // Authentication errors
+    AuthFailNoToken,
+    AuthFailTokenWrongFormat,
+    AuthFailUserNotFound,
+    AuthFailInvalidToken,
+    AuthFailRootContext,
+    // Login errors
+    LoginFailUsernameNotFound,
+    LoginFailUserHasNoPwd {
+        user_id: i64,
+    },
+    LoginFailPwdNotMatching {
+        user_id: i64,
+    },
+    // Request parameter errors
+    PaginationLimitOutOfRange(i64),
+    PaginationNegativeOffset(i64),
+    InvalidCursor(String),
+    InvalidTimestamp(String),
+    InvalidIdList(String),
+    InvalidExpand(String),
+    /// The body refers to a row (*eg* a subregion's region) that does not exist.
+    InvalidReference {
+        entity: &'static str,
+        id: i64,
+    },
+    InvalidUnits(String),
+    UnknownFields(Vec<String>),
+    // Request body errors
+    InvalidJson(String),
+    Validation(Vec<FieldError>),
+    PayloadTooLarge,
+    // Routing errors
+    RouteNotFound(String),
+    // Static asset errors
+    AssetNotFound,
+    // Import/export errors
+    ImportUnsupportedFormat,
+    ImportInvalidUpload(String),
+    CsvExportFail(String),
+    // Throttling errors
+    RateLimited {
+        retry_after_seconds: u64,
+    },
+    // Availability errors
+    Maintenance {
+        retry_after_seconds: u64,
+    },
+    // Wrapped errors
+    DataAccess(data_access::Error),
+    RequestContext(request_context::Error),
+    Security(security::Error),
+}
+
+/// The HTTP status for each data access error is decided in `status_code`:
+/// `EntityNotFound` is a 404, and `FailedToCreatePool`, `Sqlx` and `Security` are
+/// 500s. A `Security` error here comes from hashing a password on write, which is
+/// a server fault; authentication failures are reported by `mw_auth` as 401s.
+impl From<data_access::Error> for Error {
+    fn from(err: data_access::Error) -> Self {
+        Self::DataAccess(err)
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(err: csv::Error) -> Self {
+        Self::CsvExportFail(err.to_string())
+    }
+}
+
+impl From<request_context::Error> for Error {
+    fn from(err: request_context::Error) -> Self {
+        Self::RequestContext(err)
+    }
+}
+
+impl From<security::Error> for Error {
+    fn from(err: security::Error) -> Self {
+        Self::Security(err)
+    }
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
+        write!(fmt, "{self:?}")
+    }
+}
+
+impl std::error::Error for Error {}
+
+// -----------------------------------------------------------------------------
+// Axum response conversion
+// -----------------------------------------------------------------------------
+
+impl Error {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::AuthFailNoToken
+            | Self::AuthFailTokenWrongFormat
+            | Self::AuthFailUserNotFound
+            | Self::AuthFailInvalidToken
+            | Self::AuthFailRootContext => StatusCode::UNAUTHORIZED,
+            Self::LoginFailUsernameNotFound
+            | Self::LoginFailUserHasNoPwd { .. }
+            | Self::LoginFailPwdNotMatching { .. } => StatusCode::UNAUTHORIZED,
+            Self::PaginationLimitOutOfRange(_)
+            | Self::PaginationNegativeOffset(_)
+            | Self::InvalidCursor(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::DataAccess(data_access::Error::TooManyIds { .. })
+            | Self::InvalidTimestamp(_)
+            | Self::InvalidIdList(_)
+            | Self::InvalidExpand(_)
+            | Self::InvalidReference { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::InvalidJson(_) | Self::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::RouteNotFound(_) | Self::AssetNotFound => StatusCode::NOT_FOUND,
+            Self::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            Self::Maintenance { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            Self::ImportUnsupportedFormat => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::ImportInvalidUpload(_) | Self::InvalidUnits(_) | Self::UnknownFields(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            Self::DataAccess(data_access::Error::EntityNotFound { .. }) => StatusCode::NOT_FOUND,
+            Self::DataAccess(data_access::Error::UniqueViolation(_))
+            | Self::DataAccess(data_access::Error::DuplicateId { .. })
+            | Self::DataAccess(data_access::Error::ForeignKeyViolation(_))
+            | Self::DataAccess(data_access::Error::StaleWrite { .. }) => StatusCode::CONFLICT,
+            Self::DataAccess(data_access::Error::BatchItemFailed { .. }) => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            Self::DataAccess(data_access::Error::KeplerianInconsistency { .. })
+            | Self::DataAccess(data_access::Error::ParentCycle { .. })
+            | Self::DataAccess(data_access::Error::UnboundOrbit { .. }) => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            Self::RequestContext(request_context::Error::InsufficientPrivilege { .. }) => {
+                StatusCode::FORBIDDEN
+            }
+            Self::RequestContext(request_context::Error::CannotUseRootContext) => {
+                StatusCode::UNAUTHORIZED
+            }
+            Self::CsvExportFail(_)
+            | Self::DataAccess(_)
+            | Self::RequestContext(_)
+            | Self::Security(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// A stable, machine-readable identifier for the kind of error, for clients
+    /// to branch on. Unlike the message, these must not change once published.
+    pub fn client_code(&self) -> &'static str {
+        match self {
+            Self::AuthFailNoToken
+            | Self::AuthFailTokenWrongFormat
+            | Self::AuthFailUserNotFound
+            | Self::AuthFailInvalidToken
+            | Self::AuthFailRootContext => "unauthenticated",
+            Self::LoginFailUsernameNotFound
+            | Self::LoginFailUserHasNoPwd { .. }
+            | Self::LoginFailPwdNotMatching { .. } => "invalid_credentials",
+            Self::PaginationLimitOutOfRange(_) | Self::PaginationNegativeOffset(_) => {
+                "invalid_pagination"
+            }
+            Self::InvalidCursor(_) => "invalid_cursor",
+            Self::DataAccess(data_access::Error::TooManyIds { .. }) => "batch_too_large",
+            Self::InvalidTimestamp(_) => "invalid_timestamp",
+            Self::InvalidIdList(_) => "invalid_ids",
+            Self::InvalidExpand(_) => "invalid_expand",
+            Self::InvalidReference { .. } => "invalid_reference",
+            Self::InvalidUnits(_) => "invalid_units",
+            Self::UnknownFields(_) => "unknown_fields",
+            Self::InvalidJson(_) => "invalid_json",
+            Self::Validation(_) => "validation_failed",
+            Self::PayloadTooLarge => "payload_too_large",
+            Self::RouteNotFound(_)
+            | Self::AssetNotFound
+            | Self::DataAccess(data_access::Error::EntityNotFound { .. }) => "not_found",
+            Self::RateLimited { .. } => "rate_limited",
+            Self::Maintenance { .. } => "maintenance",
+            Self::ImportUnsupportedFormat => "unsupported_format",
+            Self::ImportInvalidUpload(_) => "invalid_upload",
+            Self::DataAccess(data_access::Error::UniqueViolation(_))
+            | Self::DataAccess(data_access::Error::DuplicateId { .. })
+            | Self::DataAccess(data_access::Error::ForeignKeyViolation(_)) => "conflict",
+            Self::DataAccess(data_access::Error::StaleWrite { .. }) => "stale_write",
+            Self::DataAccess(data_access::Error::BatchItemFailed { .. }) => "batch_rejected",
+            Self::DataAccess(data_access::Error::KeplerianInconsistency { .. }) => {
+                "keplerian_inconsistency"
+            }
+            Self::DataAccess(data_access::Error::ParentCycle { .. }) => "parent_cycle",
+            Self::DataAccess(data_access::Error::UnboundOrbit { .. }) => "unbound_orbit",
+            Self::RequestContext(request_context::Error::InsufficientPrivilege { .. }) => {
+                "forbidden"
+            }
+            Self::RequestContext(request_context::Error::CannotUseRootContext) => "unauthenticated",
+            Self::CsvExportFail(_)
+            | Self::DataAccess(_)
+            | Self::RequestContext(_)
+            | Self::Security(_) => "internal",
+        }
+    }
+
+    /// The message sent to the client. This must *never* include the underlying
+    /// cause, as that may leak implementation details (SQL, table names, etc).
+    pub fn client_message(&self) -> String {
+        match self {
+            Self::AuthFailNoToken
+            | Self::AuthFailTokenWrongFormat
+            | Self::AuthFailUserNotFound
+            | Self::AuthFailInvalidToken
+            | Self::AuthFailRootContext => "authentication required".to_string(),
+            Self::LoginFailUsernameNotFound
+            | Self::LoginFailUserHasNoPwd { .. }
+            | Self::LoginFailPwdNotMatching { .. } => "invalid username or password".to_string(),
+            Self::PaginationLimitOutOfRange(_) | Self::PaginationNegativeOffset(_) => {
+                "invalid pagination parameters".to_string()
+            }
+            // NOTE: the value is the client's own input, so is safe to return.
+            Self::InvalidCursor(value) => {
+                format!("invalid cursor '{value}'; expected a `next_cursor` from a previous page")
+            }
+            Self::DataAccess(data_access::Error::TooManyIds { limit }) => {
+                format!("too many ids in the batch; at most {limit} are allowed")
+            }
+            // NOTE: the value is the client's own input, so is safe to return.
+            Self::InvalidTimestamp(value) => {
+                format!("invalid timestamp '{value}'; expected RFC 3339")
+            }
+            Self::InvalidIdList(value) => {
+                format!("invalid id list '{value}'; expected comma-separated integers")
+            }
+            Self::InvalidExpand(value) => {
+                format!("cannot expand '{value}'; expected 'region' or 'subregion'")
+            }
+            Self::InvalidReference { entity, id } => format!("no {entity} with id {id}"),
+            Self::InvalidUnits(value) => {
+                format!("unknown units '{value}'; expected 'km', 'au' or 'earth_radii'")
+            }
+            Self::UnknownFields(fields) => format!("unknown field(s): {}", fields.join(", ")),
+            // NOTE: the reason describes the client's own body, so is safe to return.
+            Self::InvalidJson(reason) => format!("invalid JSON body: {reason}"),
+            Self::Validation(errors) => {
+                format!("{} invalid field(s); see `fields`", errors.len())
+            }
+            Self::PayloadTooLarge => "request body is too large".to_string(),
+            Self::RouteNotFound(_)
+            | Self::AssetNotFound
+            | Self::DataAccess(data_access::Error::EntityNotFound { .. }) => {
+                "resource not found".to_string()
+            }
+            Self::RateLimited { retry_after_seconds } => {
+                format!("too many requests; retry after {retry_after_seconds} seconds")
+            }
+            Self::Maintenance { retry_after_seconds } => {
+                format!("down for maintenance; retry after {retry_after_seconds} seconds")
+            }
+            Self::ImportUnsupportedFormat => "upload must be CSV or JSON".to_string(),
+            // NOTE: the reason describes the client's own upload, so is safe to return.
+            Self::ImportInvalidUpload(reason) => format!("invalid upload: {reason}"),
+            Self::DataAccess(data_access::Error::UniqueViolation(_)) => {
+                "resource already exists".to_string()
+            }
+            Self::DataAccess(data_access::Error::DuplicateId { id, .. }) => {
+                format!("id {id} is already taken")
+            }
+            Self::DataAccess(data_access::Error::ForeignKeyViolation(_)) => {
+                "resource references, or is referenced by, another resource".to_string()
+            }
+            Self::DataAccess(data_access::Error::StaleWrite { .. }) => {
+                "stale write: the resource has been modified since it was read".to_string()
+            }
+            // NOTE: only the index is reported; the cause may contain SQL.
+            Self::DataAccess(data_access::Error::BatchItemFailed { index, .. }) => {
+                format!("item {index} of the batch was rejected; nothing was saved")
+            }
+            Self::DataAccess(data_access::Error::KeplerianInconsistency {
+                expected_days,
+                supplied_days,
+            }) => format!(
+                "orbital period of {supplied_days:.2} days is inconsistent with the expected {expected_days:.2} days"
+            ),
+            Self::DataAccess(data_access::Error::ParentCycle { .. }) => {
+                "a body cannot orbit itself or one of its own satellites".to_string()
+            }
+            Self::DataAccess(data_access::Error::UnboundOrbit { eccentricity }) => format!(
+                "an orbit with eccentricity {eccentricity:.3} is unbound, so has no position"
+            ),
+            Self::RequestContext(request_context::Error::InsufficientPrivilege { .. }) => {
+                "insufficient privileges".to_string()
+            }
+            Self::RequestContext(request_context::Error::CannotUseRootContext) => {
+                "authentication required".to_string()
+            }
+            Self::CsvExportFail(_)
+            | Self::DataAccess(_)
+            | Self::RequestContext(_)
+            | Self::Security(_) => "internal server error".to_string(),
+        }
+    }
+
+    /// The JSON body sent to the client: `{"error": {"code": ..., "message": ...}}`,
+    /// plus a `request_id` for clients to quote when the error occurs mid-request,
+    /// and the `[{"field": ..., "message": ...}]` list for a `Validation` error.
+    pub fn client_body(&self) -> Value {
+        let mut body = json!({
+            "error": {
+                "code": self.client_code(),
+                "message": self.client_message(),
+            }
+        });
+        if let Self::Validation(errors) = self {
+            body["error"]["fields"] = json!(errors);
+        }
+        if let Some(request_id) = current_request_id() {
+            body["error"]["request_id"] = json!(request_id);
+        }
+
+        body
+    }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        // NOTE: the full error is logged; only the client-safe message is returned.
+        tracing::error!("{self}");
+
+        let mut res = (self.status_code(), Json(self.client_body())).into_response();
+        if let Self::RateLimited {
+            retry_after_seconds,
+        }
+        | Self::Maintenance {
+            retry_after_seconds,
+        } = self
+        {
+            res.headers_mut()
+                .insert(RETRY_AFTER, retry_after_seconds.into());
+        }
+
+        res
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_body_for_each_variant() {
+        let not_found = || data_access::Error::EntityNotFound {
+            entity: "celestial_region",
+            id: 1,
+        };
+        let cases = [
+            (Error::AuthFailNoToken, 401, "unauthenticated"),
+            (Error::AuthFailTokenWrongFormat, 401, "unauthenticated"),
+            (Error::AuthFailUserNotFound, 401, "unauthenticated"),
+            (Error::AuthFailInvalidToken, 401, "unauthenticated"),
+            (Error::AuthFailRootContext, 401, "unauthenticated"),
+            (Error::LoginFailUsernameNotFound, 401, "invalid_credentials"),
+            (
+                Error::LoginFailUserHasNoPwd { user_id: 1 },
+                401,
+                "invalid_credentials",
+            ),
+            (
+                Error::LoginFailPwdNotMatching { user_id: 1 },
+                401,
+                "invalid_credentials",
+            ),
+            (
+                Error::PaginationLimitOutOfRange(0),
+                422,
+                "invalid_pagination",
+            ),
+            (
+                Error::PaginationNegativeOffset(-1),
+                422,
+                "invalid_pagination",
+            ),
+            (
+                Error::InvalidCursor("bm9wZQ".to_string()),
+                422,
+                "invalid_cursor",
+            ),
+            (
+                Error::DataAccess(data_access::Error::TooManyIds { limit: 500 }),
+                422,
+                "batch_too_large",
+            ),
+            (
+                Error::InvalidTimestamp("yesterday".to_string()),
+                422,
+                "invalid_timestamp",
+            ),
+            (
+                Error::InvalidIdList("1,two".to_string()),
+                422,
+                "invalid_ids",
+            ),
+            (
+                Error::InvalidExpand("moons".to_string()),
+                422,
+                "invalid_expand",
+            ),
+            (
+                Error::InvalidReference {
+                    entity: "celestial_region",
+                    id: 1,
+                },
+                422,
+                "invalid_reference",
+            ),
+            (
+                Error::InvalidUnits("furlongs".to_string()),
+                400,
+                "invalid_units",
+            ),
+            (
+                Error::UnknownFields(vec!["colour".to_string()]),
+                400,
+                "unknown_fields",
+            ),
+            (
+                Error::InvalidJson("missing field `name`".to_string()),
+                422,
+                "invalid_json",
+            ),
+            (
+                Error::Validation(vec![FieldError {
+                    field: "name".to_string(),
+                    message: "must not be empty".to_string(),
+                }]),
+                422,
+                "validation_failed",
+            ),
+            (Error::PayloadTooLarge, 413, "payload_too_large"),
+            (
+                Error::RouteNotFound("/api/v9".to_string()),
+                404,
+                "not_found",
+            ),
+            (Error::AssetNotFound, 404, "not_found"),
+            (Error::ImportUnsupportedFormat, 415, "unsupported_format"),
+            (
+                Error::ImportInvalidUpload("empty".to_string()),
+                400,
+                "invalid_upload",
+            ),
+            (Error::CsvExportFail("io".to_string()), 500, "internal"),
+            (
+                Error::RateLimited {
+                    retry_after_seconds: 1,
+                },
+                429,
+                "rate_limited",
+            ),
+            (
+                Error::Maintenance {
+                    retry_after_seconds: 60,
+                },
+                503,
+                "maintenance",
+            ),
+            (Error::DataAccess(not_found()), 404, "not_found"),
+            (
+                Error::DataAccess(data_access::Error::UniqueViolation("UNIQUE".to_string())),
+                409,
+                "conflict",
+            ),
+            (
+                Error::DataAccess(data_access::Error::DuplicateId {
+                    entity: "celestial_region",
+                    id: 1,
+                }),
+                409,
+                "conflict",
+            ),
+            (
+                Error::DataAccess(data_access::Error::ForeignKeyViolation(
+                    "FOREIGN KEY".to_string(),
+                )),
+                409,
+                "conflict",
+            ),
+            (
+                Error::DataAccess(data_access::Error::StaleWrite {
+                    entity: "celestial_region",
+                    id: 1,
+                }),
+                409,
+                "stale_write",
+            ),
+            (
+                Error::DataAccess(data_access::Error::BatchItemFailed {
+                    index: 0,
+                    cause: Box::new(not_found()),
+                }),
+                422,
+                "batch_rejected",
+            ),
+            (
+                Error::DataAccess(data_access::Error::KeplerianInconsistency {
+                    expected_days: 365.0,
+                    supplied_days: 100.0,
+                }),
+                422,
+                "keplerian_inconsistency",
+            ),
+            (
+                Error::DataAccess(data_access::Error::ParentCycle {
+                    id: 1,
+                    parent_id: 2,
+                }),
+                422,
+                "parent_cycle",
+            ),
+            (
+                Error::DataAccess(data_access::Error::UnboundOrbit { eccentricity: 1.0 }),
+                422,
+                "unbound_orbit",
+            ),
+            (
+                Error::RequestContext(request_context::Error::InsufficientPrivilege {
+                    required: crate::Role::Editor,
+                    actual: crate::Role::Viewer,
+                }),
+                403,
+                "forbidden",
+            ),
+            (
+                Error::RequestContext(request_context::Error::CannotUseRootContext),
+                401,
+                "unauthenticated",
+            ),
+            (
+                Error::DataAccess(data_access::Error::FailedToCreatePool("secret".to_string())),
+                500,
+                "internal",
+            ),
+        ];
+
+        for (error, status, code) in cases {
+            assert_eq!(error.status_code().as_u16(), status, "{error}");
+            let body = error.client_body();
+            assert_eq!(body["error"]["code"], code, "{error}");
+            assert_eq!(body["error"]["message"], error.client_message(), "{error}");
+            assert_eq!(body.as_object().unwrap().len(), 1);
+            let keys = if matches!(error, Error::Validation(_)) {
+                3
+            } else {
+                2
+            };
+            assert_eq!(body["error"].as_object().unwrap().len(), keys);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_data_access_errors_through_a_handler() {
+        use axum::extract::Path;
+        use axum::routing::get;
+        use axum::Router;
+        use axum_test_helper::TestClient;
+
+        // A handler that fails with the requested data access error, via `?`.
+        async fn fail(Path(variant): Path<String>) -> Result<()> {
+            let err = match variant.as_str() {
+                "not_found" => data_access::Error::EntityNotFound {
+                    entity: "celestial_body",
+                    id: 1,
+                },
+                "pool" => data_access::Error::FailedToCreatePool("timed out".to_string()),
+                _ => data_access::Error::Sqlx(sqlx::Error::PoolClosed),
+            };
+            Err(err)?
+        }
+        let client = TestClient::new(Router::new().route("/:variant", get(fail)));
+
+        for (variant, status, code) in [
+            ("not_found", 404, "not_found"),
+            ("pool", 500, "internal"),
+            ("sqlx", 500, "internal"),
+        ] {
+            let res = client.get(&format!("/{variant}")).send().await;
+            assert_eq!(res.status().as_u16(), status, "{variant}");
+            let body: Value = res.json().await;
+            assert_eq!(body["error"]["code"], code, "{variant}");
+            assert!(!body.to_string().contains("timed out"));
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use axum::http::header::{ETAG, IF_NONE_MATCH};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+// -----------------------------------------------------------------------------
+// Conditional GETs
+// -----------------------------------------------------------------------------
+
+/// Respond with `value` as JSON, tagged with a weak ETag derived from its
+/// serialization; or, if the request's `If-None-Match` already has that tag,
+/// with a bodiless 304 instead.
+///
+/// NOTE: hashing the body (rather than using `updated_at`) means any change to
+///       the resource changes the tag, even two updates within the same second.
+pub fn conditional_json<T: Serialize>(headers: &HeaderMap, value: T) -> Response {
+    let body = serde_json::to_vec(&value).unwrap_or_default();
+    let etag = weak_etag(&body);
+    let etag_header = HeaderValue::from_str(&etag).expect("an ETag is always a valid header");
+
+    if if_none_match(headers, &etag) {
+        return (StatusCode::NOT_MODIFIED, [(ETAG, etag_header)]).into_response();
+    }
+
+    ([(ETAG, etag_header)], Json(value)).into_response()
+}
+
+fn weak_etag(body: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+
+    format!("W/\"{:016x}\"", hasher.finish())
+}
+
+/// Whether any tag in `If-None-Match` matches `etag`. Comparison is weak, so a
+/// `W/` prefix on either side is ignored; `*` matches anything.
+fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+
+    headers
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|tag| tag.trim() == "*" || opaque(tag) == opaque(etag))
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(if_none_match: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(IF_NONE_MATCH, HeaderValue::from_str(if_none_match).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_if_none_match() {
+        let etag = weak_etag(b"{}");
+
+        assert!(!if_none_match(&HeaderMap::new(), &etag));
+        assert!(if_none_match(&headers(&etag), &etag));
+        assert!(if_none_match(
+            &headers(etag.trim_start_matches("W/")),
+            &etag
+        ));
+        assert!(if_none_match(
+            &headers(&format!("W/\"other\", {etag}")),
+            &etag
+        ));
+        assert!(if_none_match(&headers("*"), &etag));
+        assert!(!if_none_match(&headers("W/\"other\""), &etag));
+    }
+
+    #[test]
+    fn test_etag_changes_with_the_body() {
+        assert_eq!(weak_etag(b"{\"a\":1}"), weak_etag(b"{\"a\":1}"));
+        assert_ne!(weak_etag(b"{\"a\":1}"), weak_etag(b"{\"a\":2}"));
+    }
+}
@@ -0,0 +1,262 @@
+use crate::web::mw_body_limit::LimitedBody;
+use crate::web::{Error, Result};
+use axum::body::Bytes;
+use axum::extract::{FromRequest, Multipart};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{Request, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+// -----------------------------------------------------------------------------
+// Types
+// -----------------------------------------------------------------------------
+
+/// Query string for the import endpoints: `?strict=true` rejects the whole file
+/// if any row fails, rather than importing the rows that succeed.
+#[derive(Debug, Default, Deserialize)]
+pub struct ImportParams {
+    #[serde(default)]
+    pub strict: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportFormat {
+    Csv,
+    Json,
+}
+
+impl ImportFormat {
+    fn from_content_type(content_type: &str) -> Option<Self> {
+        // NOTE: only the essence matters, so parameters such as `charset` are dropped.
+        match content_type.split(';').next()?.trim() {
+            "text/csv" => Some(Self::Csv),
+            "application/json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    fn from_file_name(file_name: &str) -> Option<Self> {
+        let file_name = file_name.to_ascii_lowercase();
+        if file_name.ends_with(".csv") {
+            Some(Self::Csv)
+        } else if file_name.ends_with(".json") {
+            Some(Self::Json)
+        } else {
+            None
+        }
+    }
+}
+
+/// A row that could not be imported. `line` is the line in a CSV file, or the
+/// 1-based position in a JSON array.
+#[derive(Debug, Serialize)]
+pub struct ImportRowError {
+    pub line: usize,
+    pub reason: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub imported: usize,
+    pub errors: Vec<ImportRowError>,
+}
+
+impl ImportReport {
+    pub fn reject(&mut self, line: usize, reason: impl Into<String>) {
+        self.errors.push(ImportRowError {
+            line,
+            reason: reason.into(),
+        });
+    }
+}
+
+/// A parsed row, tagged with its line, or the reason it could not be parsed.
+pub type ImportRow<T> = (usize, core::result::Result<T, String>);
+
+// -----------------------------------------------------------------------------
+// Reading
+// -----------------------------------------------------------------------------
+
+/// Read an uploaded file: either the first file of a `multipart/form-data` body,
+/// or the raw body itself. The format comes from the content type, falling back
+/// to the file name's extension for multipart uploads.
+pub async fn read_upload(req: Request<LimitedBody>) -> Result<(ImportFormat, Bytes)> {
+    let content_type = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+
+    if !content_type.starts_with("multipart/form-data") {
+        let format =
+            ImportFormat::from_content_type(&content_type).ok_or(Error::ImportUnsupportedFormat)?;
+        let bytes = Bytes::from_request(req, &()).await.map_err(|err| {
+            if err.status() == StatusCode::PAYLOAD_TOO_LARGE {
+                Error::PayloadTooLarge
+            } else {
+                Error::ImportInvalidUpload(err.to_string())
+            }
+        })?;
+
+        return Ok((format, bytes));
+    }
+
+    let mut multipart = Multipart::from_request(req, &())
+        .await
+        .map_err(|err| Error::ImportInvalidUpload(err.to_string()))?;
+    let field = multipart
+        .next_field()
+        .await
+        .map_err(|err| Error::ImportInvalidUpload(err.to_string()))?
+        .ok_or_else(|| Error::ImportInvalidUpload("no file was uploaded".to_string()))?;
+    let format = field
+        .content_type()
+        .and_then(ImportFormat::from_content_type)
+        .or_else(|| field.file_name().and_then(ImportFormat::from_file_name))
+        .ok_or(Error::ImportUnsupportedFormat)?;
+    let bytes = field
+        .bytes()
+        .await
+        .map_err(|err| Error::ImportInvalidUpload(err.to_string()))?;
+
+    Ok((format, bytes))
+}
+
+// -----------------------------------------------------------------------------
+// Parsing
+// -----------------------------------------------------------------------------
+
+/// Parse every row of the file. A row that fails to parse is returned as an
+/// error for that row; only a file that cannot be read at all is an `Err`.
+pub fn parse_rows<T: DeserializeOwned>(
+    format: ImportFormat,
+    bytes: &[u8],
+) -> Result<Vec<ImportRow<T>>> {
+    match format {
+        ImportFormat::Csv => parse_csv_rows(bytes),
+        ImportFormat::Json => parse_json_rows(bytes),
+    }
+}
+
+fn parse_csv_rows<T: DeserializeOwned>(bytes: &[u8]) -> Result<Vec<ImportRow<T>>> {
+    let mut reader = csv::Reader::from_reader(bytes);
+    let headers = reader
+        .headers()
+        .map_err(|err| Error::ImportInvalidUpload(err.to_string()))?
+        .clone();
+    let mut rows = Vec::new();
+    let mut record = csv::StringRecord::new();
+
+    loop {
+        match reader.read_record(&mut record) {
+            Ok(false) => break,
+            Ok(true) => {
+                let line = record.position().map_or(0, |position| position.line()) as usize;
+                let row = record
+                    .deserialize(Some(&headers))
+                    .map_err(|err| err.to_string());
+                rows.push((line, row));
+            }
+            // NOTE: the reader skips past a malformed record, so carry on with the next.
+            Err(err) if !err.is_io_error() => {
+                let line = err.position().map_or(0, |position| position.line()) as usize;
+                rows.push((line, Err(err.to_string())));
+            }
+            Err(err) => return Err(Error::ImportInvalidUpload(err.to_string())),
+        }
+    }
+
+    Ok(rows)
+}
+
+fn parse_json_rows<T: DeserializeOwned>(bytes: &[u8]) -> Result<Vec<ImportRow<T>>> {
+    let values: Vec<serde_json::Value> =
+        serde_json::from_slice(bytes).map_err(|err| Error::ImportInvalidUpload(err.to_string()))?;
+    let rows = values
+        .into_iter()
+        .enumerate()
+        .map(|(index, value)| {
+            (
+                index + 1,
+                serde_json::from_value(value).map_err(|err| err.to_string()),
+            )
+        })
+        .collect();
+
+    Ok(rows)
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Row {
+        name: String,
+        size: f64,
+    }
+
+    #[test]
+    fn test_parse_csv_rows() -> Result<()> {
+        let csv = "name,size\na,1.5\nb,big\nc\nd,4\n";
+
+        let rows: Vec<ImportRow<Row>> = parse_rows(ImportFormat::Csv, csv.as_bytes())?;
+
+        let lines: Vec<usize> = rows.iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, [2, 3, 4, 5]);
+        assert_eq!(
+            rows[0].1,
+            Ok(Row {
+                name: "a".to_string(),
+                size: 1.5
+            })
+        );
+        assert!(rows[1].1.is_err());
+        assert!(rows[2].1.is_err());
+        assert!(rows[3].1.is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_json_rows() -> Result<()> {
+        let json = r#"[{"name": "a", "size": 1.5}, {"name": "b"}]"#;
+
+        let rows: Vec<ImportRow<Row>> = parse_rows(ImportFormat::Json, json.as_bytes())?;
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].0, 1);
+        assert!(rows[0].1.is_ok());
+        assert_eq!(rows[1].0, 2);
+        assert!(rows[1].1.is_err());
+
+        // Not an array at all.
+        let result: Result<Vec<ImportRow<Row>>> = parse_rows(ImportFormat::Json, b"{}");
+        assert!(matches!(result, Err(Error::ImportInvalidUpload(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_format_detection() {
+        assert_eq!(
+            ImportFormat::from_content_type("text/csv; charset=utf-8"),
+            Some(ImportFormat::Csv)
+        );
+        assert_eq!(
+            ImportFormat::from_content_type("application/json"),
+            Some(ImportFormat::Json)
+        );
+        assert_eq!(ImportFormat::from_content_type("text/plain"), None);
+        assert_eq!(
+            ImportFormat::from_file_name("Bodies.CSV"),
+            Some(ImportFormat::Csv)
+        );
+        assert_eq!(ImportFormat::from_file_name("bodies.txt"), None);
+    }
+}
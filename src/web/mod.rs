@@ -0,0 +1,159 @@
+//! HTTP layer for the application.
+//!
+//! Rules:
+//! 1. Everything Axum-specific (routing, extractors, `IntoResponse`) lives in this module.
+//! 2. Handlers are thin: they unpack the request, call into the data access layer, and
+//!    pack the response. No SQL is written here.
+//! 3. Errors from other layers are converted into the web `Error` via `From` impls, so
+//!    handlers can use the `?` operator throughout.
+mod cors;
+mod error;
+mod etag;
+mod import;
+mod mw_auth;
+mod mw_body_limit;
+mod mw_maintenance;
+mod mw_rate_limit;
+mod mw_request_id;
+mod mw_request_log;
+#[cfg(feature = "openapi")]
+mod openapi;
+mod pagination;
+mod prometheus;
+mod routes_admin;
+mod routes_celestial_body;
+mod routes_celestial_region;
+mod routes_celestial_subregion;
+mod routes_health;
+mod routes_login;
+mod routes_metrics;
+mod routes_orrery;
+mod routes_static;
+mod routes_versions;
+mod routes_ws;
+mod search;
+mod units;
+mod validation;
+
+pub use self::error::{Error, Result};
+use crate::config::get_config;
+use crate::data_access::DataAccessManager;
+pub use crate::web::mw_maintenance::Maintenance;
+use crate::web::mw_rate_limit::RateLimiter;
+pub use crate::web::routes_health::Readiness;
+pub use crate::web::routes_versions::ApiVersion;
+use crate::{RequestContext, SystemContext};
+use axum::extract::FromRef;
+use axum::{middleware, Router};
+
+// -----------------------------------------------------------------------------
+// Application state
+// -----------------------------------------------------------------------------
+
+/// The context public reads are served with. They need no token, so there is no
+/// user to act for, and the system reads on the client's behalf.
+fn public_read_context() -> RequestContext {
+    SystemContext.into()
+}
+
+/// Shared state handed to every handler. `FromRef` allows handlers to extract
+/// the individual parts (for example `State<DataAccessManager>`) directly.
+#[derive(Clone, FromRef)]
+pub struct AppState {
+    pub dam: DataAccessManager,
+    pub rate_limiter: RateLimiter,
+    pub readiness: Readiness,
+    pub maintenance: Maintenance,
+}
+
+impl AppState {
+    /// The state for the given data access manager, with the configured rate limits.
+    /// It is not yet ready for traffic (see `Readiness`), nor in maintenance.
+    pub fn new(dam: DataAccessManager) -> Self {
+        let config = get_config();
+        let rate_limiter = RateLimiter::new(
+            config.RATE_LIMIT_READS_PER_MINUTE,
+            config.RATE_LIMIT_WRITES_PER_MINUTE,
+        );
+
+        Self {
+            dam,
+            rate_limiter,
+            readiness: Readiness::default(),
+            maintenance: Maintenance::default(),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Routing
+// -----------------------------------------------------------------------------
+
+pub fn construct_routes(state: AppState) -> Router {
+    let config = get_config();
+    // NOTE: the health checks and metrics sit outside `/api`, where probes and
+    //       scrapers expect to find them.
+    let routes = Router::new()
+        .merge(routes_health::health_routes())
+        .merge(routes_metrics::metrics_routes())
+        .merge(routes_static::static_routes(&config.ASSETS_FOLDER))
+        .nest("/api", api_routes(state.clone()));
+    #[cfg(feature = "openapi")]
+    let routes = routes.merge(openapi::openapi_routes());
+    // NOTE: the last layer added runs first, so requests are logged within the
+    //       request id's span.
+    let mut routes = routes
+        .layer(middleware::from_fn(mw_request_log::mw_request_log))
+        .layer(middleware::from_fn(mw_request_id::mw_request_id));
+    if let Some(cors) = cors::cors_layer(&config.ALLOWED_ORIGINS) {
+        routes = routes.layer(cors);
+    }
+
+    routes.with_state(state)
+}
+
+/// Every supported version is nested under `/api/<version>`, alongside the
+/// version listing. Anything else under `/api` is a JSON 404, so an unknown
+/// version is reported the same way as any other missing route.
+pub fn api_routes(state: AppState) -> Router<AppState> {
+    let mut routes = Router::new().merge(routes_versions::versions_routes());
+    for version in ApiVersion::ALL {
+        let prefix = format!("/{}", version.as_str());
+        routes = routes.nest(&prefix, version_routes(version, state.clone()));
+    }
+
+    routes.fallback(routes_versions::api_not_found)
+}
+
+/// The routes making up one version of the API. A breaking change gets a new
+/// `ApiVersion` variant and arm here, while older versions keep working.
+///
+/// NOTE: `route_layer` only wraps the routes added before it, so the login and
+///       token routes, which authenticate by other means, are merged afterwards,
+///       as are the maintenance toggles after `mw_maintenance`. The last layer
+///       added runs first: auth, then rate limiting, then maintenance.
+pub fn version_routes(version: ApiVersion, state: AppState) -> Router<AppState> {
+    match version {
+        ApiVersion::V1 => Router::new()
+            .merge(routes_celestial_body::body_routes())
+            .merge(routes_celestial_region::region_routes())
+            .merge(routes_celestial_subregion::subregion_routes())
+            .merge(routes_admin::admin_routes())
+            .merge(routes_orrery::orrery_routes())
+            .merge(routes_ws::ws_routes())
+            .route_layer(middleware::from_fn_with_state(
+                state.maintenance.clone(),
+                mw_maintenance::mw_maintenance,
+            ))
+            .merge(routes_admin::maintenance_routes())
+            .route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                mw_rate_limit::mw_rate_limit,
+            ))
+            .route_layer(middleware::from_fn_with_state(
+                state,
+                mw_auth::mw_require_auth,
+            ))
+            .merge(routes_login::login_routes()),
+    }
+}
@@ -0,0 +1,186 @@
+use crate::data_access::model::user::{Server, UserAuth};
+use crate::security::{self, Token};
+use crate::web::{AppState, Error, Result};
+use crate::{RequestContext, Role, SystemContext, UserId};
+use axum::async_trait;
+use axum::extract::{FromRequestParts, State};
+use axum::http::header::AUTHORIZATION;
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, Method, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+
+// -----------------------------------------------------------------------------
+// RequestContext extractor
+// -----------------------------------------------------------------------------
+
+const BEARER_PREFIX: &str = "Bearer ";
+
+/// Parse the token out of an `Authorization: Bearer <token>` header.
+pub(in crate::web) fn bearer_token(headers: &HeaderMap) -> Result<Token> {
+    let token = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix(BEARER_PREFIX))
+        .ok_or(Error::AuthFailNoToken)?;
+
+    token.parse().map_err(|_| Error::AuthFailTokenWrongFormat)
+}
+
+/// Build the context from an `Authorization: Bearer <token>` header. The token
+/// identifies the user by username, and is validated against that user's
+/// `token_salt`. Any failure is rejected with a 401.
+///
+/// If `mw_require_auth` has already resolved the context, that one is reused.
+#[async_trait]
+impl FromRequestParts<AppState> for RequestContext {
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self> {
+        if let Some(ctx) = parts.extensions.get::<RequestContext>() {
+            return Ok(ctx.clone());
+        }
+
+        let token = bearer_token(&parts.headers)?;
+
+        // NOTE: the user lookup is a system operation, hence the root context.
+        let user: UserAuth = Server::read_by_username(
+            &RequestContext::from(SystemContext),
+            &state.dam,
+            &token.ident,
+        )
+        .await?
+        .ok_or(Error::AuthFailUserNotFound)?;
+        security::validate_web_token(&token, &user.token_salt)
+            .map_err(|_| Error::AuthFailInvalidToken)?;
+
+        let role: Role = user.role.parse()?;
+
+        // NOTE: a `UserId` is never the root id, so external callers can never act as root.
+        let user_id = UserId::new(user.id).map_err(|_| Error::AuthFailRootContext)?;
+
+        Ok(RequestContext::new(user_id, role))
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Middleware
+// -----------------------------------------------------------------------------
+
+/// Reject unauthenticated mutations (anything but `GET`, `HEAD` and `OPTIONS`)
+/// with a 401 before the handler, or any of its other extractors, runs. The
+/// resolved `RequestContext` is stored in the request extensions.
+pub async fn mw_require_auth<B>(
+    State(state): State<AppState>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Result<Response> {
+    if matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return Ok(next.run(req).await);
+    }
+
+    let (mut parts, body) = req.into_parts();
+    let ctx = RequestContext::from_request_parts(&mut parts, &state).await?;
+    parts.extensions.insert(ctx);
+
+    Ok(next.run(Request::from_parts(parts, body)).await)
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils::{create_test_user_authorization, initialise_test_environment};
+    use crate::web::construct_routes;
+    use anyhow::Result;
+    use axum::http::StatusCode;
+    use axum_test_helper::TestClient;
+    use serde_json::json;
+    use serial_test::serial;
+
+    async fn extract(
+        state: &AppState,
+        authorization: Option<&str>,
+    ) -> super::Result<RequestContext> {
+        let mut request = Request::builder().uri("/");
+        if let Some(authorization) = authorization {
+            request = request.header(AUTHORIZATION, authorization);
+        }
+        let (mut parts, _) = request.body(()).unwrap().into_parts();
+
+        RequestContext::from_request_parts(&mut parts, state).await
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_extract_request_context() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let id = Server::create_for_test(&ctx, &dam, "test_extract_ctx", "welcome", Role::Editor)
+            .await?;
+        let user: UserAuth = Server::read(&ctx, &dam, id).await?;
+        let state = AppState::new(dam);
+
+        // Valid, hand-minted token.
+        let token = security::generate_web_token(&user.username, &user.token_salt)?;
+        let extracted = extract(&state, Some(&format!("Bearer {token}"))).await?;
+        assert_eq!(extracted.user_id()?.get(), id);
+        assert_eq!(extracted.role(), Role::Editor);
+
+        // Missing header.
+        let result = extract(&state, None).await;
+        assert!(matches!(result, Err(Error::AuthFailNoToken)));
+
+        // Malformed token.
+        let result = extract(&state, Some("Bearer not-a-token")).await;
+        assert!(matches!(result, Err(Error::AuthFailTokenWrongFormat)));
+
+        // Token signed with the wrong salt.
+        let token = security::generate_web_token(&user.username, "some-other-salt")?;
+        let result = extract(&state, Some(&format!("Bearer {token}"))).await;
+        assert!(matches!(result, Err(Error::AuthFailInvalidToken)));
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_mutations_require_auth() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let auth = create_test_user_authorization(&dam, "test_require_auth", Role::Editor).await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        // Unauthenticated reads are public.
+        let res = client.get("/api/v1/regions").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // Unauthenticated writes are rejected, before the body is even parsed.
+        let res = client
+            .post("/api/v1/regions")
+            .json(&json!({ "name": "test_require_auth" }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        let res = client
+            .post("/api/v1/regions")
+            .header("Content-Type", "application/json")
+            .body("not json")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        // Authenticated writes reach the handler.
+        let res = client
+            .post("/api/v1/regions")
+            .header("Authorization", &auth)
+            .json(&json!({ "name": "test_require_auth" }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+use crate::web::Error;
+use axum::body::Body;
+use axum::extract::DefaultBodyLimit;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::StatusCode;
+use axum::middleware;
+use axum::response::{IntoResponse, Response};
+use axum::routing::MethodRouter;
+use http_body::Limited;
+use tower_http::limit::RequestBodyLimitLayer;
+
+/// The request body seen by handlers behind `limit_body`, for those that take the
+/// whole `Request` rather than an extractor.
+pub type LimitedBody = Limited<Body>;
+
+// -----------------------------------------------------------------------------
+// Middleware
+// -----------------------------------------------------------------------------
+
+/// Refuse request bodies over `max_bytes` on `route` with a 413, in the usual
+/// error format. Applied per route, so that imports can be allowed more than
+/// ordinary writes (see `MAX_REQUEST_BODY_BYTES` and `MAX_IMPORT_BODY_BYTES`).
+///
+/// NOTE: Axum's own default limit (2 MiB) is disabled on these routes, so that
+///       the configured one is the only one that applies.
+pub fn limit_body<S>(route: MethodRouter<S, LimitedBody>, max_bytes: usize) -> MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    route
+        .layer(DefaultBodyLimit::disable())
+        .layer(RequestBodyLimitLayer::new(max_bytes))
+        .layer(middleware::map_response(payload_too_large_as_json))
+}
+
+/// The limit layer answers a body that is declared too large (by its
+/// `Content-Length`) with a plain-text 413, as do Axum's extractors for one that
+/// turns out to be too large while being read. Either is replaced with the JSON
+/// error; every other response is left alone.
+async fn payload_too_large_as_json(res: Response) -> Response {
+    let is_json = res
+        .headers()
+        .get(CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"application/json"));
+    if res.status() != StatusCode::PAYLOAD_TOO_LARGE || is_json {
+        return res;
+    }
+
+    Error::PayloadTooLarge.into_response()
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::_dev_utils::{create_test_user_authorization, initialise_test_environment};
+    use crate::config::get_config;
+    use crate::web::{construct_routes, AppState};
+    use crate::Role;
+    use anyhow::Result;
+    use axum::http::StatusCode;
+    use axum_test_helper::TestClient;
+    use serde_json::Value;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_oversized_bodies_are_refused() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let auth = create_test_user_authorization(&dam, "test_body_limit", Role::Editor).await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+        let oversized = "x".repeat(get_config().MAX_REQUEST_BODY_BYTES + 1);
+
+        let res = client
+            .post("/api/v1/bodies")
+            .header("Authorization", &auth)
+            .header("Content-Type", "application/json")
+            .body(oversized.clone())
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body: Value = res.json().await;
+        assert_eq!(body["error"]["code"], "payload_too_large");
+
+        // Imports are allowed more, so this gets as far as the format check.
+        let res = client
+            .post("/api/v1/bodies/import")
+            .header("Authorization", &auth)
+            .header("Content-Type", "text/plain")
+            .body(oversized)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        Ok(())
+    }
+}
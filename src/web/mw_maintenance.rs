@@ -0,0 +1,130 @@
+use crate::config::get_config;
+use crate::web::{Error, Result};
+use axum::extract::State;
+use axum::http::{Method, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+// -----------------------------------------------------------------------------
+// Types
+// -----------------------------------------------------------------------------
+
+/// Whether the service is in maintenance, *eg* during a migration: writes are
+/// refused while reads keep working. Shared by every clone of the `AppState`, and
+/// flipped through `/admin/maintenance/{on,off}`.
+#[derive(Clone, Default)]
+pub struct Maintenance(Arc<AtomicBool>);
+
+impl Maintenance {
+    pub fn is_on(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub fn set(&self, on: bool) {
+        self.0.store(on, Ordering::Relaxed);
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Middleware
+// -----------------------------------------------------------------------------
+
+/// While in maintenance, reject writes (anything but `GET`, `HEAD` and `OPTIONS`)
+/// with a 503 and a `Retry-After` of `MAINTENANCE_RETRY_AFTER_SECONDS`.
+pub async fn mw_maintenance<B>(
+    State(maintenance): State<Maintenance>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Result<Response> {
+    let is_read = matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    if maintenance.is_on() && !is_read {
+        return Err(Error::Maintenance {
+            retry_after_seconds: get_config().MAINTENANCE_RETRY_AFTER_SECONDS,
+        });
+    }
+
+    Ok(next.run(req).await)
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::_dev_utils::{create_test_user_authorization, initialise_test_environment};
+    use crate::web::{construct_routes, AppState};
+    use crate::Role;
+    use anyhow::Result;
+    use axum::http::header::RETRY_AFTER;
+    use axum::http::StatusCode;
+    use axum_test_helper::TestClient;
+    use serde_json::{json, Value};
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_maintenance_blocks_writes_only() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let admin =
+            create_test_user_authorization(&dam, "test_maintenance_admin", Role::Admin).await;
+        let editor =
+            create_test_user_authorization(&dam, "test_maintenance_editor", Role::Editor).await;
+        let state = AppState::new(dam);
+        let maintenance = state.maintenance.clone();
+        let client = TestClient::new(construct_routes(state));
+        let create = || {
+            client
+                .post("/api/v1/bodies")
+                .header("Authorization", &editor)
+                .json(&json!({
+                    "name": "test_maintenance",
+                    "aphelion": 0.0,
+                    "perihelion": 0.0,
+                    "orbital_period": 0.0,
+                    "radius": 0.0,
+                    "mass": 0.0,
+                }))
+                .send()
+        };
+
+        let res = client
+            .post("/api/v1/admin/maintenance/on")
+            .header("Authorization", &editor)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        let res = client
+            .post("/api/v1/admin/maintenance/on")
+            .header("Authorization", &admin)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let status: Value = res.json().await;
+        assert_eq!(status["maintenance"], true);
+        // The flag is shared with the state the routes were built from.
+        assert!(maintenance.is_on());
+
+        let res = create().await;
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(res.headers().contains_key(RETRY_AFTER));
+        let body: Value = res.json().await;
+        assert_eq!(body["error"]["code"], "maintenance");
+        let res = client.get("/api/v1/bodies").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // Maintenance itself can still be turned off.
+        let res = client
+            .post("/api/v1/admin/maintenance/off")
+            .header("Authorization", &admin)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!maintenance.is_on());
+        assert_eq!(create().await.status(), StatusCode::OK);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,225 @@
+use crate::web::{AppState, Error, Result};
+use crate::RequestContext;
+use axum::extract::{ConnectInfo, State};
+use axum::http::{Method, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// -----------------------------------------------------------------------------
+// Types
+// -----------------------------------------------------------------------------
+
+/// Who a request is counted against: the authenticated user if there is one,
+/// otherwise the client's IP address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Requester {
+    User(i64),
+    Ip(IpAddr),
+    /// NOTE: only when no connection info is available (e.g. in tests), so all
+    ///       such requests share one bucket.
+    Unknown,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Access {
+    Read,
+    Write,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// Once this many buckets are held, idle (i.e. full) buckets are dropped before
+/// a new one is added, so the map cannot grow without bound.
+const MAX_BUCKETS: usize = 10_000;
+
+/// A token bucket per client and kind of access. Each bucket holds up to a
+/// minute's allowance, and refills continuously at that rate.
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    reads_per_minute: u32,
+    writes_per_minute: u32,
+    buckets: Arc<Mutex<HashMap<(Requester, Access), Bucket>>>,
+}
+
+impl RateLimiter {
+    pub fn new(reads_per_minute: u32, writes_per_minute: u32) -> Self {
+        Self {
+            reads_per_minute,
+            writes_per_minute,
+            buckets: Arc::default(),
+        }
+    }
+
+    /// Take a token from the client's bucket, or return how long until one is
+    /// available.
+    fn acquire(&self, requester: Requester, access: Access, now: Instant) -> Result<()> {
+        let per_minute = match access {
+            Access::Read => self.reads_per_minute,
+            Access::Write => self.writes_per_minute,
+        };
+        let capacity = f64::from(per_minute);
+        let per_second = capacity / 60.0;
+
+        // NOTE: a poisoned lock only means another request panicked mid-update;
+        //       the buckets are still usable.
+        let mut buckets = self.buckets.lock().unwrap_or_else(|err| err.into_inner());
+        if buckets.len() >= MAX_BUCKETS && !buckets.contains_key(&(requester, access)) {
+            buckets.retain(|_, bucket| {
+                now.duration_since(bucket.refilled_at) < Duration::from_secs(60)
+            });
+        }
+        let bucket = buckets.entry((requester, access)).or_insert(Bucket {
+            tokens: capacity,
+            refilled_at: now,
+        });
+
+        let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_second).min(capacity);
+        bucket.refilled_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else if per_second > 0.0 {
+            Err(Error::RateLimited {
+                retry_after_seconds: ((1.0 - bucket.tokens) / per_second).ceil() as u64,
+            })
+        } else {
+            Err(Error::RateLimited {
+                retry_after_seconds: 60,
+            })
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Middleware
+// -----------------------------------------------------------------------------
+
+/// Reject requests over the client's allowance with a 429 and a `Retry-After`
+/// header. Must run after `mw_require_auth`, so writes are counted per user;
+/// reads are public, so are counted per IP.
+pub async fn mw_rate_limit<B>(
+    State(state): State<AppState>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Result<Response> {
+    let user_id = req
+        .extensions()
+        .get::<RequestContext>()
+        .and_then(|ctx| ctx.user_id().ok());
+    let requester = if let Some(user_id) = user_id {
+        Requester::User(user_id.get())
+    } else if let Some(ConnectInfo(addr)) = req.extensions().get::<ConnectInfo<SocketAddr>>() {
+        Requester::Ip(addr.ip())
+    } else {
+        Requester::Unknown
+    };
+    let access = if matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        Access::Read
+    } else {
+        Access::Write
+    };
+
+    state
+        .rate_limiter
+        .acquire(requester, access, Instant::now())?;
+
+    Ok(next.run(req).await)
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils::{create_test_user_authorization, initialise_test_environment};
+    use crate::web::construct_routes;
+    use crate::Role;
+    use anyhow::Result;
+    use axum::http::header::RETRY_AFTER;
+    use axum::http::StatusCode;
+    use axum_test_helper::TestClient;
+    use serde_json::json;
+    use serial_test::serial;
+
+    #[test]
+    fn test_bucket_refills_over_time() {
+        let limiter = RateLimiter::new(60, 60);
+        let start = Instant::now();
+        let requester = Requester::User(1);
+
+        for _ in 0..60 {
+            limiter.acquire(requester, Access::Read, start).unwrap();
+        }
+        assert!(matches!(
+            limiter.acquire(requester, Access::Read, start),
+            Err(Error::RateLimited {
+                retry_after_seconds: 1
+            })
+        ));
+        // Other clients, and the same requester's writes, have their own buckets.
+        limiter
+            .acquire(Requester::User(2), Access::Read, start)
+            .unwrap();
+        limiter.acquire(requester, Access::Write, start).unwrap();
+
+        // One token a second.
+        limiter
+            .acquire(requester, Access::Read, start + Duration::from_secs(1))
+            .unwrap();
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_rate_limit_returns_429() -> Result<()> {
+        const READS: u32 = 3;
+        const WRITES: u32 = 2;
+        let dam = initialise_test_environment().await;
+        let auth = create_test_user_authorization(&dam, "test_rate_limit", Role::Editor).await;
+        let state = AppState {
+            rate_limiter: RateLimiter::new(READS, WRITES),
+            ..AppState::new(dam)
+        };
+        let client = TestClient::new(construct_routes(state));
+
+        for _ in 0..READS {
+            let res = client.get("/api/v1/regions").send().await;
+            assert_eq!(res.status(), StatusCode::OK);
+        }
+        let res = client.get("/api/v1/regions").send().await;
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(res.headers().contains_key(RETRY_AFTER));
+
+        // Writes are limited independently of reads.
+        for i in 0..WRITES {
+            let res = client
+                .post("/api/v1/regions")
+                .header("Authorization", &auth)
+                .json(&json!({ "name": format!("test_rate_limit_{i}") }))
+                .send()
+                .await;
+            assert_eq!(res.status(), StatusCode::OK);
+        }
+        let res = client
+            .post("/api/v1/regions")
+            .header("Authorization", &auth)
+            .json(&json!({ "name": "test_rate_limit_last" }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(res.headers().contains_key(RETRY_AFTER));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+use axum::http::{HeaderValue, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use tracing::Instrument;
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Inbound ids longer than this are ignored (and a fresh one generated), so a
+/// client cannot stuff arbitrary data into the logs.
+const MAX_INBOUND_REQUEST_ID_LENGTH: usize = 128;
+
+/// The id of the request being handled, available to handlers via `Extension`.
+#[derive(Clone, Debug)]
+pub struct RequestId(pub String);
+
+tokio::task_local! {
+    // NOTE: a task-local, rather than only the request extensions, so that the web
+    //       `Error` can include the id when it is converted into a response.
+    static REQUEST_ID: String;
+}
+
+/// The id of the request currently being handled, if called from within one.
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+// -----------------------------------------------------------------------------
+// Middleware
+// -----------------------------------------------------------------------------
+
+/// Tag each request with an id: the inbound `X-Request-Id` if there is a usable
+/// one, otherwise a new UUID. The id is stored in the request extensions, added
+/// to the tracing span for the request, and echoed in the response headers.
+pub async fn mw_request_id<B>(mut req: Request<B>, next: Next<B>) -> Response {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| !id.is_empty() && id.len() <= MAX_INBOUND_REQUEST_ID_LENGTH)
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    req.extensions_mut().insert(RequestId(request_id.clone()));
+    let span = tracing::info_span!("request", request_id = %request_id);
+    let mut res = REQUEST_ID
+        .scope(request_id.clone(), next.run(req).instrument(span))
+        .await;
+
+    // NOTE: the id came from a header (or is a UUID), so it is always a valid value.
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        res.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+
+    res
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils::initialise_test_environment;
+    use crate::web::{construct_routes, AppState};
+    use anyhow::Result;
+    use axum::http::StatusCode;
+    use axum_test_helper::TestClient;
+    use serde_json::Value;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_request_id_round_trips() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client
+            .get("/health")
+            .header(REQUEST_ID_HEADER, "test-request-id")
+            .send()
+            .await;
+        assert_eq!(res.headers()[REQUEST_ID_HEADER], "test-request-id");
+
+        let res = client.get("/health").send().await;
+        let generated = res.headers()[REQUEST_ID_HEADER].to_str()?;
+        assert!(Uuid::parse_str(generated).is_ok(), "{generated}");
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_error_body_includes_request_id() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client
+            .get("/api/v1/regions/999999")
+            .header(REQUEST_ID_HEADER, "test-error-request-id")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let error: Value = res.json().await;
+        assert_eq!(error["error"]["request_id"], "test-error-request-id");
+
+        Ok(())
+    }
+}
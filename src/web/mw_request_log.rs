@@ -0,0 +1,85 @@
+use crate::web::prometheus;
+use axum::extract::MatchedPath;
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use std::time::Instant;
+
+// -----------------------------------------------------------------------------
+// Middleware
+// -----------------------------------------------------------------------------
+
+/// Log each request once its response is ready: the method, path, status and
+/// how long it took. Server errors are logged at `error`, everything else at
+/// `info`, so the usual `RUST_LOG` filtering applies. The same figures are
+/// recorded for `/metrics` (see `prometheus::record_request`).
+///
+/// NOTE: only the path is logged, not the query string, which may hold search
+///       terms or other client data.
+pub async fn mw_request_log<B>(req: Request<B>, next: Next<B>) -> Response {
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    let matched_path = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|matched| matched.as_str().to_string());
+    let started = Instant::now();
+
+    let res = next.run(req).await;
+
+    let status = res.status().as_u16();
+    let elapsed = started.elapsed();
+    let elapsed_ms = elapsed.as_millis() as u64;
+    prometheus::record_request(matched_path.as_deref(), status, elapsed);
+    if res.status().is_server_error() {
+        tracing::error!(%method, path = path.as_str(), status, elapsed_ms, "request failed");
+    } else {
+        tracing::info!(%method, path = path.as_str(), status, elapsed_ms, "request");
+    }
+
+    res
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::_dev_utils::{initialise_test_environment, CapturedLogs};
+    use crate::web::{construct_routes, AppState};
+    use anyhow::Result;
+    use axum::http::StatusCode;
+    use axum_test_helper::TestClient;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_requests_are_logged() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::INFO)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client.get("/api/v1/regions/999999").send().await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let output = logs.contents();
+        let line = output
+            .lines()
+            .find(|line| line.contains("path=\"/api/v1/regions/999999\""))
+            .expect("the request was not logged");
+        assert!(line.contains("INFO"), "{line}");
+        assert!(line.contains("method=GET"), "{line}");
+        assert!(line.contains("status=404"), "{line}");
+        assert!(line.contains("elapsed_ms="), "{line}");
+
+        Ok(())
+    }
+}
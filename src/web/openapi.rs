@@ -0,0 +1,144 @@
+//! OpenAPI spec for the API, served at `/api-docs/openapi.json`, with a Swagger UI
+//! at `/swagger`. Only built with the `openapi` feature.
+//!
+//! The handlers and types are annotated where they are defined; this module only
+//! collects them.
+use crate::data_access::model::celestial_body::{
+    CelestialBody, CelestialBodyCreate, CelestialBodyExpanded,
+};
+use crate::data_access::model::celestial_region::{Region, RegionCreate};
+use crate::data_access::model::celestial_subregion::{Subregion, SubregionCreate};
+use crate::web::routes_admin::{MaintenanceStatus, RecomputeReport};
+use crate::web::routes_celestial_body::{
+    BodyDistance, BodyPage, BodySuggestion, DeleteReport, MoveBody,
+};
+use crate::web::routes_orrery::{BodyState, OrrerySnapshot};
+use crate::web::AppState;
+use crate::web::{
+    routes_admin, routes_celestial_body, routes_celestial_region, routes_celestial_subregion,
+    routes_orrery, routes_ws,
+};
+use axum::Router;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+use utoipa_swagger_ui::SwaggerUi;
+
+// -----------------------------------------------------------------------------
+// Spec
+// -----------------------------------------------------------------------------
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        routes_celestial_region::create_region,
+        routes_celestial_region::get_all_regions,
+        routes_celestial_region::search_regions,
+        routes_celestial_region::get_regions_batch,
+        routes_celestial_region::get_region,
+        routes_celestial_region::get_region_subregions,
+        routes_celestial_region::delete_region,
+        routes_celestial_region::update_region_name,
+        routes_celestial_region::update_region_description,
+        routes_celestial_subregion::create_subregion,
+        routes_celestial_subregion::get_all_subregions,
+        routes_celestial_subregion::get_subregion,
+        routes_celestial_subregion::delete_subregion,
+        routes_celestial_body::create_body,
+        routes_celestial_body::create_bodies,
+        routes_celestial_body::get_all_bodies,
+        routes_celestial_body::export_bodies_csv,
+        routes_celestial_body::export_bodies_ndjson,
+        routes_celestial_body::get_bodies_by_period,
+        routes_celestial_body::get_bodies_by_distance,
+        routes_celestial_body::search_bodies,
+        routes_celestial_body::get_body,
+        routes_celestial_body::get_distance,
+        routes_celestial_body::update_body,
+        routes_celestial_body::move_body,
+        routes_celestial_body::delete_bodies,
+        routes_celestial_body::suggest_bodies,
+        routes_admin::recompute_periods,
+        routes_admin::maintenance_on,
+        routes_admin::maintenance_off,
+        routes_orrery::get_snapshot,
+        routes_ws::upgrade,
+    ),
+    components(schemas(
+        Region,
+        RegionCreate,
+        Subregion,
+        SubregionCreate,
+        CelestialBody,
+        CelestialBodyExpanded,
+        CelestialBodyCreate,
+        BodyDistance,
+        BodyPage,
+        BodySuggestion,
+        DeleteReport,
+        MoveBody,
+        RecomputeReport,
+        MaintenanceStatus,
+        OrrerySnapshot,
+        BodyState
+    )),
+    modifiers(&BearerAuth)
+)]
+struct ApiDoc;
+
+/// Registers the `bearer` scheme referenced by the `security` of the mutating routes.
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "bearer",
+                SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+            );
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Routes
+// -----------------------------------------------------------------------------
+
+pub fn openapi_routes() -> Router<AppState> {
+    SwaggerUi::new("/swagger")
+        .url("/api-docs/openapi.json", ApiDoc::openapi())
+        .into()
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils::initialise_test_environment;
+    use crate::web::construct_routes;
+    use anyhow::Result;
+    use axum::http::StatusCode;
+    use axum_test_helper::TestClient;
+    use serde_json::Value;
+
+    #[tokio::test]
+    async fn test_openapi_spec() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client.get("/api-docs/openapi.json").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let spec: Value = res.json().await;
+        assert!(spec["paths"]["/api/v1/regions"]["get"].is_object());
+        assert!(spec["paths"]["/api/v1/regions"]["post"].is_object());
+        // `Option` fields are nullable.
+        assert_eq!(
+            spec["components"]["schemas"]["Region"]["properties"]["description"]["nullable"],
+            true
+        );
+
+        Ok(())
+    }
+}
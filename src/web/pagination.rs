@@ -0,0 +1,138 @@
+use crate::generic_utils::{b64u_decode, b64u_encode};
+use crate::web::{Error, Result};
+use serde::Deserialize;
+
+// -----------------------------------------------------------------------------
+// Offset pagination
+// -----------------------------------------------------------------------------
+
+pub const DEFAULT_LIMIT: i64 = 50;
+pub const MAX_LIMIT: i64 = 500;
+
+/// Query parameters for listing endpoints, *eg* `?limit=20&offset=40`.
+///
+/// NOTE: the fields are signed so that negative values deserialize successfully
+///       and can be rejected with a 422, rather than Axum's generic 400.
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+pub struct Pagination {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+impl Pagination {
+    /// Resolve the defaults and check the bounds, returning `(limit, offset)`.
+    /// Out-of-range values are rejected rather than silently clamped.
+    pub fn validate(&self) -> Result<(i64, i64)> {
+        let limit = self.limit.unwrap_or(DEFAULT_LIMIT);
+        let offset = self.offset.unwrap_or(0);
+
+        if !(1..=MAX_LIMIT).contains(&limit) {
+            return Err(Error::PaginationLimitOutOfRange(limit));
+        }
+        if offset < 0 {
+            return Err(Error::PaginationNegativeOffset(offset));
+        }
+
+        Ok((limit, offset))
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Cursor pagination
+// -----------------------------------------------------------------------------
+
+/// The opaque cursor for the page after the entity with this id. Clients are only
+/// meant to pass back a `next_cursor` they were given, so the encoding is free to
+/// change.
+pub fn encode_cursor(id: i64) -> String {
+    b64u_encode(&id.to_string())
+}
+
+/// The id a cursor from `encode_cursor` points after.
+pub fn decode_cursor(cursor: &str) -> Result<i64> {
+    b64u_decode(cursor)
+        .ok()
+        .and_then(|id| id.parse().ok())
+        .ok_or_else(|| Error::InvalidCursor(cursor.to_string()))
+}
+
+/// Trim a page read with one extra row (`limit + 1`) back to `limit`, returning the
+/// cursor for the next page, or `None` if there was no extra row, *ie* this is the
+/// last page. Reading the extra row saves clients from fetching a final empty page.
+pub fn next_cursor<T>(items: &mut Vec<T>, limit: i64, id: impl Fn(&T) -> i64) -> Option<String> {
+    let limit = limit as usize;
+    if items.len() <= limit {
+        return None;
+    }
+    items.truncate(limit);
+
+    items.last().map(|item| encode_cursor(id(item)))
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pagination(limit: Option<i64>, offset: Option<i64>) -> Pagination {
+        Pagination { limit, offset }
+    }
+
+    #[test]
+    fn test_defaults() {
+        assert_eq!(
+            Pagination::default().validate().unwrap(),
+            (DEFAULT_LIMIT, 0)
+        );
+    }
+
+    #[test]
+    fn test_limit_boundaries() {
+        assert!(pagination(Some(0), None).validate().is_err());
+        assert_eq!(pagination(Some(1), None).validate().unwrap(), (1, 0));
+        assert_eq!(
+            pagination(Some(MAX_LIMIT), None).validate().unwrap(),
+            (MAX_LIMIT, 0)
+        );
+        assert!(pagination(Some(MAX_LIMIT + 1), None).validate().is_err());
+        assert!(pagination(Some(-1), None).validate().is_err());
+    }
+
+    #[test]
+    fn test_offset_boundaries() {
+        assert_eq!(
+            pagination(None, Some(0)).validate().unwrap(),
+            (DEFAULT_LIMIT, 0)
+        );
+        assert!(pagination(None, Some(-1)).validate().is_err());
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        for id in [0, 1, 42, i64::MAX] {
+            assert_eq!(decode_cursor(&encode_cursor(id)).unwrap(), id);
+        }
+        for cursor in ["", "42", "not base64!", &b64u_encode("forty-two")] {
+            assert!(
+                matches!(decode_cursor(cursor), Err(Error::InvalidCursor(_))),
+                "{cursor}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_next_cursor() {
+        let mut full = vec![1, 2, 3];
+        assert_eq!(next_cursor(&mut full, 2, |id| *id), Some(encode_cursor(2)));
+        assert_eq!(full, [1, 2]);
+
+        let mut last = vec![1, 2];
+        assert_eq!(next_cursor(&mut last, 2, |id| *id), None);
+        assert_eq!(last, [1, 2]);
+    }
+}
@@ -0,0 +1,55 @@
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+pub const REQUESTS_TOTAL: &str = "http_requests_total";
+pub const REQUEST_DURATION_SECONDS: &str = "http_request_duration_seconds";
+pub const DB_POOL_CONNECTIONS: &str = "db_pool_connections";
+pub const DB_POOL_IDLE_CONNECTIONS: &str = "db_pool_idle_connections";
+
+/// Histogram buckets for request durations, in seconds: 1ms to 10s.
+const REQUEST_DURATION_BUCKETS: [f64; 12] = [
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 10.0,
+];
+
+/// The label for requests that matched no route. Using the raw path instead would
+/// let a client create unbounded label values by requesting random paths.
+const UNMATCHED_PATH: &str = "unmatched";
+
+// -----------------------------------------------------------------------------
+// Recorder
+// -----------------------------------------------------------------------------
+
+/// The handle for rendering everything recorded so far, installing the Prometheus
+/// recorder as the global `metrics` recorder on first use.
+///
+/// NOTE: a process can only have one global recorder. If something else has already
+///       installed one, the request metrics go to it instead, and are missing here.
+pub fn prometheus_handle() -> &'static PrometheusHandle {
+    static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+    HANDLE.get_or_init(|| {
+        let recorder = PrometheusBuilder::new()
+            .set_buckets(&REQUEST_DURATION_BUCKETS)
+            .expect("the buckets are not empty")
+            .build_recorder();
+        let handle = recorder.handle();
+        if metrics::set_boxed_recorder(Box::new(recorder)).is_err() {
+            tracing::warn!("a metrics recorder is already installed; /metrics will be incomplete");
+        }
+
+        handle
+    })
+}
+
+/// Count a handled request and record how long it took. `path` is the route
+/// template (*eg* `/api/v1/bodies/:id`), or `None` if no route matched.
+pub fn record_request(path: Option<&str>, status: u16, elapsed: Duration) {
+    let labels = [
+        ("path", path.unwrap_or(UNMATCHED_PATH).to_string()),
+        ("status", status.to_string()),
+    ];
+
+    metrics::counter!(REQUESTS_TOTAL, 1, &labels);
+    metrics::histogram!(REQUEST_DURATION_SECONDS, elapsed.as_secs_f64(), &labels);
+}
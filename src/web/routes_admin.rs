@@ -0,0 +1,199 @@
+use crate::config::get_config;
+use crate::data_access::model::celestial_body::Client;
+use crate::data_access::DataAccessManager;
+use crate::web::mw_maintenance::Maintenance;
+use crate::web::{AppState, Result};
+use crate::{RequestContext, Role};
+use axum::extract::State;
+use axum::routing::post;
+use axum::{Json, Router};
+use serde::Serialize;
+
+// -----------------------------------------------------------------------------
+// Types
+// -----------------------------------------------------------------------------
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub(crate) struct RecomputeReport {
+    /// How many bodies had their orbital period rewritten.
+    changed: usize,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub(crate) struct MaintenanceStatus {
+    /// Whether writes are currently refused.
+    maintenance: bool,
+}
+
+// -----------------------------------------------------------------------------
+// Routes
+// -----------------------------------------------------------------------------
+
+pub fn admin_routes() -> Router<AppState> {
+    Router::new().route("/admin/recompute-periods", post(recompute_periods))
+}
+
+/// Kept apart from `admin_routes`, so that they can be left outside
+/// `mw_maintenance`: otherwise maintenance could never be turned off.
+pub fn maintenance_routes() -> Router<AppState> {
+    Router::new()
+        .route("/admin/maintenance/on", post(maintenance_on))
+        .route("/admin/maintenance/off", post(maintenance_off))
+}
+
+// -----------------------------------------------------------------------------
+// Handlers
+// -----------------------------------------------------------------------------
+
+/// Rewrite every stored orbital period that is inconsistent with the body's
+/// aphelion and perihelion by more than `KEPLER_TOLERANCE` (see
+/// `Client::recompute_periods`). Meant for repairing imported data.
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/api/v1/admin/recompute-periods",
+    responses(
+        (status = 200, description = "How many bodies were corrected", body = RecomputeReport),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 403, description = "Requires the admin role"),
+    ),
+    security(("bearer" = [])),
+))]
+async fn recompute_periods(
+    State(dam): State<DataAccessManager>,
+    ctx: RequestContext,
+) -> Result<Json<RecomputeReport>> {
+    ctx.require(Role::Admin)?;
+    let changed = Client::recompute_periods(&ctx, &dam, get_config().KEPLER_TOLERANCE).await?;
+
+    Ok(Json(RecomputeReport { changed }))
+}
+
+/// Refuse writes with a 503 until maintenance is turned off again; reads are
+/// unaffected (see `mw_maintenance`).
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/api/v1/admin/maintenance/on",
+    responses(
+        (status = 200, description = "Now in maintenance", body = MaintenanceStatus),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 403, description = "Requires the admin role"),
+    ),
+    security(("bearer" = [])),
+))]
+async fn maintenance_on(
+    State(maintenance): State<Maintenance>,
+    ctx: RequestContext,
+) -> Result<Json<MaintenanceStatus>> {
+    set_maintenance(&maintenance, &ctx, true)
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/api/v1/admin/maintenance/off",
+    responses(
+        (status = 200, description = "Writes are accepted again", body = MaintenanceStatus),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 403, description = "Requires the admin role"),
+    ),
+    security(("bearer" = [])),
+))]
+async fn maintenance_off(
+    State(maintenance): State<Maintenance>,
+    ctx: RequestContext,
+) -> Result<Json<MaintenanceStatus>> {
+    set_maintenance(&maintenance, &ctx, false)
+}
+
+fn set_maintenance(
+    maintenance: &Maintenance,
+    ctx: &RequestContext,
+    on: bool,
+) -> Result<Json<MaintenanceStatus>> {
+    ctx.require(Role::Admin)?;
+    maintenance.set(on);
+    tracing::warn!(maintenance = on, "maintenance mode changed");
+
+    Ok(Json(MaintenanceStatus { maintenance: on }))
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils::{
+        body_create, create_test_user_authorization, initialise_test_environment, reset_database,
+    };
+    use crate::data_access::model::celestial_body::CelestialBodyCreate;
+    use crate::web::construct_routes;
+    use anyhow::Result;
+    use axum::http::StatusCode;
+    use axum_test_helper::TestClient;
+    use serde_json::Value;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_recompute_periods() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        reset_database(&dam).await?;
+        let ctx = RequestContext::root_context();
+        let admin = create_test_user_authorization(&dam, "test_recompute_admin", Role::Admin).await;
+        let editor =
+            create_test_user_authorization(&dam, "test_recompute_editor", Role::Editor).await;
+        let create = |name: &str, orbital_period: f64| {
+            let data = CelestialBodyCreate {
+                aphelion: 152_100_000.0,
+                perihelion: 147_095_000.0,
+                orbital_period,
+                ..body_create(name)
+            };
+            Client::create(&ctx, &dam, data)
+        };
+        let stale = create("test_recompute_stale", 100.0).await?;
+        let correct = create("test_recompute_correct", 365.256).await?;
+        let client = TestClient::new(construct_routes(AppState::new(dam.clone())));
+
+        let res = client
+            .post("/api/v1/admin/recompute-periods")
+            .header("Authorization", &editor)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let res = client
+            .post("/api/v1/admin/recompute-periods")
+            .header("Authorization", &admin)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let report: Value = res.json().await;
+        assert_eq!(report["changed"], 1);
+
+        let body = Client::read(&ctx, &dam, stale).await?;
+        assert!(
+            (body.orbital_period - 365.25).abs() < 0.1,
+            "{}",
+            body.orbital_period
+        );
+        assert_eq!(
+            Client::read(&ctx, &dam, correct).await?.orbital_period,
+            365.256
+        );
+
+        // Once corrected, there is nothing left to change.
+        let res = client
+            .post("/api/v1/admin/recompute-periods")
+            .header("Authorization", &admin)
+            .send()
+            .await;
+        let report: Value = res.json().await;
+        assert_eq!(report["changed"], 0);
+
+        Ok(())
+    }
+}
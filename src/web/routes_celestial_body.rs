@@ -0,0 +1,1899 @@
+use crate::config::get_config;
+use crate::data_access::model::celestial_body::{
+    check_keplerian_consistency, check_keplerian_consistency_around, BodyOrdering, CelestialBody,
+    CelestialBodyCreate, CelestialBodyExpanded, CelestialBodyUpdate, Client, KM_PER_AU,
+};
+use crate::data_access::{self, DataAccessManager, DbCrudAction, SortSpec, ID_BATCH_LIMIT};
+use crate::generic_utils::{format_utc_time, parse_utc};
+use crate::web::etag::conditional_json;
+use crate::web::import::{self, ImportParams, ImportReport};
+use crate::web::mw_body_limit::{limit_body, LimitedBody};
+use crate::web::pagination::{self, decode_cursor, Pagination};
+use crate::web::search::{SearchParams, SEARCH_LIMIT, SUGGEST_LIMIT};
+use crate::web::units::{in_units, RequestedUnits, Units};
+use crate::web::validation::{check_name, FieldError, ValidJson, Validate};
+use crate::web::{public_read_context, AppState, Error, Result};
+use crate::{RequestContext, Role};
+use axum::body::{boxed, Body, Bytes, StreamBody};
+use axum::extract::{Path, Query, State};
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::http::{HeaderMap, Request, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, patch, post};
+use axum::{BoxError, Json, Router};
+use chrono::{TimeZone, Utc};
+use futures_util::stream;
+use serde::{Deserialize, Serialize};
+
+// -----------------------------------------------------------------------------
+// Types
+// -----------------------------------------------------------------------------
+
+/// The direction of a listing: `asc` or `desc`.
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SortDirection {
+    Asc,
+    Desc,
+}
+
+/// Query string for the body listing: `?sort=<field>&dir=asc|desc`, optionally
+/// with `&expand=region,subregion` for the names as well as the ids; or
+/// `?updated_since=<Unix timestamp>` for only the recently changed bodies; or a
+/// page at a time in id order, with `?limit=<n>` and either `&after_id=<cursor>`
+/// or `&offset=<n>` (see `BodyPage`).
+#[derive(Deserialize)]
+struct ListBodies {
+    sort: Option<String>,
+    dir: Option<SortDirection>,
+    expand: Option<String>,
+    updated_since: Option<i64>,
+    after_id: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+/// Query string for the computed orderings: `?order=asc|desc`, ascending by
+/// default.
+#[derive(Deserialize)]
+struct OrderParams {
+    order: Option<SortDirection>,
+}
+
+/// Query string for a single body: `?fields=id,name` for only those fields.
+/// Unknown fields are ignored, or with `&strict=true` rejected.
+#[derive(Deserialize)]
+struct BodyFields {
+    fields: Option<String>,
+    #[serde(default)]
+    strict: bool,
+}
+
+/// A page of the body listing. Pass `next_cursor` back as `after_id` for the next
+/// page; it is `null` on the last one.
+#[derive(Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub(crate) struct BodyPage {
+    bodies: Vec<CelestialBody>,
+    next_cursor: Option<String>,
+}
+
+/// The values accepted in `?expand=`.
+const EXPANSIONS: [&str; 2] = ["region", "subregion"];
+
+/// `expected_updated_at` is the `updated_at` the client last saw (`null` if the
+/// body has never been updated); the write is rejected if it has since changed.
+#[derive(Deserialize)]
+struct UpdateBody {
+    name: Option<String>,
+    parent_id: Option<i64>,
+    expected_updated_at: Option<i64>,
+}
+
+/// Only the fields being changed are checked.
+impl Validate for UpdateBody {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if let Some(name) = &self.name {
+            check_name(&mut errors, name);
+        }
+
+        errors
+    }
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub(crate) struct MoveBody {
+    region_id: i64,
+}
+
+/// Nothing to check beyond the shape; the region's existence is checked by the
+/// move itself.
+impl Validate for MoveBody {
+    fn validate(&self) -> Vec<FieldError> {
+        Vec::new()
+    }
+}
+
+/// Query string for the distance between two bodies: `?at=<RFC 3339 timestamp>`.
+#[derive(Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+struct DistanceParams {
+    at: String,
+}
+
+/// A body whose name is a close match for the query, with how close: `1.0` for
+/// an exact match (ignoring case), less for each typo.
+#[derive(Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub(crate) struct BodySuggestion {
+    id: i64,
+    name: String,
+    score: f64,
+}
+
+/// Query string for the bulk delete: `?ids=1,2,3`.
+#[derive(Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+struct DeleteBodies {
+    ids: String,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub(crate) struct DeleteReport {
+    /// How many bodies were deleted; ids with no body are not counted.
+    deleted: u64,
+}
+
+/// The straight-line distance between two bodies at an instant. See
+/// `CelestialBody::position_at` for the approximations made.
+#[derive(Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub(crate) struct BodyDistance {
+    from: i64,
+    to: i64,
+    /// The instant, as RFC 3339.
+    at: String,
+    km: f64,
+    au: f64,
+}
+
+/// How many bodies are read from the database per chunk of the CSV export.
+const CSV_PAGE_SIZE: i64 = 500;
+
+const CSV_HEADER: [&str; 12] = [
+    "id",
+    "name",
+    "region",
+    "subregion",
+    "parent_id",
+    "aphelion",
+    "perihelion",
+    "orbital_period",
+    "radius",
+    "mass",
+    "created_at",
+    "updated_at",
+];
+
+/// One line of the CSV export: a `CelestialBody`, with RFC3339 timestamps.
+/// NOTE: fields must stay in the same order as `CSV_HEADER`.
+#[derive(Serialize)]
+struct CsvBody<'a> {
+    id: i64,
+    name: &'a str,
+    region: Option<i64>,
+    subregion: Option<i64>,
+    parent_id: Option<i64>,
+    aphelion: f64,
+    perihelion: f64,
+    orbital_period: f64,
+    radius: f64,
+    mass: f64,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+}
+
+impl<'a> From<&'a CelestialBody> for CsvBody<'a> {
+    fn from(body: &'a CelestialBody) -> Self {
+        // NOTE: timestamps are stored as seconds since the Unix epoch.
+        let to_rfc3339 = |secs: i64| Utc.timestamp_opt(secs, 0).single().map(format_utc_time);
+
+        Self {
+            id: body.id,
+            name: &body.name,
+            region: body.region,
+            subregion: body.subregion,
+            parent_id: body.parent_id,
+            aphelion: body.aphelion,
+            perihelion: body.perihelion,
+            orbital_period: body.orbital_period,
+            radius: body.radius,
+            mass: body.mass,
+            created_at: to_rfc3339(body.created_at),
+            updated_at: body.updated_at.and_then(to_rfc3339),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Routes
+// -----------------------------------------------------------------------------
+
+pub fn body_routes() -> Router<AppState> {
+    let config = get_config();
+    let max_bytes = config.MAX_REQUEST_BODY_BYTES;
+    Router::new()
+        .route(
+            "/bodies",
+            limit_body(
+                get(get_all_bodies).post(create_body).delete(delete_bodies),
+                max_bytes,
+            ),
+        )
+        .route("/bodies.csv", get(export_bodies_csv))
+        .route("/bodies.ndjson", get(export_bodies_ndjson))
+        .route("/bodies/bulk", limit_body(post(create_bodies), max_bytes))
+        .route(
+            "/bodies/import",
+            limit_body(post(import_bodies), config.MAX_IMPORT_BODY_BYTES),
+        )
+        .route("/bodies/by-period", get(get_bodies_by_period))
+        .route("/bodies/by-distance", get(get_bodies_by_distance))
+        .route("/bodies/search", get(search_bodies))
+        .route("/bodies/suggest", get(suggest_bodies))
+        .route(
+            "/bodies/:id",
+            limit_body(get(get_body).patch(update_body), max_bytes),
+        )
+        .route(
+            "/bodies/:id/region",
+            limit_body(patch(move_body), max_bytes),
+        )
+        .route("/bodies/:id/:other_id/distance", get(get_distance))
+}
+
+// -----------------------------------------------------------------------------
+// Handlers
+// -----------------------------------------------------------------------------
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/api/v1/bodies",
+    request_body = CelestialBodyCreate,
+    responses(
+        (status = 200, description = "The created body", body = CelestialBody),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 403, description = "Requires the editor role"),
+        (status = 404, description = "No such parent body"),
+        (status = 422, description = "Invalid fields, or orbital period inconsistent with \
+            Kepler's third law"),
+    ),
+    security(("bearer" = [])),
+))]
+async fn create_body(
+    State(dam): State<DataAccessManager>,
+    ctx: RequestContext,
+    ValidJson(new_body): ValidJson<CelestialBodyCreate>,
+) -> Result<Json<CelestialBody>> {
+    ctx.require(Role::Editor)?;
+    check_orbit(&ctx, &dam, &new_body).await?;
+    let id = Client::create(&ctx, &dam, new_body).await?;
+    let body = Client::read(&ctx, &dam, id).await?;
+
+    Ok(Json(body))
+}
+
+/// Create several bodies atomically: either all are saved, or none are.
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/api/v1/bodies/bulk",
+    request_body = [CelestialBodyCreate],
+    responses(
+        (status = 200, description = "The created bodies", body = [CelestialBody]),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 403, description = "Requires the editor role"),
+        (status = 422, description = "An item was rejected; nothing was saved"),
+    ),
+    security(("bearer" = [])),
+))]
+async fn create_bodies(
+    State(dam): State<DataAccessManager>,
+    ctx: RequestContext,
+    ValidJson(new_bodies): ValidJson<Vec<CelestialBodyCreate>>,
+) -> Result<Json<Vec<CelestialBody>>> {
+    ctx.require(Role::Editor)?;
+    for (index, new_body) in new_bodies.iter().enumerate() {
+        check_orbit(&ctx, &dam, new_body).await.map_err(|err| {
+            data_access::Error::BatchItemFailed {
+                index,
+                cause: Box::new(err),
+            }
+        })?;
+    }
+    let ids = Client::create_many(&ctx, &dam, new_bodies).await?;
+    // NOTE: `read_many` caps the ids per call, so a batch larger than that is
+    //       read back in chunks rather than failing after it has been saved.
+    let mut bodies = Vec::with_capacity(ids.len());
+    for chunk in ids.chunks(ID_BATCH_LIMIT) {
+        bodies.extend(Client::read_many(&ctx, &dam, chunk).await?);
+    }
+
+    Ok(Json(bodies))
+}
+
+/// Delete several bodies at once, *eg* to clean up an import. Ids are
+/// deduplicated, and ids with no body are skipped; the response says how many
+/// were actually deleted.
+#[cfg_attr(feature = "openapi", utoipa::path(
+    delete,
+    path = "/api/v1/bodies",
+    params(DeleteBodies),
+    responses(
+        (status = 200, description = "How many bodies were deleted", body = DeleteReport),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 403, description = "Requires the editor role"),
+        (status = 422, description = "Malformed id list, or too many ids"),
+    ),
+    security(("bearer" = [])),
+))]
+async fn delete_bodies(
+    State(dam): State<DataAccessManager>,
+    ctx: RequestContext,
+    Query(DeleteBodies { ids }): Query<DeleteBodies>,
+) -> Result<Json<DeleteReport>> {
+    ctx.require(Role::Editor)?;
+    let parsed = ids
+        .split(',')
+        .map(|id| id.trim().parse::<i64>())
+        .collect::<core::result::Result<Vec<_>, _>>()
+        .map_err(|_| Error::InvalidIdList(ids.clone()))?;
+    let deleted = Client::delete_many(&ctx, &dam, &parsed).await?;
+
+    Ok(Json(DeleteReport { deleted }))
+}
+
+/// Import bodies from an uploaded CSV or JSON file (see `web::import`). Every
+/// row is parsed and checked, then the valid ones are inserted in a single
+/// transaction. Rows that fail are listed in the report, with their line.
+///
+/// By default the rest of the file is still imported. With `?strict=true` a
+/// single failure rejects the whole file with a 422, and nothing is saved.
+async fn import_bodies(
+    State(dam): State<DataAccessManager>,
+    ctx: RequestContext,
+    Query(params): Query<ImportParams>,
+    req: Request<LimitedBody>,
+) -> Result<(StatusCode, Json<ImportReport>)> {
+    ctx.require(Role::Editor)?;
+    let (format, bytes) = import::read_upload(req).await?;
+    let rows = import::parse_rows::<CelestialBodyCreate>(format, &bytes)?;
+    let mut report = ImportReport::default();
+
+    let mut lines = Vec::with_capacity(rows.len());
+    let mut bodies = Vec::with_capacity(rows.len());
+    for (line, row) in rows {
+        let row = match row {
+            Ok(body) => validate_import_row(&ctx, &dam, body).await,
+            Err(reason) => Err(reason),
+        };
+        match row {
+            Ok(body) => {
+                lines.push(line);
+                bodies.push(body);
+            }
+            Err(reason) => report.reject(line, reason),
+        }
+    }
+    if params.strict && !report.errors.is_empty() {
+        return Ok((StatusCode::UNPROCESSABLE_ENTITY, Json(report)));
+    }
+
+    let results = Client::create_each(&ctx, &dam, bodies, params.strict).await?;
+    for (line, result) in lines.into_iter().zip(results) {
+        match result {
+            Ok(_) => report.imported += 1,
+            Err(err) => report.reject(line, Error::from(err).client_message()),
+        }
+    }
+    report.errors.sort_by_key(|error| error.line);
+    if params.strict && !report.errors.is_empty() {
+        // NOTE: `create_each` has rolled the inserts back.
+        report.imported = 0;
+        return Ok((StatusCode::UNPROCESSABLE_ENTITY, Json(report)));
+    }
+
+    Ok((StatusCode::OK, Json(report)))
+}
+
+/// The checks `create_body` makes, with the failure as a client-safe reason: for
+/// invalid fields, *eg* `radius must not be negative; ...`.
+async fn validate_import_row(
+    ctx: &RequestContext,
+    dam: &DataAccessManager,
+    body: CelestialBodyCreate,
+) -> core::result::Result<CelestialBodyCreate, String> {
+    let errors = body.validate();
+    if !errors.is_empty() {
+        let reasons: Vec<String> = errors
+            .iter()
+            .map(|err| format!("{} {}", err.field, err.message))
+            .collect();
+        return Err(reasons.join("; "));
+    }
+    check_orbit(ctx, dam, &body)
+        .await
+        .map_err(|err| Error::from(err).client_message())?;
+
+    Ok(body)
+}
+
+/// Check the orbital period against whatever the body orbits: its parent, if
+/// it has one, otherwise the Sun.
+async fn check_orbit(
+    ctx: &RequestContext,
+    dam: &DataAccessManager,
+    body: &CelestialBodyCreate,
+) -> data_access::Result<()> {
+    let tolerance = get_config().KEPLER_TOLERANCE;
+    if body.parent_id.is_none() {
+        return check_keplerian_consistency(body, tolerance);
+    }
+
+    let primary_mass = Client::primary_mass(ctx, dam, body.parent_id).await?;
+    check_keplerian_consistency_around(body, primary_mass, tolerance)
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/bodies",
+    params(("sort" = Option<String>, Query, description = "Field to sort by, `id` by default"),
+        ("dir" = Option<String>, Query, description = "`asc` (default) or `desc`"),
+        ("expand" = Option<String>, Query,
+            description = "`region`, `subregion` or both (comma-separated): include \
+                `region_name` and `subregion_name`"),
+        ("updated_since" = Option<i64>, Query,
+            description = "Only bodies created or updated since this Unix timestamp, \
+                oldest change first; `sort`, `dir` and `expand` are then ignored"),
+        ("after_id" = Option<String>, Query,
+            description = "The `next_cursor` of the previous page: return a `BodyPage` \
+                of the bodies after it, in id order"),
+        ("limit" = Option<i64>, Query,
+            description = "The page size (50 by default, at most 500): return a `BodyPage`; \
+                `sort`, `dir` and `expand` are then ignored"),
+        ("offset" = Option<i64>, Query,
+            description = "How many bodies to skip, if `after_id` is not given"),
+        ("units" = Option<String>, Query,
+            description = "`km` (as stored), `au` or `earth_radii`: convert `radius`, \
+                `aphelion` and `perihelion`, and add `semi_major_axis` and `units`; also \
+                accepted as an `Accept-Units` header")),
+    responses(
+        (status = 200, description = "Every body, or with `after_id`, `limit` or `offset` a \
+            `BodyPage`; with `expand`, each also has `region_name` and `subregion_name` \
+            (see `CelestialBodyExpanded`)",
+            body = [CelestialBody]),
+        (status = 400, description = "Unknown `units`"),
+        (status = 422, description = "Unknown `expand` value, invalid cursor or pagination"),
+    ),
+))]
+async fn get_all_bodies(
+    State(dam): State<DataAccessManager>,
+    Query(params): Query<ListBodies>,
+    RequestedUnits(units): RequestedUnits,
+) -> Result<Response> {
+    let ctx = public_read_context();
+    if let Some(since) = params.updated_since {
+        let bodies = Client::read_updated_since(&ctx, &dam, since).await?;
+        return Ok(json_in_units(bodies, units));
+    }
+    if params.after_id.is_some() || params.limit.is_some() || params.offset.is_some() {
+        let page = Pagination {
+            limit: params.limit,
+            offset: params.offset,
+        };
+        let page = read_body_page(&ctx, &dam, page, params.after_id).await?;
+        return Ok(json_in_units(page, units));
+    }
+
+    let expand = match params.expand {
+        Some(expand) => parse_expand(&expand)?,
+        None => false,
+    };
+    let sort = SortSpec {
+        column: params.sort.unwrap_or_else(|| "id".to_string()),
+        descending: matches!(params.dir, Some(SortDirection::Desc)),
+    };
+    if expand {
+        let bodies: Vec<CelestialBodyExpanded> =
+            Client::read_all_expanded(&ctx, &dam, sort).await?;
+        return Ok(json_in_units(bodies, units));
+    }
+    let bodies = Client::read_all_sorted(&ctx, &dam, sort).await?;
+
+    Ok(json_in_units(bodies, units))
+}
+
+/// Respond with `value` as JSON, converted to the requested units if any were
+/// (see `in_units`); otherwise as is, in km.
+fn json_in_units(value: impl Serialize, units: Option<Units>) -> Response {
+    match units {
+        Some(units) => Json(in_units(value, units)).into_response(),
+        None => Json(value).into_response(),
+    }
+}
+
+/// As `json_in_units`, answering `If-None-Match` (see `conditional_json`).
+fn conditional_json_in_units(
+    headers: &HeaderMap,
+    value: impl Serialize,
+    units: Option<Units>,
+) -> Response {
+    match units {
+        Some(units) => conditional_json(headers, in_units(value, units)),
+        None => conditional_json(headers, value),
+    }
+}
+
+/// A page of bodies in id order: after the cursor if there is one, otherwise at
+/// the offset. Offsets are kept for compatibility, but cursors are preferred: a
+/// body created or deleted earlier in the listing does not shift the next page.
+async fn read_body_page(
+    ctx: &RequestContext,
+    dam: &DataAccessManager,
+    page: Pagination,
+    after_id: Option<String>,
+) -> Result<BodyPage> {
+    let (limit, offset) = page.validate()?;
+    // NOTE: one more than the limit, to tell whether there is a next page.
+    let mut bodies = match after_id {
+        Some(cursor) => Client::read_after(ctx, dam, decode_cursor(&cursor)?, limit + 1).await?,
+        None => Client::read_page(ctx, dam, limit + 1, offset).await?,
+    };
+    let next_cursor = pagination::next_cursor(&mut bodies, limit, |body| body.id);
+
+    Ok(BodyPage {
+        bodies,
+        next_cursor,
+    })
+}
+
+/// Check every comma-separated value of `?expand=` is known, returning whether
+/// any were given.
+///
+/// NOTE: both names come from the same query, so asking for either includes both.
+fn parse_expand(expand: &str) -> Result<bool> {
+    let mut any = false;
+    for value in expand
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+    {
+        if !EXPANSIONS.contains(&value) {
+            return Err(Error::InvalidExpand(value.to_string()));
+        }
+        any = true;
+    }
+
+    Ok(any)
+}
+
+/// Every body, shortest orbital period first (or last, with `order=desc`).
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/bodies/by-period",
+    params(("order" = Option<String>, Query, description = "`asc` (default) or `desc`")),
+    responses(
+        (status = 200, description = "Every body, by orbital period", body = [CelestialBody]),
+        (status = 400, description = "Unknown `order`"),
+    ),
+))]
+async fn get_bodies_by_period(
+    State(dam): State<DataAccessManager>,
+    Query(params): Query<OrderParams>,
+) -> Result<Json<Vec<CelestialBody>>> {
+    read_ordered(&dam, BodyOrdering::Period, params).await
+}
+
+/// Every body, nearest its primary on average first (or last, with
+/// `order=desc`): for the planets, the order from the Sun.
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/bodies/by-distance",
+    params(("order" = Option<String>, Query, description = "`asc` (default) or `desc`")),
+    responses(
+        (status = 200, description = "Every body, by `aphelion + perihelion`",
+            body = [CelestialBody]),
+        (status = 400, description = "Unknown `order`"),
+    ),
+))]
+async fn get_bodies_by_distance(
+    State(dam): State<DataAccessManager>,
+    Query(params): Query<OrderParams>,
+) -> Result<Json<Vec<CelestialBody>>> {
+    read_ordered(&dam, BodyOrdering::Distance, params).await
+}
+
+async fn read_ordered(
+    dam: &DataAccessManager,
+    ordering: BodyOrdering,
+    OrderParams { order }: OrderParams,
+) -> Result<Json<Vec<CelestialBody>>> {
+    let ctx = public_read_context();
+    let descending = matches!(order, Some(SortDirection::Desc));
+    let bodies = Client::read_all_ordered(&ctx, dam, ordering, descending).await?;
+
+    Ok(Json(bodies))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/bodies/search",
+    params(SearchParams),
+    responses(
+        (status = 200, description = "Bodies whose name contains `q`", body = [CelestialBody]),
+    ),
+))]
+async fn search_bodies(
+    State(dam): State<DataAccessManager>,
+    Query(SearchParams { q }): Query<SearchParams>,
+) -> Result<Json<Vec<CelestialBody>>> {
+    let ctx = public_read_context();
+    let bodies = Client::search_by_name(&ctx, &dam, &q, SEARCH_LIMIT).await?;
+
+    Ok(Json(bodies))
+}
+
+/// Bodies whose name is close to `q`, allowing for typos (*eg* "jupter" finds
+/// Jupiter), best match first. An empty `q` gives an empty list.
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/bodies/suggest",
+    params(SearchParams),
+    responses(
+        (status = 200, description = "The closest matches, best first", body = [BodySuggestion]),
+    ),
+))]
+async fn suggest_bodies(
+    State(dam): State<DataAccessManager>,
+    Query(SearchParams { q }): Query<SearchParams>,
+) -> Result<Json<Vec<BodySuggestion>>> {
+    let ctx = public_read_context();
+    let suggestions = Client::suggest_by_name(&ctx, &dam, &q, SUGGEST_LIMIT)
+        .await?
+        .into_iter()
+        .map(|(body, score)| BodySuggestion {
+            id: body.id,
+            name: body.name,
+            score,
+        })
+        .collect();
+
+    Ok(Json(suggestions))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/bodies/{id}",
+    params(("id" = i64, Path, description = "Database id"),
+        ("fields" = Option<String>, Query,
+            description = "Comma-separated fields to return, rather than all of them"),
+        ("strict" = Option<bool>, Query,
+            description = "Reject unknown `fields`, rather than ignoring them"),
+        ("units" = Option<String>, Query,
+            description = "`km` (as stored), `au` or `earth_radii`: convert `radius`, \
+                `aphelion` and `perihelion`, and add `semi_major_axis` and `units`; also \
+                accepted as an `Accept-Units` header")),
+    responses(
+        (status = 200, description = "The body, or with `fields` only those",
+            body = CelestialBody),
+        (status = 304, description = "Unchanged since the `If-None-Match` ETag"),
+        (status = 400, description = "Unknown `units`, or with `strict` `fields`"),
+        (status = 404, description = "No such body"),
+    ),
+))]
+async fn get_body(
+    State(dam): State<DataAccessManager>,
+    Path(id): Path<i64>,
+    Query(BodyFields { fields, strict }): Query<BodyFields>,
+    RequestedUnits(units): RequestedUnits,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let ctx = public_read_context();
+    if let Some(fields) = fields {
+        let fields: Vec<&str> = fields
+            .split(',')
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+            .collect();
+        let unknown = DbCrudAction::unknown_columns::<CelestialBody>(&fields);
+        if strict && !unknown.is_empty() {
+            return Err(Error::UnknownFields(unknown));
+        }
+        let body = Client::read_columns(&ctx, &dam, id, &fields).await?;
+        return Ok(conditional_json_in_units(&headers, body, units));
+    }
+    let body = Client::read(&ctx, &dam, id).await?;
+
+    Ok(conditional_json_in_units(&headers, body, units))
+}
+
+/// The distance between two bodies at `at`, from their approximate heliocentric
+/// positions.
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/bodies/{id}/{other_id}/distance",
+    params(
+        ("id" = i64, Path, description = "Database id of the first body"),
+        ("other_id" = i64, Path, description = "Database id of the second body"),
+        DistanceParams,
+    ),
+    responses(
+        (status = 200, description = "The distance between the bodies", body = BodyDistance),
+        (status = 404, description = "No such body"),
+        (status = 422, description = "Invalid timestamp, or an unbound orbit"),
+    ),
+))]
+async fn get_distance(
+    State(dam): State<DataAccessManager>,
+    Path((id, other_id)): Path<(i64, i64)>,
+    Query(DistanceParams { at }): Query<DistanceParams>,
+) -> Result<Json<BodyDistance>> {
+    let epoch = parse_utc(&at)
+        .map_err(|_| Error::InvalidTimestamp(at))?
+        .with_timezone(&Utc);
+    let ctx = public_read_context();
+    let body = Client::read(&ctx, &dam, id).await?;
+    let other = Client::read(&ctx, &dam, other_id).await?;
+
+    let (x, y) = Client::heliocentric_position_at(&ctx, &dam, &body, epoch).await?;
+    let (other_x, other_y) = Client::heliocentric_position_at(&ctx, &dam, &other, epoch).await?;
+    let km = (x - other_x).hypot(y - other_y);
+
+    Ok(Json(BodyDistance {
+        from: id,
+        to: other_id,
+        at: format_utc_time(epoch),
+        km,
+        au: km / KM_PER_AU,
+    }))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    patch,
+    path = "/api/v1/bodies/{id}",
+    params(("id" = i64, Path, description = "Database id")),
+    responses(
+        (status = 200, description = "The updated body", body = CelestialBody),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 403, description = "Requires the editor role"),
+        (status = 404, description = "No such body"),
+        (status = 409, description = "Modified since `expected_updated_at`"),
+        (status = 422, description = "Invalid fields, or the new parent orbits this body"),
+    ),
+    security(("bearer" = [])),
+))]
+async fn update_body(
+    State(dam): State<DataAccessManager>,
+    ctx: RequestContext,
+    Path(id): Path<i64>,
+    ValidJson(payload): ValidJson<UpdateBody>,
+) -> Result<Json<CelestialBody>> {
+    ctx.require(Role::Editor)?;
+    let data = CelestialBodyUpdate {
+        name: payload.name,
+        parent_id: payload.parent_id,
+    };
+    Client::update_if_unchanged(&ctx, &dam, id, payload.expected_updated_at, data).await?;
+    let body = Client::read(&ctx, &dam, id).await?;
+
+    Ok(Json(body))
+}
+
+/// Move a body into another region, checking that the region exists.
+#[cfg_attr(feature = "openapi", utoipa::path(
+    patch,
+    path = "/api/v1/bodies/{id}/region",
+    params(("id" = i64, Path, description = "Database id")),
+    request_body = MoveBody,
+    responses(
+        (status = 200, description = "The moved body", body = CelestialBody),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 403, description = "Requires the editor role"),
+        (status = 404, description = "No such body, or no such region"),
+        (status = 422, description = "Malformed body"),
+    ),
+    security(("bearer" = [])),
+))]
+async fn move_body(
+    State(dam): State<DataAccessManager>,
+    ctx: RequestContext,
+    Path(id): Path<i64>,
+    ValidJson(MoveBody { region_id }): ValidJson<MoveBody>,
+) -> Result<Json<CelestialBody>> {
+    ctx.require(Role::Editor)?;
+    Client::move_to_region(&ctx, &dam, id, region_id).await?;
+    let body = Client::read(&ctx, &dam, id).await?;
+
+    Ok(Json(body))
+}
+
+/// Stream every body as CSV. The table is read a page at a time, by id (so rows
+/// written meanwhile do not shift the pages), and each page is sent as soon as
+/// it is written, so the export is never held in memory.
+///
+/// NOTE: the status has been sent by the time a page fails, so a failure
+///       aborts the body, and the client sees an incomplete download rather
+///       than a truncated file that looks complete.
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/bodies.csv",
+    responses((status = 200, description = "Every body, as CSV", content_type = "text/csv")),
+))]
+async fn export_bodies_csv(State(dam): State<DataAccessManager>) -> Response {
+    let (mut sender, body) = Body::channel();
+    tokio::spawn(async move {
+        let ctx = public_read_context();
+        // NOTE: `None` until the first page is read, which is also the one that
+        //       carries the header row.
+        let mut after_id = None;
+        loop {
+            let from = after_id.unwrap_or(i64::MIN);
+            let chunk = match Client::read_after(&ctx, &dam, from, CSV_PAGE_SIZE).await {
+                Ok(page) => write_csv_page(&page, after_id.is_none())
+                    .map(|chunk| (chunk, page.len(), page.last().map(|body| body.id))),
+                Err(err) => Err(err.into()),
+            };
+            let (chunk, rows, last_id) = match chunk {
+                Ok(chunk) => chunk,
+                Err(err) => {
+                    tracing::error!("csv export failed: {err}");
+                    sender.abort();
+                    return;
+                }
+            };
+            // NOTE: an error here means the client has gone away.
+            if sender.send_data(Bytes::from(chunk)).await.is_err() {
+                return;
+            }
+            if (rows as i64) < CSV_PAGE_SIZE {
+                return;
+            }
+            after_id = last_id;
+        }
+    });
+
+    let headers = [
+        (CONTENT_TYPE, "text/csv; charset=utf-8"),
+        (
+            CONTENT_DISPOSITION,
+            "attachment; filename=\"celestial_bodies.csv\"",
+        ),
+    ];
+
+    (headers, boxed(body)).into_response()
+}
+
+/// Stream every body as JSON Lines: one JSON object per line, in id order. Rows
+/// are sent as they are read from the database, so memory stays flat however
+/// many bodies there are.
+///
+/// NOTE: as with the CSV export, a failure part way through is logged and aborts
+///       the body, so the client sees the response end early rather than an
+///       apparently complete stream.
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/bodies.ndjson",
+    responses((status = 200, description = "Every body, one JSON object per line",
+        content_type = "application/x-ndjson")),
+))]
+async fn export_bodies_ndjson(State(dam): State<DataAccessManager>) -> Response {
+    let ctx = public_read_context();
+    let rows = Client::stream_all(&ctx, &dam);
+    let lines = stream::unfold(rows, |mut rows| async move {
+        let line = match rows.recv().await? {
+            Ok(body) => serde_json::to_vec(&body)
+                .map(|mut line| {
+                    line.push(b'\n');
+                    Bytes::from(line)
+                })
+                .map_err(BoxError::from),
+            Err(err) => Err(BoxError::from(err)),
+        };
+        if let Err(err) = &line {
+            tracing::error!("ndjson export failed: {err}");
+        }
+
+        Some((line, rows))
+    });
+
+    (
+        [(CONTENT_TYPE, "application/x-ndjson")],
+        StreamBody::new(lines),
+    )
+        .into_response()
+}
+
+fn write_csv_page(bodies: &[CelestialBody], with_header: bool) -> Result<Vec<u8>> {
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(Vec::new());
+    if with_header {
+        writer.write_record(CSV_HEADER)?;
+    }
+    for body in bodies {
+        writer.serialize(CsvBody::from(body))?;
+    }
+
+    writer
+        .into_inner()
+        .map_err(|err| Error::CsvExportFail(err.to_string()))
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils::{
+        body_create, create_test_user_authorization, initialise_test_environment, reset_database,
+    };
+    use crate::web::construct_routes;
+    use anyhow::Result;
+    use axum::http::StatusCode;
+    use axum_test_helper::TestClient;
+    use serde_json::{json, Value};
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_body_then_read_back() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let auth = create_test_user_authorization(&dam, "test_create_body", Role::Editor).await;
+        let client = TestClient::new(construct_routes(AppState::new(dam.clone())));
+
+        let res = client
+            .post("/api/v1/bodies")
+            .header("Authorization", &auth)
+            .json(&json!({
+                "name": "test_create_body_then_read_back",
+                "aphelion": 152_100_000.0,
+                "perihelion": 147_095_000.0,
+                "orbital_period": 365.256,
+            }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let created: Value = res.json().await;
+        let id = created["id"].as_i64().unwrap();
+        assert_eq!(created["region"], Value::Null);
+        assert_eq!(created["subregion"], Value::Null);
+
+        let body = Client::read(&RequestContext::root_context(), &dam, id).await?;
+        assert_eq!(body.name, "test_create_body_then_read_back");
+        assert_eq!(body.region, None);
+        assert_eq!(body.subregion, None);
+
+        let res = client.get(&format!("/api/v1/bodies/{id}")).send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let etag = res.headers()["etag"].to_str()?.to_string();
+        let fetched: Value = res.json().await;
+        assert_eq!(fetched, created);
+
+        let res = client
+            .get(&format!("/api/v1/bodies/{id}"))
+            .header("If-None-Match", &etag)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_body_rejects_inconsistent_period() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let auth = create_test_user_authorization(&dam, "test_inconsistent", Role::Editor).await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client
+            .post("/api/v1/bodies")
+            .header("Authorization", &auth)
+            .json(&json!({
+                "name": "test_create_body_rejects_inconsistent_period",
+                "aphelion": 152_100_000.0,
+                "perihelion": 147_095_000.0,
+                "orbital_period": 100.0,
+            }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(res.text().await.contains("365.2"));
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_body_requires_editor() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let viewer = create_test_user_authorization(&dam, "test_body_viewer", Role::Viewer).await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client
+            .post("/api/v1/bodies")
+            .header("Authorization", &viewer)
+            .json(&json!({
+                "name": "test_create_body_requires_editor",
+                "aphelion": 0.0,
+                "perihelion": 0.0,
+                "orbital_period": 0.0,
+            }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_body_rejects_invalid_fields() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let auth = create_test_user_authorization(&dam, "test_body_invalid", Role::Editor).await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client
+            .post("/api/v1/bodies")
+            .header("Authorization", &auth)
+            .json(&json!({
+                "name": "",
+                "aphelion": 100.0,
+                "perihelion": 200.0,
+                "orbital_period": 0.0,
+                "radius": -1.0,
+            }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let error: Value = res.json().await;
+        assert_eq!(error["error"]["code"], "validation_failed");
+        let fields: Vec<&str> = error["error"]["fields"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|field| field["field"].as_str())
+            .collect();
+        assert_eq!(fields, ["name", "radius", "perihelion"]);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_bodies_in_bulk() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let auth = create_test_user_authorization(&dam, "test_bulk_bodies", Role::Editor).await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+        let body = |name: &str| json!({ "name": name, "aphelion": 0.0, "perihelion": 0.0, "orbital_period": 0.0 });
+
+        let res = client
+            .post("/api/v1/bodies/bulk")
+            .header("Authorization", &auth)
+            .json(&json!([body("test_bulk_a"), body("test_bulk_b")]))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let created: Vec<Value> = res.json().await;
+        assert_eq!(created[0]["name"], "test_bulk_a");
+        assert_eq!(created[1]["name"], "test_bulk_b");
+
+        let res = client
+            .post("/api/v1/bodies/bulk")
+            .header("Authorization", &auth)
+            .json(&json!([body("test_bulk_c"), body("test_bulk_a")]))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(res.text().await.contains("item 1"));
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_delete_bodies_by_id_list() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let editor = create_test_user_authorization(&dam, "test_delete_editor", Role::Editor).await;
+        let viewer = create_test_user_authorization(&dam, "test_delete_viewer", Role::Viewer).await;
+        let create = |name: &str| Client::create(&ctx, &dam, body_create(name));
+        let a = create("test_delete_bodies_a").await?;
+        let b = create("test_delete_bodies_b").await?;
+        let client = TestClient::new(construct_routes(AppState::new(dam.clone())));
+        let url = format!("/api/v1/bodies?ids={a},{b},{b},999999");
+
+        let res = client
+            .delete(&url)
+            .header("Authorization", &viewer)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let res = client
+            .delete("/api/v1/bodies?ids=1,two")
+            .header("Authorization", &editor)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        // The duplicate is counted once, and the missing id not at all.
+        let res = client
+            .delete(&url)
+            .header("Authorization", &editor)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let report: Value = res.json().await;
+        assert_eq!(report["deleted"], 2);
+        assert!(Client::read(&ctx, &dam, a).await.is_err());
+        assert!(Client::read(&ctx, &dam, b).await.is_err());
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_move_body_to_region() -> Result<()> {
+        use crate::data_access::model::celestial_region::{self, RegionCreate};
+
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let auth = create_test_user_authorization(&dam, "test_move_editor", Role::Editor).await;
+        let region = RegionCreate {
+            name: "test_move_route_region".to_string(),
+            description: None,
+        };
+        let region_id = celestial_region::Client::create(&ctx, &dam, region).await?;
+        let id = Client::create(&ctx, &dam, body_create("test_move_route_body")).await?;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+        let move_body = |id: i64, region_id: i64| {
+            client
+                .patch(&format!("/api/v1/bodies/{id}/region"))
+                .header("Authorization", &auth)
+                .json(&json!({ "region_id": region_id }))
+                .send()
+        };
+
+        let res = move_body(id, region_id).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body: Value = res.json().await;
+        assert_eq!(body["region"], region_id);
+
+        assert_eq!(
+            move_body(999_999, region_id).await.status(),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(move_body(id, 999_999).await.status(), StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_move_body_rejects_invalid_payload() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let auth = create_test_user_authorization(&dam, "test_move_invalid", Role::Editor).await;
+        let id = Client::create(&ctx, &dam, body_create("test_move_invalid_body")).await?;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client
+            .patch(&format!("/api/v1/bodies/{id}/region"))
+            .header("Authorization", &auth)
+            .json(&json!({ "region_id": "not an id" }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let error: Value = res.json().await;
+        assert_eq!(error["error"]["code"], "invalid_json");
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_stale_body_update_is_conflict() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let auth = create_test_user_authorization(&dam, "test_stale_body", Role::Editor).await;
+        let ctx = RequestContext::root_context();
+        let id = Client::create(&ctx, &dam, body_create("test_stale_body_update")).await?;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client
+            .patch(&format!("/api/v1/bodies/{id}"))
+            .header("Authorization", &auth)
+            .json(&json!({ "name": "test_stale_body_first", "expected_updated_at": null }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let updated: Value = res.json().await;
+
+        let res = client
+            .patch(&format!("/api/v1/bodies/{id}"))
+            .header("Authorization", &auth)
+            .json(&json!({ "name": "test_stale_body_second", "expected_updated_at": null }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+
+        let res = client
+            .patch(&format!("/api/v1/bodies/{id}"))
+            .header("Authorization", &auth)
+            .json(&json!({
+                "name": "test_stale_body_third",
+                "expected_updated_at": updated["updated_at"],
+            }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let updated: Value = res.json().await;
+
+        // A name that is sent is checked as on create; one that is not, is not.
+        let res = client
+            .patch(&format!("/api/v1/bodies/{id}"))
+            .header("Authorization", &auth)
+            .json(&json!({ "name": "", "expected_updated_at": updated["updated_at"] }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let error: Value = res.json().await;
+        assert_eq!(
+            error["error"]["fields"],
+            json!([{ "field": "name", "message": "must not be empty" }])
+        );
+        let res = client
+            .patch(&format!("/api/v1/bodies/{id}"))
+            .header("Authorization", &auth)
+            .json(&json!({ "expected_updated_at": updated["updated_at"] }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_search_bodies() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        for name in ["test_search_body_one", "test_search_body_two"] {
+            Client::create(&ctx, &dam, body_create(name)).await?;
+        }
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client
+            .get("/api/v1/bodies/search?q=search_body")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let bodies: Vec<Value> = res.json().await;
+        assert_eq!(bodies.len(), 2);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_suggest_bodies_despite_a_typo() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        reset_database(&dam).await?;
+        let ctx = RequestContext::root_context();
+        for name in ["Jupiter", "Juniper", "Saturn"] {
+            Client::create(&ctx, &dam, body_create(name)).await?;
+        }
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+        let suggest = |q: &'static str| {
+            let client = &client;
+            async move {
+                let res = client
+                    .get(&format!("/api/v1/bodies/suggest?q={q}"))
+                    .send()
+                    .await;
+                assert_eq!(res.status(), StatusCode::OK);
+                res.json::<Vec<Value>>().await
+            }
+        };
+
+        // One character missing, replaced or added.
+        for q in ["jupter", "Jupiler", "jupiterr"] {
+            let suggestions = suggest(q).await;
+            assert_eq!(suggestions[0]["name"], "Jupiter", "{q}");
+            assert!(suggestions[0]["score"].as_f64().unwrap() > 0.8, "{q}");
+        }
+        let suggestions = suggest("saturn").await;
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0]["score"], 1.0);
+
+        assert!(suggest("").await.is_empty());
+        assert!(suggest("neptune").await.is_empty());
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_get_all_bodies_by_cursor() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        reset_database(&dam).await?;
+        let ctx = RequestContext::root_context();
+        let mut ids = Vec::new();
+        for index in 0..5 {
+            let name = format!("test_cursor_{index}");
+            ids.push(Client::create(&ctx, &dam, body_create(&name)).await?);
+        }
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+        let get_page = |query: String| {
+            let client = &client;
+            async move {
+                let res = client.get(&format!("/api/v1/bodies?{query}")).send().await;
+                assert_eq!(res.status(), StatusCode::OK, "{query}");
+                res.json::<Value>().await
+            }
+        };
+        let page_ids = |page: &Value| -> Vec<i64> {
+            let bodies = page["bodies"].as_array().unwrap();
+            bodies
+                .iter()
+                .map(|body| body["id"].as_i64().unwrap())
+                .collect()
+        };
+
+        let mut seen = Vec::new();
+        let mut pages = 0;
+        let mut page = get_page("limit=2".to_string()).await;
+        loop {
+            pages += 1;
+            seen.extend(page_ids(&page));
+            let Some(cursor) = page["next_cursor"].as_str() else {
+                break;
+            };
+            // The cursor is opaque, not the raw id.
+            assert_ne!(cursor, seen.last().unwrap().to_string());
+            page = get_page(format!("limit=2&after_id={cursor}")).await;
+        }
+        assert_eq!(seen, ids);
+        assert_eq!(pages, 3);
+
+        // A full last page has no cursor either.
+        let page = get_page("limit=5".to_string()).await;
+        assert_eq!(page_ids(&page), ids);
+        assert!(page["next_cursor"].is_null());
+
+        // The offset variant gives the same pages.
+        let page = get_page("limit=2&offset=2".to_string()).await;
+        assert_eq!(page_ids(&page), ids[2..4]);
+        assert!(page["next_cursor"].is_string());
+
+        let res = client.get("/api/v1/bodies?after_id=42").send().await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: Value = res.json().await;
+        assert_eq!(body["error"]["code"], "invalid_cursor");
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_get_body_in_units() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        reset_database(&dam).await?;
+        let ctx = RequestContext::root_context();
+        let data = CelestialBodyCreate {
+            aphelion: 152_100_000.0,
+            perihelion: 147_095_000.0,
+            orbital_period: 365.256,
+            radius: 6_371.0,
+            mass: 5.972e24,
+            ..body_create("test_units_earth")
+        };
+        let id = Client::create(&ctx, &dam, data).await?;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        // Without units, the body is as stored.
+        let res = client.get(&format!("/api/v1/bodies/{id}")).send().await;
+        let body: Value = res.json().await;
+        assert_eq!(body["radius"], 6_371.0);
+        assert!(body.get("units").is_none());
+
+        let res = client
+            .get(&format!("/api/v1/bodies/{id}?units=km"))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body: Value = res.json().await;
+        assert_eq!(body["aphelion"], 152_100_000.0);
+        assert_eq!(body["semi_major_axis"], 149_597_500.0);
+        assert_eq!(body["units"], "km");
+
+        let res = client
+            .get(&format!("/api/v1/bodies/{id}"))
+            .header("Accept-Units", "earth_radii")
+            .send()
+            .await;
+        let body: Value = res.json().await;
+        assert_eq!(body["radius"], 1.0);
+        assert_eq!(body["units"], "earth_radii");
+
+        // The query string wins over the header, and lists are converted too.
+        let res = client
+            .get("/api/v1/bodies?units=au")
+            .header("Accept-Units", "km")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let bodies: Value = res.json().await;
+        let semi_major_axis = bodies[0]["semi_major_axis"].as_f64().unwrap();
+        assert!((semi_major_axis - 1.0).abs() < 1e-3, "{semi_major_axis}");
+        assert_eq!(bodies[0]["units"], "au");
+
+        let res = client
+            .get(&format!("/api/v1/bodies/{id}?units=furlongs"))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let body: Value = res.json().await;
+        assert_eq!(body["error"]["code"], "invalid_units");
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_get_body_fields() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let data = CelestialBodyCreate {
+            radius: 6_371.0,
+            ..body_create("test_fields")
+        };
+        let id = Client::create(&ctx, &dam, data).await?;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client
+            .get(&format!("/api/v1/bodies/{id}?fields=id,name,radius"))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body: Value = res.json().await;
+        assert_eq!(
+            body,
+            json!({ "id": id, "name": "test_fields", "radius": 6_371.0 })
+        );
+
+        // Unknown fields are ignored, unless strict.
+        let res = client
+            .get(&format!("/api/v1/bodies/{id}?fields=name,colour"))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body: Value = res.json().await;
+        assert_eq!(body, json!({ "name": "test_fields" }));
+
+        let res = client
+            .get(&format!(
+                "/api/v1/bodies/{id}?fields=name,colour,\"name\"&strict=true"
+            ))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let body: Value = res.json().await;
+        assert_eq!(body["error"]["code"], "unknown_fields");
+        assert_eq!(
+            body["error"]["message"],
+            "unknown field(s): colour, \"name\""
+        );
+
+        let res = client.get("/api/v1/bodies/999999?fields=name").send().await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_get_bodies_by_period_and_distance() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        reset_database(&dam).await?;
+        let ctx = RequestContext::root_context();
+        // Name, aphelion and perihelion (km), orbital period (days); deliberately
+        // not created in order.
+        let planets = [
+            ("Earth", 152_100_000.0, 147_095_000.0, 365.256),
+            ("Mars", 249_261_000.0, 206_650_000.0, 686.980),
+            ("Mercury", 69_817_000.0, 46_001_000.0, 87.969),
+            ("Venus", 108_939_000.0, 107_477_000.0, 224.701),
+        ];
+        for (name, aphelion, perihelion, orbital_period) in planets {
+            let data = CelestialBodyCreate {
+                aphelion,
+                perihelion,
+                orbital_period,
+                ..body_create(name)
+            };
+            Client::create(&ctx, &dam, data).await?;
+        }
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+        let names = |bodies: Value| -> Vec<String> {
+            let bodies = bodies.as_array().unwrap();
+            bodies
+                .iter()
+                .map(|body| body["name"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        for path in [
+            "/api/v1/bodies/by-period?order=asc",
+            "/api/v1/bodies/by-distance",
+        ] {
+            let res = client.get(path).send().await;
+            assert_eq!(res.status(), StatusCode::OK, "{path}");
+            assert_eq!(
+                names(res.json().await),
+                ["Mercury", "Venus", "Earth", "Mars"],
+                "{path}"
+            );
+        }
+        let res = client
+            .get("/api/v1/bodies/by-distance?order=desc")
+            .send()
+            .await;
+        assert_eq!(
+            names(res.json().await),
+            ["Mars", "Earth", "Venus", "Mercury"]
+        );
+
+        let res = client
+            .get("/api/v1/bodies/by-period?order=sideways")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_get_all_bodies_sorted() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        for name in ["test_list_sorted_a", "test_list_sorted_b"] {
+            Client::create(&ctx, &dam, body_create(name)).await?;
+        }
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+        let names = |bodies: Vec<Value>| -> Vec<String> {
+            bodies
+                .iter()
+                .filter_map(|body| body["name"].as_str())
+                .filter(|name| name.starts_with("test_list_sorted_"))
+                .map(str::to_string)
+                .collect()
+        };
+
+        let res = client.get("/api/v1/bodies?sort=name&dir=desc").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            names(res.json().await),
+            ["test_list_sorted_b", "test_list_sorted_a"]
+        );
+
+        // An unknown column falls back to ordering by id.
+        let res = client
+            .get("/api/v1/bodies?sort=nonsense&dir=desc")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            names(res.json().await),
+            ["test_list_sorted_b", "test_list_sorted_a"]
+        );
+
+        let res = client
+            .get("/api/v1/bodies?sort=name&dir=sideways")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        // Both bodies were just created, so are included since the epoch but not
+        // since the far future.
+        let res = client.get("/api/v1/bodies?updated_since=0").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(names(res.json().await).len(), 2);
+        let res = client
+            .get("/api/v1/bodies?updated_since=9999999999")
+            .send()
+            .await;
+        assert!(names(res.json().await).is_empty());
+
+        // Neither body has a region, so the names are present but null.
+        let res = client
+            .get("/api/v1/bodies?expand=region,subregion")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let bodies: Vec<Value> = res.json().await;
+        let body = bodies
+            .iter()
+            .find(|body| body["name"] == "test_list_sorted_a")
+            .unwrap();
+        assert!(body["region_name"].is_null() && body["subregion_name"].is_null());
+        assert!(body.get("region_name").is_some());
+
+        let res = client.get("/api/v1/bodies?expand=moons").send().await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_export_bodies_csv() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        for name in ["test_export_csv_a", "test_export_csv_b"] {
+            let data = CelestialBodyCreate {
+                aphelion: 152_100_000.0,
+                perihelion: 147_095_000.0,
+                orbital_period: 365.256,
+                ..body_create(name)
+            };
+            Client::create(&ctx, &dam, data).await?;
+        }
+        let expected_rows = Client::count(&ctx, &dam).await?;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client.get("/api/v1/bodies.csv").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[CONTENT_TYPE], "text/csv; charset=utf-8");
+        assert!(res.headers()[CONTENT_DISPOSITION]
+            .to_str()?
+            .starts_with("attachment"));
+
+        let text = res.text().await;
+        let mut reader = csv::Reader::from_reader(text.as_bytes());
+        assert_eq!(reader.headers()?, CSV_HEADER.as_slice());
+        let records = reader
+            .records()
+            .collect::<core::result::Result<Vec<_>, _>>()?;
+        assert_eq!(records.len() as i64, expected_rows);
+        let record = records
+            .iter()
+            .find(|record| &record[1] == "test_export_csv_a")
+            .unwrap();
+        assert!(chrono::DateTime::parse_from_rfc3339(&record[10]).is_ok());
+        assert_eq!(&record[11], "");
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_export_bodies_ndjson() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        for name in ["test_export_ndjson_a", "test_export_ndjson_b"] {
+            let data = CelestialBodyCreate {
+                aphelion: 152_100_000.0,
+                perihelion: 147_095_000.0,
+                orbital_period: 365.256,
+                ..body_create(name)
+            };
+            Client::create(&ctx, &dam, data).await?;
+        }
+        let expected = Client::read_all(&ctx, &dam).await?;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client.get("/api/v1/bodies.ndjson").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[CONTENT_TYPE], "application/x-ndjson");
+
+        let text = res.text().await;
+        assert!(text.ends_with('\n'));
+        let lines = text
+            .lines()
+            .map(serde_json::from_str::<Value>)
+            .collect::<core::result::Result<Vec<_>, _>>()?;
+        assert_eq!(lines.len(), expected.len());
+        // In id order, one body per line, as the JSON listing would give them.
+        for (line, body) in lines.iter().zip(&expected) {
+            assert_eq!(*line, serde_json::to_value(body)?);
+        }
+        assert!(lines
+            .iter()
+            .any(|line| line["name"] == "test_export_ndjson_b"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_csv_page_without_bodies() -> Result<()> {
+        let with_header = write_csv_page(&[], true)?;
+        assert_eq!(
+            String::from_utf8(with_header)?,
+            format!("{}\n", CSV_HEADER.join(","))
+        );
+        assert!(write_csv_page(&[], false)?.is_empty());
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_import_bodies() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let auth = create_test_user_authorization(&dam, "test_import", Role::Editor).await;
+        let ctx = RequestContext::root_context();
+        let before = Client::count(&ctx, &dam).await?;
+        let client = TestClient::new(construct_routes(AppState::new(dam.clone())));
+        let csv = "name,aphelion,perihelion,orbital_period\n\
+                   test_import_a,152100000,147095000,365.256\n\
+                   test_import_b,152100000,147095000,365.256\n";
+
+        let res = client
+            .post("/api/v1/bodies/import")
+            .header("Authorization", &auth)
+            .header("Content-Type", "text/csv")
+            .body(csv)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let report: Value = res.json().await;
+        assert_eq!(report, json!({ "imported": 2, "errors": [] }));
+        assert_eq!(Client::count(&ctx, &dam).await?, before + 2);
+
+        // The same rows as JSON, in a multipart upload: now both are duplicates.
+        let json = r#"[{"name": "test_import_a", "aphelion": 152100000, "perihelion": 147095000, "orbital_period": 365.256}]"#;
+        let multipart = format!(
+            "--BOUNDARY\r\n\
+             Content-Disposition: form-data; name=\"file\"; filename=\"bodies.json\"\r\n\r\n\
+             {json}\r\n\
+             --BOUNDARY--\r\n"
+        );
+        let res = client
+            .post("/api/v1/bodies/import")
+            .header("Authorization", &auth)
+            .header("Content-Type", "multipart/form-data; boundary=BOUNDARY")
+            .body(multipart)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let report: Value = res.json().await;
+        assert_eq!(report["imported"], 0);
+        assert_eq!(report["errors"][0]["line"], 1);
+        assert_eq!(report["errors"][0]["reason"], "resource already exists");
+
+        // Neither CSV nor JSON.
+        let res = client
+            .post("/api/v1/bodies/import")
+            .header("Authorization", &auth)
+            .header("Content-Type", "text/plain")
+            .body("test_import")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_import_bodies_with_a_malformed_row() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let auth =
+            create_test_user_authorization(&dam, "test_import_malformed", Role::Editor).await;
+        let ctx = RequestContext::root_context();
+        let before = Client::count(&ctx, &dam).await?;
+        let client = TestClient::new(construct_routes(AppState::new(dam.clone())));
+        let csv = "name,aphelion,perihelion,orbital_period\n\
+                   test_import_malformed_a,152100000,147095000,365.256\n\
+                   test_import_malformed_b,not-a-number,147095000,365.256\n\
+                   test_import_malformed_c,152100000,147095000,365.256\n\
+                   test_import_malformed_d,147095000,152100000,365.256\n";
+        let import = |strict: bool| {
+            client
+                .post(&format!("/api/v1/bodies/import?strict={strict}"))
+                .header("Authorization", &auth)
+                .header("Content-Type", "text/csv")
+                .body(csv)
+                .send()
+        };
+
+        // Strict: the whole file is rejected.
+        let res = import(true).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let report: Value = res.json().await;
+        assert_eq!(report["imported"], 0);
+        assert_eq!(report["errors"][0]["line"], 3);
+        assert_eq!(Client::count(&ctx, &dam).await?, before);
+
+        // Lenient: the other rows are imported, and the bad ones reported. A row
+        // that parses is still checked as on create.
+        let res = import(false).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let report: Value = res.json().await;
+        assert_eq!(report["imported"], 2);
+        assert_eq!(report["errors"].as_array().unwrap().len(), 2);
+        assert_eq!(report["errors"][0]["line"], 3);
+        assert_eq!(report["errors"][1]["line"], 5);
+        assert_eq!(
+            report["errors"][1]["reason"],
+            "perihelion must not be greater than the aphelion"
+        );
+        assert_eq!(Client::count(&ctx, &dam).await?, before + 2);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_distance_between_earth_and_mars() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let create = |name: &str, aphelion: f64, perihelion: f64, orbital_period: f64| {
+            let data = CelestialBodyCreate {
+                aphelion,
+                perihelion,
+                orbital_period,
+                ..body_create(name)
+            };
+            Client::create(&ctx, &dam, data)
+        };
+        let earth = create("test_distance_earth", 152_100_000.0, 147_095_000.0, 365.256).await?;
+        let mars = create("test_distance_mars", 249_261_000.0, 206_650_000.0, 686.98).await?;
+        let client = TestClient::new(construct_routes(AppState::new(dam.clone())));
+        let distance = |at: &str| {
+            client
+                .get(&format!("/api/v1/bodies/{earth}/{mars}/distance?at={at}"))
+                .send()
+        };
+
+        // At the reference epoch both are at perihelion, in the same direction.
+        let res = distance("2000-01-01T12:00:00Z").await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body: Value = res.json().await;
+        assert_eq!(body["from"], earth);
+        assert_eq!(body["to"], mars);
+        let km = body["km"].as_f64().unwrap();
+        assert!((km - 59_555_000.0).abs() < 1.0, "{km}");
+
+        // Half an Earth year later, Earth is at aphelion.
+        let res = distance("2000-07-02T03:04:19.200Z").await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body: Value = res.json().await;
+        let km = body["km"].as_f64().unwrap();
+        assert!((km - 239_365_339.0).abs() < 1_000.0, "{km}");
+        assert!((body["au"].as_f64().unwrap() - 1.6).abs() < 1e-3);
+
+        let res = distance("yesterday").await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let missing = mars + 1_000;
+        let res = client
+            .get(&format!(
+                "/api/v1/bodies/{earth}/{missing}/distance?at=2000-01-01T12:00:00Z"
+            ))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+}
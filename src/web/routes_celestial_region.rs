@@ -0,0 +1,787 @@
+use crate::config::get_config;
+use crate::data_access::model::celestial_region::{Client, Region, RegionCreate, RegionUpdate};
+use crate::data_access::model::celestial_subregion::{self, Subregion};
+use crate::data_access::DataAccessManager;
+use crate::web::etag::conditional_json;
+use crate::web::mw_body_limit::limit_body;
+use crate::web::pagination::Pagination;
+use crate::web::search::{SearchParams, SEARCH_LIMIT};
+use crate::web::validation::{check_name, FieldError, ValidJson, Validate};
+use crate::web::{public_read_context, AppState, Result};
+use crate::{RequestContext, Role};
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::Response;
+use axum::routing::{get, patch, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+
+// -----------------------------------------------------------------------------
+// Types
+// -----------------------------------------------------------------------------
+
+/// `expected_updated_at` is the `updated_at` the client last saw (`null` if the
+/// region has never been updated); the write is rejected if it has since changed.
+#[derive(Deserialize)]
+struct UpdateRegionName {
+    name: String,
+    expected_updated_at: Option<i64>,
+}
+
+impl Validate for UpdateRegionName {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        check_name(&mut errors, &self.name);
+
+        errors
+    }
+}
+
+#[derive(Deserialize)]
+struct UpdateRegionDescription {
+    description: String,
+    expected_updated_at: Option<i64>,
+}
+
+/// Any description is accepted, including an empty one.
+impl Validate for UpdateRegionDescription {
+    fn validate(&self) -> Vec<FieldError> {
+        Vec::new()
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Routes
+// -----------------------------------------------------------------------------
+
+pub fn region_routes() -> Router<AppState> {
+    let max_bytes = get_config().MAX_REQUEST_BODY_BYTES;
+    Router::new()
+        .route(
+            "/regions",
+            limit_body(get(get_all_regions).post(create_region), max_bytes),
+        )
+        .route("/regions/search", get(search_regions))
+        .route(
+            "/regions/batch",
+            limit_body(post(get_regions_batch), max_bytes),
+        )
+        .route("/regions/:id", get(get_region).delete(delete_region))
+        .route("/regions/:id/subregions", get(get_region_subregions))
+        .route(
+            "/regions/:id/name",
+            limit_body(patch(update_region_name), max_bytes),
+        )
+        .route(
+            "/regions/:id/description",
+            limit_body(patch(update_region_description), max_bytes),
+        )
+}
+
+// -----------------------------------------------------------------------------
+// Handlers
+// -----------------------------------------------------------------------------
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/api/v1/regions",
+    request_body = RegionCreate,
+    responses(
+        (status = 200, description = "The created region", body = Region),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 403, description = "Requires the editor role"),
+        (status = 409, description = "A region with this name already exists"),
+        (status = 422, description = "Invalid fields"),
+    ),
+    security(("bearer" = [])),
+))]
+async fn create_region(
+    State(dam): State<DataAccessManager>,
+    ctx: RequestContext,
+    ValidJson(new_region): ValidJson<RegionCreate>,
+) -> Result<Json<Region>> {
+    ctx.require(Role::Editor)?;
+    let id = Client::create(&ctx, &dam, new_region).await?;
+    let region = Client::read(&ctx, &dam, id).await?;
+
+    Ok(Json(region))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/regions",
+    params(Pagination),
+    responses(
+        (status = 200, description = "A page of regions", body = [Region]),
+        (status = 422, description = "Invalid pagination parameters"),
+    ),
+))]
+async fn get_all_regions(
+    State(dam): State<DataAccessManager>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<Vec<Region>>> {
+    let (limit, offset) = pagination.validate()?;
+    let ctx = public_read_context();
+    let regions = Client::read_page(&ctx, &dam, limit, offset).await?;
+
+    Ok(Json(regions))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/regions/search",
+    params(SearchParams),
+    responses(
+        (status = 200, description = "Regions whose name contains `q`", body = [Region]),
+    ),
+))]
+async fn search_regions(
+    State(dam): State<DataAccessManager>,
+    Query(SearchParams { q }): Query<SearchParams>,
+) -> Result<Json<Vec<Region>>> {
+    let ctx = public_read_context();
+    let regions = Client::search_by_name(&ctx, &dam, &q, SEARCH_LIMIT).await?;
+
+    Ok(Json(regions))
+}
+
+/// Look up several regions at once, *eg* to name the regions of a page of bodies.
+/// Duplicate ids are ignored, and ids with no region are left out of the result.
+///
+/// NOTE: this is a read, but is a `POST` to carry the ids in the body, so (as with
+///       any other `POST`) it needs a token.
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/api/v1/regions/batch",
+    request_body = [i64],
+    responses(
+        (status = 200, description = "The regions found, ordered by id", body = [Region]),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 422, description = "Malformed id list, or more than 500 distinct ids"),
+    ),
+    security(("bearer" = [])),
+))]
+async fn get_regions_batch(
+    State(dam): State<DataAccessManager>,
+    ctx: RequestContext,
+    ValidJson(mut ids): ValidJson<Vec<i64>>,
+) -> Result<Json<Vec<Region>>> {
+    ids.sort_unstable();
+    let regions = Client::read_many(&ctx, &dam, &ids).await?;
+
+    Ok(Json(regions))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/regions/{id}",
+    params(("id" = i64, Path, description = "Database id")),
+    responses(
+        (status = 200, description = "The region", body = Region),
+        (status = 304, description = "Unchanged since the `If-None-Match` ETag"),
+        (status = 404, description = "No such region"),
+    ),
+))]
+async fn get_region(
+    State(dam): State<DataAccessManager>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let ctx = public_read_context();
+    let region = Client::read(&ctx, &dam, id).await?;
+
+    Ok(conditional_json(&headers, region))
+}
+
+/// The subregions of a region, ordered by id.
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/regions/{id}/subregions",
+    params(("id" = i64, Path, description = "Database id of the region")),
+    responses(
+        (status = 200, description = "The region's subregions", body = [Subregion]),
+        (status = 404, description = "No such region"),
+    ),
+))]
+async fn get_region_subregions(
+    State(dam): State<DataAccessManager>,
+    Path(id): Path<i64>,
+) -> Result<Json<Vec<Subregion>>> {
+    let ctx = public_read_context();
+    // NOTE: read, so that a missing region is a 404 rather than an empty list.
+    Client::read(&ctx, &dam, id).await?;
+    let subregions = celestial_subregion::Client::read_by_region(&ctx, &dam, id).await?;
+
+    Ok(Json(subregions))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    delete,
+    path = "/api/v1/regions/{id}",
+    params(("id" = i64, Path, description = "Database id")),
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 403, description = "Requires the editor role"),
+        (status = 404, description = "No such region"),
+    ),
+    security(("bearer" = [])),
+))]
+async fn delete_region(
+    State(dam): State<DataAccessManager>,
+    ctx: RequestContext,
+    Path(id): Path<i64>,
+) -> Result<StatusCode> {
+    ctx.require(Role::Editor)?;
+    Client::delete(&ctx, &dam, id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    patch,
+    path = "/api/v1/regions/{id}/name",
+    params(("id" = i64, Path, description = "Database id")),
+    responses(
+        (status = 200, description = "The updated region", body = Region),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 403, description = "Requires the editor role"),
+        (status = 404, description = "No such region"),
+        (status = 409, description = "Modified since `expected_updated_at`"),
+        (status = 422, description = "Invalid name"),
+    ),
+    security(("bearer" = [])),
+))]
+async fn update_region_name(
+    State(dam): State<DataAccessManager>,
+    ctx: RequestContext,
+    Path(id): Path<i64>,
+    ValidJson(payload): ValidJson<UpdateRegionName>,
+) -> Result<Json<Region>> {
+    let data = RegionUpdate {
+        name: Some(payload.name),
+        ..Default::default()
+    };
+
+    update_region(&ctx, &dam, id, payload.expected_updated_at, data).await
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    patch,
+    path = "/api/v1/regions/{id}/description",
+    params(("id" = i64, Path, description = "Database id")),
+    responses(
+        (status = 200, description = "The updated region", body = Region),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 403, description = "Requires the editor role"),
+        (status = 404, description = "No such region"),
+        (status = 409, description = "Modified since `expected_updated_at`"),
+        (status = 422, description = "Malformed body"),
+    ),
+    security(("bearer" = [])),
+))]
+async fn update_region_description(
+    State(dam): State<DataAccessManager>,
+    ctx: RequestContext,
+    Path(id): Path<i64>,
+    ValidJson(payload): ValidJson<UpdateRegionDescription>,
+) -> Result<Json<Region>> {
+    let data = RegionUpdate {
+        description: Some(payload.description),
+        ..Default::default()
+    };
+
+    update_region(&ctx, &dam, id, payload.expected_updated_at, data).await
+}
+
+/// Shared by the single-field `PATCH` handlers: apply the update (if the region
+/// is unchanged since the client read it), then return the updated region.
+async fn update_region(
+    ctx: &RequestContext,
+    dam: &DataAccessManager,
+    id: i64,
+    expected_updated_at: Option<i64>,
+    data: RegionUpdate,
+) -> Result<Json<Region>> {
+    ctx.require(Role::Editor)?;
+    Client::update_if_unchanged(ctx, dam, id, expected_updated_at, data).await?;
+    let region = Client::read(ctx, dam, id).await?;
+
+    Ok(Json(region))
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils::{
+        create_test_user_authorization, initialise_test_environment, spawn_test_app,
+    };
+    use crate::data_access::ID_BATCH_LIMIT;
+    use crate::web::construct_routes;
+    use anyhow::Result;
+    use axum::http::StatusCode;
+    use axum_test_helper::TestClient;
+    use serde_json::{json, Value};
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_and_read_region_end_to_end() -> Result<()> {
+        let app = spawn_test_app().await;
+        let auth = create_test_user_authorization(&app.dam, "test_region_e2e", Role::Editor).await;
+
+        let res = app
+            .client
+            .post(app.url("/api/v1/regions"))
+            .header("Authorization", &auth)
+            .json(&json!({ "name": "test_region_e2e", "description": "Over the wire." }))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        let created: Value = res.json().await?;
+        let id = created["id"].as_i64().unwrap();
+
+        let res = app
+            .client
+            .get(app.url(&format!("/api/v1/regions/{id}")))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        // The middleware ran too.
+        assert!(res.headers().contains_key("x-request-id"));
+        let region: Value = res.json().await?;
+        assert_eq!(region, created);
+        assert_eq!(region["name"], "test_region_e2e");
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_get_all_regions_paginated() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        for name in ["test_paginated_a", "test_paginated_b"] {
+            let data = RegionCreate {
+                name: name.to_string(),
+                description: None,
+            };
+            Client::create(&ctx, &dam, data).await?;
+        }
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client.get("/api/v1/regions?limit=1&offset=0").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let regions: Vec<Value> = res.json().await;
+        assert_eq!(regions.len(), 1);
+
+        let res = client.get("/api/v1/regions?limit=500").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_get_all_regions_rejects_out_of_range_pagination() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        for query in ["limit=501", "limit=0", "offset=-1"] {
+            let res = client.get(&format!("/api/v1/regions?{query}")).send().await;
+            assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY, "{query}");
+        }
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_update_region_name_and_description() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let data = RegionCreate {
+            name: "test_update_region_before".to_string(),
+            description: None,
+        };
+        let id = Client::create(&ctx, &dam, data).await?;
+        let auth = create_test_user_authorization(&dam, "test_update_region", Role::Editor).await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client
+            .patch(&format!("/api/v1/regions/{id}/name"))
+            .header("Authorization", &auth)
+            .json(&json!({ "name": "test_update_region_after" }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let region: Value = res.json().await;
+        assert_eq!(region["name"], "test_update_region_after");
+        assert!(region["updated_at"].is_i64());
+
+        let res = client
+            .patch(&format!("/api/v1/regions/{id}/name"))
+            .header("Authorization", &auth)
+            .json(&json!({ "name": "x".repeat(101), "expected_updated_at": region["updated_at"] }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let error: Value = res.json().await;
+        assert_eq!(error["error"]["code"], "validation_failed");
+        assert_eq!(error["error"]["fields"][0]["field"], "name");
+
+        let res = client
+            .patch(&format!("/api/v1/regions/{id}/description"))
+            .header("Authorization", &auth)
+            .json(&json!({
+                "description": "A description",
+                "expected_updated_at": region["updated_at"],
+            }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let region: Value = res.json().await;
+        assert_eq!(region["name"], "test_update_region_after");
+        assert_eq!(region["description"], "A description");
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_get_region_is_conditional_on_etag() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let data = RegionCreate {
+            name: "test_etag_region_before".to_string(),
+            description: None,
+        };
+        let id = Client::create(&ctx, &dam, data).await?;
+        let auth = create_test_user_authorization(&dam, "test_etag_region", Role::Editor).await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client.get(&format!("/api/v1/regions/{id}")).send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let etag = res.headers()["etag"].to_str()?.to_string();
+
+        let res = client
+            .get(&format!("/api/v1/regions/{id}"))
+            .header("If-None-Match", &etag)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(res.headers()["etag"], etag.as_str());
+
+        let res = client
+            .patch(&format!("/api/v1/regions/{id}/name"))
+            .header("Authorization", &auth)
+            .json(&json!({ "name": "test_etag_region_after" }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = client
+            .get(&format!("/api/v1/regions/{id}"))
+            .header("If-None-Match", &etag)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_ne!(res.headers()["etag"], etag.as_str());
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_stale_region_update_is_conflict() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let data = RegionCreate {
+            name: "test_stale_region_update".to_string(),
+            description: None,
+        };
+        let id = Client::create(&ctx, &dam, data).await?;
+        let auth = create_test_user_authorization(&dam, "test_stale_region", Role::Editor).await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        // Two clients both read the region while `updated_at` was still null.
+        let res = client
+            .patch(&format!("/api/v1/regions/{id}/description"))
+            .header("Authorization", &auth)
+            .json(&json!({ "description": "First", "expected_updated_at": null }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = client
+            .patch(&format!("/api/v1/regions/{id}/description"))
+            .header("Authorization", &auth)
+            .json(&json!({ "description": "Second", "expected_updated_at": null }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+        let error: Value = res.json().await;
+        assert_eq!(error["error"]["code"], "stale_write");
+
+        let res = client.get(&format!("/api/v1/regions/{id}")).send().await;
+        let region: Value = res.json().await;
+        assert_eq!(region["description"], "First");
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_update_region_missing_id() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let auth =
+            create_test_user_authorization(&dam, "test_update_region_missing", Role::Editor).await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client
+            .patch("/api/v1/regions/999999/name")
+            .header("Authorization", &auth)
+            .json(&json!({ "name": "test_update_region_missing_id" }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let res = client
+            .patch("/api/v1/regions/999999/description")
+            .header("Authorization", &auth)
+            .json(&json!({ "description": "Nowhere" }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_get_and_delete_region() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let data = RegionCreate {
+            name: "test_get_and_delete_region".to_string(),
+            description: None,
+        };
+        let id = Client::create(&ctx, &dam, data).await?;
+        let auth = create_test_user_authorization(&dam, "test_delete_region", Role::Editor).await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client.get(&format!("/api/v1/regions/{id}")).send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = client
+            .delete(&format!("/api/v1/regions/{id}"))
+            .header("Authorization", &auth)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_missing_region_is_not_found() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let auth = create_test_user_authorization(&dam, "test_missing_region", Role::Editor).await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client.get("/api/v1/regions/999999").send().await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let res = client
+            .delete("/api/v1/regions/999999")
+            .header("Authorization", &auth)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_region_duplicate_name_is_conflict() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let auth = create_test_user_authorization(&dam, "test_region_conflict", Role::Editor).await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+        let new_region = json!({ "name": "test_create_region_duplicate_name" });
+
+        let res = client
+            .post("/api/v1/regions")
+            .header("Authorization", &auth)
+            .json(&new_region)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = client
+            .post("/api/v1/regions")
+            .header("Authorization", &auth)
+            .json(&new_region)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+        let body = res.text().await;
+        assert!(!body.contains("UNIQUE"));
+        let error: Value = serde_json::from_str(&body)?;
+        assert_eq!(error["error"]["code"], "conflict");
+        assert_eq!(error["error"]["message"], "resource already exists");
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_region_rejects_invalid_body() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let auth = create_test_user_authorization(&dam, "test_region_invalid", Role::Editor).await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client
+            .post("/api/v1/regions")
+            .header("Authorization", &auth)
+            .json(&json!({ "name": "  " }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let error: Value = res.json().await;
+        assert_eq!(error["error"]["code"], "validation_failed");
+        assert_eq!(
+            error["error"]["fields"],
+            json!([{ "field": "name", "message": "must not be empty" }])
+        );
+
+        // Bodies that don't deserialize are also 422s, in the same format.
+        for body in [r#"{"description": "no name"}"#, "not json"] {
+            let res = client
+                .post("/api/v1/regions")
+                .header("Authorization", &auth)
+                .header("Content-Type", "application/json")
+                .body(body)
+                .send()
+                .await;
+            assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY, "{body}");
+            let error: Value = res.json().await;
+            assert_eq!(error["error"]["code"], "invalid_json", "{body}");
+        }
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_region_writes_require_editor() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let viewer = create_test_user_authorization(&dam, "test_region_viewer", Role::Viewer).await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+        let new_region = json!({ "name": "test_region_writes_require_editor" });
+
+        let res = client
+            .post("/api/v1/regions")
+            .json(&new_region)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let res = client
+            .post("/api/v1/regions")
+            .header("Authorization", &viewer)
+            .json(&new_region)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_search_regions() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        for name in ["test_Search_Alpha", "test_search_beta", "test_other_100%"] {
+            let data = RegionCreate {
+                name: name.to_string(),
+                description: None,
+            };
+            Client::create(&ctx, &dam, data).await?;
+        }
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client.get("/api/v1/regions/search?q=SEARCH").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let regions: Vec<Value> = res.json().await;
+        let names: Vec<&str> = regions
+            .iter()
+            .map(|r| r["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["test_Search_Alpha", "test_search_beta"]);
+
+        // `%` is matched literally rather than as a wildcard.
+        let res = client.get("/api/v1/regions/search?q=0%25").send().await;
+        let regions: Vec<Value> = res.json().await;
+        assert_eq!(regions.len(), 1);
+
+        let res = client
+            .get("/api/v1/regions/search?q=no_such_region")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let regions: Vec<Value> = res.json().await;
+        assert!(regions.is_empty());
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_get_regions_batch() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let mut ids = Vec::new();
+        for name in ["test_batch_a", "test_batch_b"] {
+            let data = RegionCreate {
+                name: name.to_string(),
+                description: None,
+            };
+            ids.push(Client::create(&ctx, &dam, data).await?);
+        }
+        let auth = create_test_user_authorization(&dam, "test_region_batch", Role::Viewer).await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        // Present ids (one repeated) and an absent one.
+        let res = client
+            .post("/api/v1/regions/batch")
+            .header("Authorization", &auth)
+            .json(&json!([ids[1], 999_999, ids[0], ids[1]]))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let regions: Vec<Value> = res.json().await;
+        let names: Vec<&str> = regions
+            .iter()
+            .map(|r| r["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["test_batch_a", "test_batch_b"]);
+
+        let too_many: Vec<i64> = (1..=ID_BATCH_LIMIT as i64 + 1).collect();
+        let res = client
+            .post("/api/v1/regions/batch")
+            .header("Authorization", &auth)
+            .json(&too_many)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,287 @@
+use crate::config::get_config;
+use crate::data_access::model::celestial_region;
+use crate::data_access::model::celestial_subregion::{Client, Subregion, SubregionCreate};
+use crate::data_access::{DataAccessManager, DbCrudServer};
+use crate::web::etag::conditional_json;
+use crate::web::mw_body_limit::limit_body;
+use crate::web::pagination::Pagination;
+use crate::web::validation::ValidJson;
+use crate::web::{public_read_context, AppState, Error, Result};
+use crate::{RequestContext, Role};
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::Response;
+use axum::routing::get;
+use axum::{Json, Router};
+
+// -----------------------------------------------------------------------------
+// Routes
+// -----------------------------------------------------------------------------
+
+pub fn subregion_routes() -> Router<AppState> {
+    let max_bytes = get_config().MAX_REQUEST_BODY_BYTES;
+    Router::new()
+        .route(
+            "/subregions",
+            limit_body(get(get_all_subregions).post(create_subregion), max_bytes),
+        )
+        .route(
+            "/subregions/:id",
+            get(get_subregion).delete(delete_subregion),
+        )
+}
+
+// -----------------------------------------------------------------------------
+// Handlers
+// -----------------------------------------------------------------------------
+
+/// Create a subregion within an existing region.
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/api/v1/subregions",
+    request_body = SubregionCreate,
+    responses(
+        (status = 200, description = "The created subregion", body = Subregion),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 403, description = "Requires the editor role"),
+        (status = 409, description = "A subregion with this name already exists"),
+        (status = 422, description = "Invalid fields, or no such region"),
+    ),
+    security(("bearer" = [])),
+))]
+async fn create_subregion(
+    State(dam): State<DataAccessManager>,
+    ctx: RequestContext,
+    ValidJson(new_subregion): ValidJson<SubregionCreate>,
+) -> Result<Json<Subregion>> {
+    ctx.require(Role::Editor)?;
+    // NOTE: the foreign key would also catch this, but as a 409 with no detail.
+    let region_id = new_subregion.region_id;
+    if !celestial_region::Client::exists(&ctx, &dam, region_id).await? {
+        return Err(Error::InvalidReference {
+            entity: celestial_region::Client::TABLE,
+            id: region_id,
+        });
+    }
+    let id = Client::create(&ctx, &dam, new_subregion).await?;
+    let subregion = Client::read(&ctx, &dam, id).await?;
+
+    Ok(Json(subregion))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/subregions",
+    params(Pagination),
+    responses(
+        (status = 200, description = "A page of subregions", body = [Subregion]),
+        (status = 422, description = "Invalid pagination parameters"),
+    ),
+))]
+async fn get_all_subregions(
+    State(dam): State<DataAccessManager>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<Vec<Subregion>>> {
+    let (limit, offset) = pagination.validate()?;
+    let ctx = public_read_context();
+    let subregions = Client::read_page(&ctx, &dam, limit, offset).await?;
+
+    Ok(Json(subregions))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/subregions/{id}",
+    params(("id" = i64, Path, description = "Database id")),
+    responses(
+        (status = 200, description = "The subregion", body = Subregion),
+        (status = 304, description = "Unchanged since the `If-None-Match` ETag"),
+        (status = 404, description = "No such subregion"),
+    ),
+))]
+async fn get_subregion(
+    State(dam): State<DataAccessManager>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let ctx = public_read_context();
+    let subregion = Client::read(&ctx, &dam, id).await?;
+
+    Ok(conditional_json(&headers, subregion))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    delete,
+    path = "/api/v1/subregions/{id}",
+    params(("id" = i64, Path, description = "Database id")),
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 403, description = "Requires the editor role"),
+        (status = 404, description = "No such subregion"),
+    ),
+    security(("bearer" = [])),
+))]
+async fn delete_subregion(
+    State(dam): State<DataAccessManager>,
+    ctx: RequestContext,
+    Path(id): Path<i64>,
+) -> Result<StatusCode> {
+    ctx.require(Role::Editor)?;
+    Client::delete(&ctx, &dam, id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils::{create_test_user_authorization, initialise_test_environment};
+    use crate::data_access::model::celestial_region::RegionCreate;
+    use crate::web::construct_routes;
+    use anyhow::Result;
+    use axum_test_helper::TestClient;
+    use serde_json::{json, Value};
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_subregion_within_a_region() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let auth =
+            create_test_user_authorization(&dam, "test_subregion_editor", Role::Editor).await;
+        let region = RegionCreate {
+            name: "test_subregion_parent".to_string(),
+            description: None,
+        };
+        let region_id = celestial_region::Client::create(&ctx, &dam, region).await?;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client
+            .post("/api/v1/subregions")
+            .header("Authorization", &auth)
+            .json(&json!({ "name": "test_subregion_child", "region_id": region_id }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let created: Value = res.json().await;
+        assert_eq!(created["region_id"], region_id);
+
+        let res = client
+            .get(&format!("/api/v1/regions/{region_id}/subregions"))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let subregions: Vec<Value> = res.json().await;
+        assert_eq!(subregions, [created]);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_subregion_rejects_unknown_region() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let auth =
+            create_test_user_authorization(&dam, "test_subregion_orphan", Role::Editor).await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client
+            .post("/api/v1/subregions")
+            .header("Authorization", &auth)
+            .json(&json!({ "name": "test_subregion_orphan", "region_id": 999_999 }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let error: Value = res.json().await;
+        assert_eq!(error["error"]["code"], "invalid_reference");
+
+        // The region is required.
+        let res = client
+            .post("/api/v1/subregions")
+            .header("Authorization", &auth)
+            .json(&json!({ "name": "test_subregion_orphan" }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_get_all_subregions_paginated() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let region = RegionCreate {
+            name: "test_paginated_subregion_parent".to_string(),
+            description: None,
+        };
+        let region_id = celestial_region::Client::create(&ctx, &dam, region).await?;
+        for name in ["test_paginated_subregion_a", "test_paginated_subregion_b"] {
+            let data = SubregionCreate {
+                name: name.to_string(),
+                description: None,
+                region_id,
+            };
+            Client::create(&ctx, &dam, data).await?;
+        }
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client
+            .get("/api/v1/subregions?limit=1&offset=0")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let subregions: Vec<Value> = res.json().await;
+        assert_eq!(subregions.len(), 1);
+
+        let res = client.get("/api/v1/subregions?limit=500").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_get_all_subregions_rejects_out_of_range_pagination() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        for query in ["limit=501", "limit=0", "offset=-1"] {
+            let res = client
+                .get(&format!("/api/v1/subregions?{query}"))
+                .send()
+                .await;
+            assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY, "{query}");
+        }
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_missing_subregion_is_not_found() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let auth =
+            create_test_user_authorization(&dam, "test_missing_subregion", Role::Editor).await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client.get("/api/v1/subregions/999999").send().await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let res = client
+            .delete("/api/v1/subregions/999999")
+            .header("Authorization", &auth)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+}
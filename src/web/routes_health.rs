@@ -0,0 +1,190 @@
+use crate::data_access::DataAccessManager;
+use crate::web::AppState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// If the database has not answered within this window, the service is reported
+/// as degraded.
+const DB_PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+// -----------------------------------------------------------------------------
+// Types
+// -----------------------------------------------------------------------------
+
+/// Whether the service should be sent traffic, as reported by `/ready`. Not
+/// ready until the server is listening, and not ready again once shutdown
+/// begins (see `server::serve`), while `/live` keeps answering.
+#[derive(Clone, Default)]
+pub struct Readiness(Arc<AtomicBool>);
+
+impl Readiness {
+    pub fn is_ready(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub fn set_ready(&self, ready: bool) {
+        self.0.store(ready, Ordering::Relaxed);
+    }
+}
+
+#[derive(Serialize)]
+struct HealthResponse {
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    db_latency_ms: Option<u128>,
+}
+
+// -----------------------------------------------------------------------------
+// Routes
+// -----------------------------------------------------------------------------
+
+pub fn health_routes() -> Router<AppState> {
+    Router::new()
+        .route("/health", get(health))
+        .route("/live", get(live))
+        .route("/ready", get(ready))
+}
+
+// -----------------------------------------------------------------------------
+// Handlers
+// -----------------------------------------------------------------------------
+
+/// NOTE: this deliberately does not return the web `Error`: a failing database is
+///       an expected outcome here, not an error, and is reported in the body.
+async fn health(State(dam): State<DataAccessManager>) -> (StatusCode, Json<HealthResponse>) {
+    ping(&dam).await
+}
+
+/// Liveness: the process is up and serving requests. Nothing else is checked, so
+/// a struggling database never gets the process restarted.
+async fn live() -> (StatusCode, Json<HealthResponse>) {
+    (
+        StatusCode::OK,
+        Json(HealthResponse {
+            status: "ok",
+            db_latency_ms: None,
+        }),
+    )
+}
+
+/// Readiness: the server has started, is not shutting down, and the database
+/// answers. Otherwise a 503, so the load balancer routes around this instance.
+async fn ready(
+    State(readiness): State<Readiness>,
+    State(dam): State<DataAccessManager>,
+) -> (StatusCode, Json<HealthResponse>) {
+    if !readiness.is_ready() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(HealthResponse {
+                status: "not_ready",
+                db_latency_ms: None,
+            }),
+        );
+    }
+
+    ping(&dam).await
+}
+
+/// Ping the database, reporting `ok` (with the latency) or `degraded`.
+async fn ping(dam: &DataAccessManager) -> (StatusCode, Json<HealthResponse>) {
+    let start = Instant::now();
+
+    match tokio::time::timeout(DB_PING_TIMEOUT, dam.ping()).await {
+        Ok(Ok(())) => (
+            StatusCode::OK,
+            Json(HealthResponse {
+                status: "ok",
+                db_latency_ms: Some(start.elapsed().as_millis()),
+            }),
+        ),
+        Ok(Err(error)) => {
+            tracing::warn!("health check failed: {error}");
+            degraded()
+        }
+        Err(_) => {
+            tracing::warn!("health check timed out after {DB_PING_TIMEOUT:?}");
+            degraded()
+        }
+    }
+}
+
+fn degraded() -> (StatusCode, Json<HealthResponse>) {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(HealthResponse {
+            status: "degraded",
+            db_latency_ms: None,
+        }),
+    )
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils::initialise_test_environment;
+    use crate::web::construct_routes;
+    use anyhow::Result;
+    use axum_test_helper::TestClient;
+    use serde_json::Value;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_health_reports_ok() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client.get("/health").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body: Value = res.json().await;
+        assert_eq!(body["status"], "ok");
+        assert!(body["db_latency_ms"].is_u64());
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_live_and_ready_probes() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let state = AppState::new(dam);
+        let readiness = state.readiness.clone();
+        let client = TestClient::new(construct_routes(state));
+        let probe = |path: &'static str| {
+            let client = &client;
+            async move { client.get(path).send().await.status() }
+        };
+
+        // Not yet started.
+        assert_eq!(probe("/live").await, StatusCode::OK);
+        assert_eq!(probe("/ready").await, StatusCode::SERVICE_UNAVAILABLE);
+
+        readiness.set_ready(true);
+        assert_eq!(probe("/live").await, StatusCode::OK);
+        let res = client.get("/ready").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body: Value = res.json().await;
+        assert_eq!(body["status"], "ok");
+
+        // Shutting down: drained from the load balancer, but still alive.
+        readiness.set_ready(false);
+        assert_eq!(probe("/live").await, StatusCode::OK);
+        let res = client.get("/ready").send().await;
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body: Value = res.json().await;
+        assert_eq!(body["status"], "not_ready");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,307 @@
+use crate::data_access::model::user::{Server, UserAuth, UserCreate, UserLogin};
+use crate::data_access::DataAccessManager;
+use crate::security::{self, EncryptedContent};
+use crate::web::mw_auth::bearer_token;
+use crate::web::{AppState, Error, Result};
+use crate::{RequestContext, SystemContext};
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+
+// -----------------------------------------------------------------------------
+// Types
+// -----------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+struct LoginPayload {
+    username: String,
+    pwd_clear: String,
+}
+
+#[derive(Serialize)]
+struct LoginResponse {
+    token: String,
+}
+
+// -----------------------------------------------------------------------------
+// Routes
+// -----------------------------------------------------------------------------
+
+pub fn login_routes() -> Router<AppState> {
+    Router::new()
+        .route("/login", post(login))
+        .route("/token/refresh", post(refresh_token))
+        .route("/logout", post(logout))
+}
+
+// -----------------------------------------------------------------------------
+// Handlers
+// -----------------------------------------------------------------------------
+
+/// NOTE: every failure mode maps to the same 401 response, so the client cannot
+///       tell whether the username exists. The specific variant is still logged.
+async fn login(
+    State(dam): State<DataAccessManager>,
+    Json(payload): Json<LoginPayload>,
+) -> Result<Json<LoginResponse>> {
+    let LoginPayload {
+        username,
+        pwd_clear,
+    } = payload;
+    let ctx = RequestContext::from(SystemContext);
+
+    let Some(user) = Server::read_by_username::<UserLogin>(&ctx, &dam, &username).await? else {
+        // NOTE: hash anyway, so the response time does not reveal the username is unknown.
+        security::verify_dummy_password(&EncryptedContent {
+            content: pwd_clear,
+            salt: username,
+        });
+        return Err(Error::LoginFailUsernameNotFound);
+    };
+    let Some(pwd) = user.pwd else {
+        return Err(Error::LoginFailUserHasNoPwd { user_id: user.id });
+    };
+
+    let content = EncryptedContent {
+        content: pwd_clear,
+        salt: user.pwd_salt,
+    };
+    if !security::verify_password(&content, &pwd)? {
+        return Err(Error::LoginFailPwdNotMatching { user_id: user.id });
+    }
+    // NOTE: an outdated hash can only be upgraded while the cleartext is at hand,
+    //       but failing to is no reason to fail the login.
+    if security::needs_rehash(&pwd) {
+        if let Err(err) = Server::update_password(&ctx, &dam, user.id, &content.content).await {
+            tracing::warn!(user_id = user.id, %err, "failed to rehash password");
+        }
+    }
+
+    let token = security::generate_web_token(&user.username, &user.token_salt)?;
+
+    Ok(Json(LoginResponse {
+        token: token.to_string(),
+    }))
+}
+
+/// Exchange a valid, or recently expired, bearer token for a fresh one. Tokens
+/// that expired more than `TOKEN_REFRESH_GRACE_SECONDS` ago are rejected with a 401.
+async fn refresh_token(
+    State(dam): State<DataAccessManager>,
+    headers: HeaderMap,
+) -> Result<Json<LoginResponse>> {
+    let token = bearer_token(&headers)?;
+    let ctx = RequestContext::from(SystemContext);
+
+    let user: UserAuth = Server::read_by_username(&ctx, &dam, &token.ident)
+        .await?
+        .ok_or(Error::AuthFailUserNotFound)?;
+    security::validate_web_token_for_refresh(&token, &user.token_salt)
+        .map_err(|_| Error::AuthFailInvalidToken)?;
+
+    let token = security::generate_web_token(&user.username, &user.token_salt)?;
+
+    Ok(Json(LoginResponse {
+        token: token.to_string(),
+    }))
+}
+
+/// Sign the current user out everywhere: rotating their token salt invalidates
+/// every token issued to them, not just the one sent with this request.
+async fn logout(State(dam): State<DataAccessManager>, ctx: RequestContext) -> Result<StatusCode> {
+    Server::rotate_token_salt(&ctx, &dam, ctx.user_id()?).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils::{create_test_user_authorization, initialise_test_environment};
+    use crate::web::construct_routes;
+    use crate::Role;
+    use anyhow::Result;
+    use axum_test_helper::TestClient;
+    use serde_json::{json, Value};
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_login() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        Server::create_for_test(&ctx, &dam, "test_login", "welcome", Role::Viewer).await?;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        // Success
+        let res = client
+            .post("/api/v1/login")
+            .json(&json!({ "username": "test_login", "pwd_clear": "welcome" }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body: Value = res.json().await;
+        assert!(body["token"].is_string());
+
+        // Wrong password
+        let res = client
+            .post("/api/v1/login")
+            .json(&json!({ "username": "test_login", "pwd_clear": "wrong" }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        let mut wrong_password_body: Value = res.json().await;
+        // NOTE: the request id differs between any two responses.
+        wrong_password_body["error"]
+            .as_object_mut()
+            .unwrap()
+            .remove("request_id");
+
+        // Unknown user: indistinguishable from a wrong password.
+        let res = client
+            .post("/api/v1/login")
+            .json(&json!({ "username": "test_login_unknown", "pwd_clear": "welcome" }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        let mut unknown_user_body: Value = res.json().await;
+        unknown_user_body["error"]
+            .as_object_mut()
+            .unwrap()
+            .remove("request_id");
+        assert_eq!(unknown_user_body, wrong_password_body);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_login_after_create() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let data = UserCreate {
+            username: "test_login_after_create".to_string(),
+            pwd_clear: "welcome".to_string(),
+        };
+        Server::create(&ctx, &dam, data).await?;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client
+            .post("/api/v1/login")
+            .json(&json!({ "username": "test_login_after_create", "pwd_clear": "welcome" }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_login_upgrades_old_hashes() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let id = Server::create_for_test(&ctx, &dam, "test_login_rehash", "welcome", Role::Viewer)
+            .await?;
+        let user: UserLogin = Server::read(&ctx, &dam, id).await?;
+        let content = EncryptedContent {
+            content: "welcome".to_string(),
+            salt: user.pwd_salt,
+        };
+        let old = security::encrypt_password_with_scheme("01", &content)?;
+        Server::set_pwd_for_test(&dam, id, &old).await?;
+        let client = TestClient::new(construct_routes(AppState::new(dam.clone())));
+
+        let res = client
+            .post("/api/v1/login")
+            .json(&json!({ "username": "test_login_rehash", "pwd_clear": "welcome" }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let pwd = Server::read::<UserLogin>(&ctx, &dam, id)
+            .await?
+            .pwd
+            .unwrap();
+        assert!(pwd.starts_with("#02#"), "{pwd}");
+        assert!(!security::needs_rehash(&pwd));
+        assert!(security::verify_password(&content, &pwd)?);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_refresh_token() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let ctx = RequestContext::root_context();
+        let id = Server::create_for_test(&ctx, &dam, "test_refresh_token", "welcome", Role::Viewer)
+            .await?;
+        let user: UserAuth = Server::read(&ctx, &dam, id).await?;
+        let grace = crate::config::get_config().TOKEN_REFRESH_GRACE_SECONDS;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        // Expired, but within the grace window: a fresh, valid token is issued.
+        let token =
+            security::generate_web_token_with_duration(&user.username, &user.token_salt, -10.0)?;
+        let res = client
+            .post("/api/v1/token/refresh")
+            .header("Authorization", format!("Bearer {token}"))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body: Value = res.json().await;
+        let refreshed: security::Token = body["token"].as_str().unwrap().parse()?;
+        security::validate_web_token(&refreshed, &user.token_salt)?;
+
+        // Expired beyond the grace window.
+        let token = security::generate_web_token_with_duration(
+            &user.username,
+            &user.token_salt,
+            -(grace + 60.0),
+        )?;
+        let res = client
+            .post("/api/v1/token/refresh")
+            .header("Authorization", format!("Bearer {token}"))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        // No token at all.
+        let res = client.post("/api/v1/token/refresh").send().await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_logout() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let auth = create_test_user_authorization(&dam, "test_logout", Role::Viewer).await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client
+            .post("/api/v1/logout")
+            .header("Authorization", &auth)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+
+        // The token is no longer accepted, so a second logout is unauthenticated.
+        let res = client
+            .post("/api/v1/logout")
+            .header("Authorization", &auth)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+use crate::data_access::{DataAccessManager, PoolStats};
+use crate::web::prometheus::{self, DB_POOL_CONNECTIONS, DB_POOL_IDLE_CONNECTIONS};
+use crate::web::{AppState, Result};
+use crate::{RequestContext, Role};
+use axum::extract::State;
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+
+/// The content type of the Prometheus text exposition format.
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+// -----------------------------------------------------------------------------
+// Routes
+// -----------------------------------------------------------------------------
+
+pub fn metrics_routes() -> Router<AppState> {
+    // NOTE: installed now, rather than on the first scrape, so that requests made
+    //       before then are counted too.
+    prometheus::prometheus_handle();
+
+    Router::new()
+        .route("/metrics", get(prometheus_metrics))
+        .route("/metrics/pool", get(pool_metrics))
+}
+
+// -----------------------------------------------------------------------------
+// Handlers
+// -----------------------------------------------------------------------------
+
+/// Request counts and durations, and the pool gauges, for Prometheus to scrape.
+///
+/// NOTE: this is public, as scrapers do not usually authenticate; restrict access
+///       to it at the network level if the figures are sensitive.
+async fn prometheus_metrics(State(dam): State<DataAccessManager>) -> impl IntoResponse {
+    // NOTE: the gauges are set at scrape time, so they are always current.
+    let stats = dam.pool_stats();
+    metrics::gauge!(DB_POOL_CONNECTIONS, f64::from(stats.size));
+    metrics::gauge!(DB_POOL_IDLE_CONNECTIONS, stats.idle as f64);
+
+    (
+        [(CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)],
+        prometheus::prometheus_handle().render(),
+    )
+}
+
+/// NOTE: unlike the other reads, this is restricted to admins, as it describes
+///       the server rather than the catalogue.
+async fn pool_metrics(
+    State(dam): State<DataAccessManager>,
+    ctx: RequestContext,
+) -> Result<Json<PoolStats>> {
+    ctx.require(Role::Admin)?;
+
+    Ok(Json(dam.pool_stats()))
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils::{create_test_user_authorization, initialise_test_environment};
+    use crate::web::construct_routes;
+    use anyhow::Result;
+    use axum::http::StatusCode;
+    use axum_test_helper::TestClient;
+    use serde_json::Value;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_pool_metrics() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let admin = create_test_user_authorization(&dam, "test_metrics_admin", Role::Admin).await;
+        let editor =
+            create_test_user_authorization(&dam, "test_metrics_editor", Role::Editor).await;
+        let expected = dam.pool_stats();
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client
+            .get("/metrics/pool")
+            .header("Authorization", &admin)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body: Value = res.json().await;
+        assert_eq!(body["max_connections"], expected.max_connections);
+        assert!(body["size"].is_u64());
+        assert!(body["idle"].is_u64());
+
+        let res = client
+            .get("/metrics/pool")
+            .header("Authorization", &editor)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let res = client.get("/metrics/pool").send().await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        Ok(())
+    }
+
+    /// The number of health checks answered with a 200, according to `/metrics`.
+    fn health_check_count(metrics: &str) -> f64 {
+        metrics
+            .lines()
+            .find(|line| {
+                line.starts_with(prometheus::REQUESTS_TOTAL)
+                    && line.contains("path=\"/health\"")
+                    && line.contains("status=\"200\"")
+            })
+            .and_then(|line| line.rsplit(' ').next())
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0.0)
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_prometheus_metrics() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client.get("/metrics").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let before = health_check_count(&res.text().await);
+
+        let res = client.get("/health").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = client.get("/metrics").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()["content-type"], PROMETHEUS_CONTENT_TYPE);
+        let metrics = res.text().await;
+        assert_eq!(health_check_count(&metrics), before + 1.0);
+        assert!(
+            metrics.contains(prometheus::REQUEST_DURATION_SECONDS),
+            "{metrics}"
+        );
+        assert!(metrics.contains(DB_POOL_CONNECTIONS), "{metrics}");
+
+        Ok(())
+    }
+}
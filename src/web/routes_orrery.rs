@@ -0,0 +1,220 @@
+use crate::data_access::model::celestial_body::{
+    self, heliocentric_position_among, reference_epoch, CelestialBody,
+};
+use crate::data_access::model::celestial_region::{self, Region};
+use crate::data_access::model::celestial_subregion::{self, Subregion};
+use crate::data_access::DataAccessManager;
+use crate::generic_utils::{format_utc_time, now_utc, parse_utc};
+use crate::web::{public_read_context, AppState, Error, Result};
+use axum::extract::{Query, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// -----------------------------------------------------------------------------
+// Types
+// -----------------------------------------------------------------------------
+
+/// Query string for the snapshot: `?at=<RFC 3339 timestamp>`, now by default.
+#[derive(Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+struct SnapshotParams {
+    at: Option<String>,
+}
+
+/// Everything needed to draw the whole system at an instant, in one document.
+#[derive(Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub(crate) struct OrrerySnapshot {
+    /// The instant, as RFC 3339.
+    at: String,
+    regions: Vec<Region>,
+    subregions: Vec<Subregion>,
+    bodies: Vec<BodyState>,
+}
+
+/// A body with where it is at the snapshot's instant. See
+/// `CelestialBody::position_at` for the approximations made. Both are `null` if
+/// the position cannot be estimated, *eg* for an unbound orbit.
+#[derive(Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub(crate) struct BodyState {
+    #[serde(flatten)]
+    body: CelestialBody,
+    /// The distance from the Sun, in km: for a moon, via its parent.
+    distance_from_sun_at: Option<f64>,
+    /// The true anomaly around the body's primary, in radians in `[0, 2π)`.
+    true_anomaly_at: Option<f64>,
+}
+
+// -----------------------------------------------------------------------------
+// Routes
+// -----------------------------------------------------------------------------
+
+pub fn orrery_routes() -> Router<AppState> {
+    Router::new().route("/orrery", get(get_snapshot))
+}
+
+// -----------------------------------------------------------------------------
+// Handlers
+// -----------------------------------------------------------------------------
+
+/// Every region, subregion and body, with each body's position at `at`. Three
+/// queries, however many bodies there are: the positions of moons are worked
+/// out from the bodies already read.
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/orrery",
+    params(SnapshotParams),
+    responses(
+        (status = 200, description = "The state of the system at the instant",
+            body = OrrerySnapshot),
+        (status = 422, description = "Invalid timestamp"),
+    ),
+))]
+async fn get_snapshot(
+    State(dam): State<DataAccessManager>,
+    Query(SnapshotParams { at }): Query<SnapshotParams>,
+) -> Result<Json<OrrerySnapshot>> {
+    let epoch = match at {
+        Some(at) => parse_utc(&at)
+            .map_err(|_| Error::InvalidTimestamp(at))?
+            .with_timezone(&Utc),
+        None => now_utc(),
+    };
+    let ctx = public_read_context();
+    let regions = celestial_region::Client::read_all(&ctx, &dam).await?;
+    let subregions = celestial_subregion::Client::read_all(&ctx, &dam).await?;
+    let bodies = celestial_body::Client::read_all(&ctx, &dam).await?;
+
+    Ok(Json(OrrerySnapshot {
+        at: format_utc_time(epoch),
+        regions,
+        subregions,
+        bodies: body_states(bodies, epoch),
+    }))
+}
+
+fn body_states(bodies: Vec<CelestialBody>, epoch: DateTime<Utc>) -> Vec<BodyState> {
+    let by_id: HashMap<i64, &CelestialBody> = bodies.iter().map(|body| (body.id, body)).collect();
+    let positions: Vec<_> = bodies
+        .iter()
+        .map(|body| {
+            let (x, y) = heliocentric_position_among(body, &by_id, epoch).ok()?;
+            let true_anomaly = body.true_anomaly_at(epoch, reference_epoch()).ok()?;
+            Some((x.hypot(y), true_anomaly))
+        })
+        .collect();
+
+    bodies
+        .into_iter()
+        .zip(positions)
+        .map(|(body, position)| BodyState {
+            body,
+            distance_from_sun_at: position.map(|(distance, _)| distance),
+            true_anomaly_at: position.map(|(_, true_anomaly)| true_anomaly),
+        })
+        .collect()
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils::{body_create, initialise_test_environment, reset_database};
+    use crate::data_access::model::celestial_body::CelestialBodyCreate;
+    use crate::data_access::model::celestial_region::RegionCreate;
+    use crate::data_access::model::celestial_subregion::SubregionCreate;
+    use crate::web::construct_routes;
+    use crate::RequestContext;
+    use anyhow::Result;
+    use axum::http::StatusCode;
+    use axum_test_helper::TestClient;
+    use serde_json::Value;
+    use serial_test::serial;
+    use std::f64::consts::TAU;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_get_snapshot() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        reset_database(&dam).await?;
+        let ctx = RequestContext::root_context();
+        let region = RegionCreate {
+            name: "Inner Solar System".to_string(),
+            description: None,
+        };
+        let region_id = celestial_region::Client::create(&ctx, &dam, region).await?;
+        let subregion = SubregionCreate {
+            name: "Terrestrial planets".to_string(),
+            description: None,
+            region_id,
+        };
+        celestial_subregion::Client::create(&ctx, &dam, subregion).await?;
+        let earth = CelestialBodyCreate {
+            region: Some(region_id),
+            aphelion: 152_100_000.0,
+            perihelion: 147_095_000.0,
+            orbital_period: 365.256,
+            radius: 6_371.0,
+            mass: 5.972e24,
+            ..body_create("Earth")
+        };
+        let earth_id = celestial_body::Client::create(&ctx, &dam, earth).await?;
+        let moon = CelestialBodyCreate {
+            region: Some(region_id),
+            parent_id: Some(earth_id),
+            aphelion: 405_400.0,
+            perihelion: 362_600.0,
+            orbital_period: 27.322,
+            radius: 1_737.4,
+            mass: 7.342e22,
+            ..body_create("Moon")
+        };
+        celestial_body::Client::create(&ctx, &dam, moon).await?;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client
+            .get("/api/v1/orrery?at=2024-03-20T03:06:00Z")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let snapshot: Value = res.json().await;
+        assert_eq!(snapshot["at"], "2024-03-20T03:06:00+00:00");
+        assert_eq!(snapshot["regions"].as_array().unwrap().len(), 1);
+        assert_eq!(snapshot["subregions"].as_array().unwrap().len(), 1);
+        let bodies = snapshot["bodies"].as_array().unwrap();
+        assert_eq!(bodies.len(), 2);
+
+        let earth = &bodies[0];
+        assert_eq!(earth["name"], "Earth");
+        let distance = earth["distance_from_sun_at"].as_f64().unwrap();
+        assert!(
+            (147_095_000.0..=152_100_000.0).contains(&distance),
+            "{distance}"
+        );
+        let true_anomaly = earth["true_anomaly_at"].as_f64().unwrap();
+        assert!((0.0..TAU).contains(&true_anomaly), "{true_anomaly}");
+        // The Moon is never further from the Earth than its aphelion.
+        let moon_distance = bodies[1]["distance_from_sun_at"].as_f64().unwrap();
+        assert!(
+            (moon_distance - distance).abs() <= 405_400.0,
+            "{moon_distance}"
+        );
+
+        // Now, by default.
+        let res = client.get("/api/v1/orrery").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = client.get("/api/v1/orrery?at=tomorrow").send().await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        Ok(())
+    }
+}
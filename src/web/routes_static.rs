@@ -0,0 +1,63 @@
+use crate::web::{AppState, Error};
+use axum::handler::HandlerWithoutStateExt;
+use axum::Router;
+use std::path::Path;
+use tower_http::services::ServeDir;
+
+// -----------------------------------------------------------------------------
+// Routes
+// -----------------------------------------------------------------------------
+
+/// Serve the files in `folder` under `/assets`. Missing files, and paths that
+/// would resolve outside of `folder`, are answered with the web `Error`'s 404
+/// rather than anything describing the filesystem.
+pub fn static_routes(folder: impl AsRef<Path>) -> Router<AppState> {
+    let serve_dir = ServeDir::new(folder).not_found_service(asset_not_found.into_service());
+
+    Router::new().nest_service("/assets", serve_dir)
+}
+
+// -----------------------------------------------------------------------------
+// Handlers
+// -----------------------------------------------------------------------------
+
+async fn asset_not_found() -> Error {
+    Error::AssetNotFound
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils::initialise_test_environment;
+    use anyhow::Result;
+    use axum::http::StatusCode;
+    use axum_test_helper::TestClient;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_serves_assets_from_folder() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let folder = std::env::temp_dir().join(format!("orrery-assets-{}", std::process::id()));
+        std::fs::create_dir_all(&folder)?;
+        std::fs::write(folder.join("hello.txt"), "hello from the assets folder")?;
+        let client = TestClient::new(static_routes(&folder).with_state(AppState::new(dam)));
+
+        let res = client.get("/assets/hello.txt").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.text().await, "hello from the assets folder");
+
+        let res = client.get("/assets/missing.txt").send().await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let error: serde_json::Value = res.json().await;
+        assert_eq!(error["error"]["code"], "not_found");
+
+        std::fs::remove_dir_all(&folder)?;
+
+        Ok(())
+    }
+}
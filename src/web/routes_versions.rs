@@ -0,0 +1,108 @@
+use crate::web::{AppState, Error};
+use axum::extract::OriginalUri;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Serialize;
+
+// -----------------------------------------------------------------------------
+// Types
+// -----------------------------------------------------------------------------
+
+/// A version of the API, served under `/api/<version>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApiVersion {
+    V1,
+}
+
+impl ApiVersion {
+    /// Every version currently served, oldest first.
+    pub const ALL: [ApiVersion; 1] = [ApiVersion::V1];
+
+    /// The path segment, *eg* `v1`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "v1",
+        }
+    }
+
+    /// Deprecated versions are still served, but clients should move off them.
+    pub fn is_deprecated(&self) -> bool {
+        match self {
+            ApiVersion::V1 => false,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct VersionInfo {
+    version: &'static str,
+    deprecated: bool,
+}
+
+// -----------------------------------------------------------------------------
+// Routes
+// -----------------------------------------------------------------------------
+
+pub fn versions_routes() -> Router<AppState> {
+    Router::new().route("/versions", get(get_versions))
+}
+
+// -----------------------------------------------------------------------------
+// Handlers
+// -----------------------------------------------------------------------------
+
+async fn get_versions() -> Json<Vec<VersionInfo>> {
+    let versions = ApiVersion::ALL
+        .iter()
+        .map(|version| VersionInfo {
+            version: version.as_str(),
+            deprecated: version.is_deprecated(),
+        })
+        .collect();
+
+    Json(versions)
+}
+
+/// Fallback for everything under `/api` that no route matched, including any
+/// unknown version.
+pub async fn api_not_found(OriginalUri(uri): OriginalUri) -> Error {
+    Error::RouteNotFound(uri.path().to_string())
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::_dev_utils::initialise_test_environment;
+    use crate::web::{construct_routes, AppState};
+    use anyhow::Result;
+    use axum::http::StatusCode;
+    use axum_test_helper::TestClient;
+    use serde_json::{json, Value};
+
+    #[tokio::test]
+    async fn test_versioned_routes() -> Result<()> {
+        let dam = initialise_test_environment().await;
+        let client = TestClient::new(construct_routes(AppState::new(dam)));
+
+        let res = client.get("/api/versions").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body: Value = res.json().await;
+        assert_eq!(body, json!([{ "version": "v1", "deprecated": false }]));
+
+        let res = client.get("/api/v1/regions").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // The unversioned path is gone, and unknown versions are a JSON 404.
+        for path in ["/api/regions", "/api/v9/regions", "/api/v1/nothing-here"] {
+            let res = client.get(path).send().await;
+            assert_eq!(res.status(), StatusCode::NOT_FOUND, "{path}");
+            let body: Value = res.json().await;
+            assert_eq!(body["error"]["code"], "not_found", "{path}");
+        }
+
+        Ok(())
+    }
+}
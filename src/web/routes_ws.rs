@@ -0,0 +1,172 @@
+use crate::config::get_config;
+use crate::data_access::model::{celestial_body, celestial_region, celestial_subregion};
+use crate::data_access::{DataAccessManager, DbCrudServer, EntityEvent};
+use crate::web::AppState;
+use crate::RequestContext;
+use axum::extract::ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::Response;
+use axum::routing::get;
+use axum::Router;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::time::{interval_at, timeout, Instant};
+
+/// The tables whose writes are streamed; anything else (*eg* users) is not.
+const STREAMED_TABLES: [&str; 3] = [
+    celestial_body::Client::TABLE,
+    celestial_region::Client::TABLE,
+    celestial_subregion::Client::TABLE,
+];
+
+// -----------------------------------------------------------------------------
+// Routes
+// -----------------------------------------------------------------------------
+
+pub fn ws_routes() -> Router<AppState> {
+    Router::new().route("/ws", get(upgrade))
+}
+
+// -----------------------------------------------------------------------------
+// Handlers
+// -----------------------------------------------------------------------------
+
+/// Upgrade to a WebSocket streaming every committed write to a body, region or
+/// subregion, as a JSON `EntityEvent` per text message, *eg*
+/// `{"table":"celestial_body","op":"update","id":3}`. Unlike the other reads,
+/// this needs a token.
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/api/v1/ws",
+    responses(
+        (status = 101, description = "Switched to a WebSocket streaming `{table, op, id}` \
+            events"),
+        (status = 401, description = "Missing or invalid token"),
+    ),
+    security(("bearer" = [])),
+))]
+async fn upgrade(
+    _ctx: RequestContext,
+    State(dam): State<DataAccessManager>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    // NOTE: subscribed before the upgrade, so no write made once the client has
+    //       its 101 response is missed.
+    let events = dam.subscribe();
+
+    ws.on_upgrade(move |socket| stream_events(socket, events))
+}
+
+/// Forward events to the client until either side goes away, pinging it every
+/// `WS_PING_SECONDS`. Writers never wait on a client: one that falls too far
+/// behind, or does not take a message within the ping interval, is dropped, and
+/// can reconnect and reload.
+async fn stream_events(mut socket: WebSocket, mut events: broadcast::Receiver<EntityEvent>) {
+    let period = Duration::from_secs(get_config().WS_PING_SECONDS.max(1));
+    let mut ping = interval_at(Instant::now() + period, period);
+
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) if STREAMED_TABLES.contains(&event.table) => {
+                    let json = serde_json::to_string(&event).unwrap_or_default();
+                    if !send(&mut socket, Message::Text(json), period).await {
+                        break;
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::info!(missed, "dropping a websocket client that fell behind");
+                    let close = CloseFrame {
+                        code: close_code::AGAIN,
+                        reason: "too far behind".into(),
+                    };
+                    send(&mut socket, Message::Close(Some(close)), period).await;
+                    break;
+                }
+                Err(RecvError::Closed) => break,
+            },
+            _ = ping.tick() => {
+                if !send(&mut socket, Message::Ping(Vec::new()), period).await {
+                    break;
+                }
+            }
+            // NOTE: nothing is expected from the client; reading is still needed to
+            //       answer its pings and notice it closing.
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+/// Whether `message` was sent within `limit`.
+async fn send(socket: &mut WebSocket, message: Message, limit: Duration) -> bool {
+    matches!(timeout(limit, socket.send(message)).await, Ok(Ok(())))
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils::{create_test_user_authorization, spawn_test_app};
+    use crate::web::construct_routes;
+    use crate::Role;
+    use anyhow::Result;
+    use axum::http::StatusCode;
+    use axum_test_helper::TestClient;
+    use futures_util::StreamExt;
+    use serde_json::{json, Value};
+    use serial_test::serial;
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+    use tokio_tungstenite::tungstenite::Message as ClientMessage;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_writes_are_streamed() -> Result<()> {
+        let app = spawn_test_app().await;
+        let auth = create_test_user_authorization(&app.dam, "test_ws", Role::Editor).await;
+        let client = TestClient::new(construct_routes(AppState::new(app.dam.clone())));
+
+        let res = client.get("/api/v1/ws").send().await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let mut request = format!("ws://{}/api/v1/ws", app.addr).into_client_request()?;
+        request.headers_mut().insert("Authorization", auth.parse()?);
+        let (mut socket, _) = tokio_tungstenite::connect_async(request).await?;
+
+        let res = client
+            .post("/api/v1/bodies")
+            .header("Authorization", &auth)
+            .json(&json!({
+                "name": "test_ws",
+                "aphelion": 0.0,
+                "perihelion": 0.0,
+                "orbital_period": 0.0,
+                "radius": 0.0,
+                "mass": 0.0,
+            }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let id = res.json::<Value>().await["id"].clone();
+
+        let message = timeout(Duration::from_secs(5), socket.next())
+            .await?
+            .expect("the socket is still open")?;
+        let ClientMessage::Text(text) = message else {
+            panic!("expected a text message, got {message:?}");
+        };
+        let event: Value = serde_json::from_str(&text)?;
+        assert_eq!(
+            event,
+            json!({ "table": "celestial_body", "op": "create", "id": id })
+        );
+
+        Ok(())
+    }
+}
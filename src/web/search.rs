@@ -0,0 +1,15 @@
+use serde::Deserialize;
+
+/// The most rows a search endpoint will return.
+pub const SEARCH_LIMIT: i64 = 50;
+
+/// The most suggestions a `/suggest` endpoint will return.
+pub const SUGGEST_LIMIT: usize = 10;
+
+/// Query string for the `/search` endpoints: `?q=<part of a name>`.
+#[derive(Debug, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+pub struct SearchParams {
+    pub q: String,
+}
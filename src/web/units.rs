@@ -0,0 +1,162 @@
+use crate::data_access::model::celestial_body::{KM_PER_AU, KM_PER_EARTH_RADIUS};
+use crate::web::Error;
+use axum::async_trait;
+use axum::extract::{FromRequestParts, Query};
+use axum::http::request::Parts;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Asks for distances in a unit other than km, as an alternative to `?units=`.
+pub const ACCEPT_UNITS_HEADER: &str = "accept-units";
+
+/// The length fields of a body, converted by `in_units`.
+const LENGTH_FIELDS: [&str; 3] = ["radius", "aphelion", "perihelion"];
+
+// -----------------------------------------------------------------------------
+// Units
+// -----------------------------------------------------------------------------
+
+/// A unit for the lengths of a body in a response. Stored lengths are always km.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Units {
+    Km,
+    Au,
+    EarthRadii,
+}
+
+impl Units {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "km" => Some(Self::Km),
+            "au" => Some(Self::Au),
+            "earth_radii" => Some(Self::EarthRadii),
+            _ => None,
+        }
+    }
+
+    fn km_per_unit(self) -> f64 {
+        match self {
+            Self::Km => 1.0,
+            Self::Au => KM_PER_AU,
+            Self::EarthRadii => KM_PER_EARTH_RADIUS,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct UnitsParams {
+    units: Option<String>,
+}
+
+/// The units asked for with `?units=km|au|earth_radii`, or failing that the
+/// `Accept-Units` header; `None` if neither was given. Anything else is
+/// rejected with `Error::InvalidUnits`.
+pub struct RequestedUnits(pub Option<Units>);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for RequestedUnits {
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let from_query = Query::<UnitsParams>::from_request_parts(parts, state)
+            .await
+            .ok()
+            .and_then(|Query(params)| params.units);
+        let from_header = || {
+            let value = parts.headers.get(ACCEPT_UNITS_HEADER)?;
+            Some(String::from_utf8_lossy(value.as_bytes()).into_owned())
+        };
+
+        match from_query.or_else(from_header) {
+            Some(value) => Units::parse(&value)
+                .map(|units| Self(Some(units)))
+                .ok_or(Error::InvalidUnits(value)),
+            None => Ok(Self(None)),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Conversion
+// -----------------------------------------------------------------------------
+
+/// `value` as JSON, with every body in it (at any depth, so lists and pages too)
+/// converted to `units`: its lengths are rewritten, and it gains the
+/// `semi_major_axis` and the `units` themselves.
+///
+/// NOTE: a body is recognised by having all of `LENGTH_FIELDS` as numbers.
+pub fn in_units(value: impl Serialize, units: Units) -> Value {
+    let mut value = serde_json::to_value(value).unwrap_or_default();
+    convert(&mut value, units);
+
+    value
+}
+
+fn convert(value: &mut Value, units: Units) {
+    match value {
+        Value::Array(items) => items.iter_mut().for_each(|item| convert(item, units)),
+        Value::Object(fields) => {
+            let lengths: Option<Vec<f64>> = LENGTH_FIELDS
+                .iter()
+                .map(|field| fields.get(*field).and_then(Value::as_f64))
+                .collect();
+            let Some(lengths) = lengths else {
+                fields.values_mut().for_each(|field| convert(field, units));
+                return;
+            };
+
+            let km_per_unit = units.km_per_unit();
+            for (field, km) in LENGTH_FIELDS.iter().zip(&lengths) {
+                fields.insert(field.to_string(), (km / km_per_unit).into());
+            }
+            // NOTE: the mean of the aphelion and perihelion, as in `semi_major_axis`.
+            let semi_major_axis = (lengths[1] + lengths[2]) / 2.0 / km_per_unit;
+            fields.insert("semi_major_axis".to_string(), semi_major_axis.into());
+            fields.insert(
+                "units".to_string(),
+                serde_json::to_value(units).unwrap_or_default(),
+            );
+        }
+        _ => {}
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Units::parse("km"), Some(Units::Km));
+        assert_eq!(Units::parse(" AU "), Some(Units::Au));
+        assert_eq!(Units::parse("earth_radii"), Some(Units::EarthRadii));
+        assert_eq!(Units::parse("furlongs"), None);
+    }
+
+    #[test]
+    fn test_in_units_finds_nested_bodies() {
+        let body = json!({
+            "name": "Moon",
+            "radius": 1_737.4,
+            "aphelion": 3.0 * KM_PER_EARTH_RADIUS,
+            "perihelion": KM_PER_EARTH_RADIUS,
+        });
+        let page = json!({ "bodies": [body], "next_cursor": null });
+
+        let converted = in_units(page, Units::EarthRadii);
+
+        let moon = &converted["bodies"][0];
+        assert_eq!(moon["name"], "Moon");
+        assert_eq!(moon["aphelion"], 3.0);
+        assert_eq!(moon["perihelion"], 1.0);
+        assert_eq!(moon["semi_major_axis"], 2.0);
+        assert_eq!(moon["units"], "earth_radii");
+        assert!(converted["next_cursor"].is_null());
+    }
+}
@@ -0,0 +1,250 @@
+use crate::data_access::model::celestial_body::CelestialBodyCreate;
+use crate::data_access::model::celestial_region::RegionCreate;
+use crate::data_access::model::celestial_subregion::SubregionCreate;
+use crate::web::Error;
+use axum::async_trait;
+use axum::body::HttpBody;
+use axum::extract::FromRequest;
+use axum::http::{Request, StatusCode};
+use axum::{BoxError, Json};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+pub const NAME_MAX_LENGTH: usize = 100;
+
+// -----------------------------------------------------------------------------
+// Field errors
+// -----------------------------------------------------------------------------
+
+/// One invalid field of a request body, as reported to the client.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    fn new(field: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Field-level checks on an incoming DTO, run by `ValidJson` before the handler.
+/// Every failing field is reported, not just the first.
+pub trait Validate {
+    fn validate(&self) -> Vec<FieldError>;
+}
+
+/// Items of a list are reported by position, *eg* `1.name`.
+impl<T: Validate> Validate for Vec<T> {
+    fn validate(&self) -> Vec<FieldError> {
+        self.iter()
+            .enumerate()
+            .flat_map(|(index, item)| {
+                item.validate().into_iter().map(move |err| FieldError {
+                    field: format!("{index}.{}", err.field),
+                    message: err.message,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Ids carry no field-level constraints: an id with no row is for the handler
+/// to report (or skip).
+impl Validate for i64 {
+    fn validate(&self) -> Vec<FieldError> {
+        Vec::new()
+    }
+}
+
+/// Shared with the `Validate` impls for the route-local update payloads.
+pub(crate) fn check_name(errors: &mut Vec<FieldError>, name: &str) {
+    if name.trim().is_empty() {
+        errors.push(FieldError::new("name", "must not be empty"));
+    } else if name.chars().count() > NAME_MAX_LENGTH {
+        errors.push(FieldError::new(
+            "name",
+            format!("must be at most {NAME_MAX_LENGTH} characters"),
+        ));
+    }
+}
+
+fn check_non_negative(errors: &mut Vec<FieldError>, field: &str, value: f64) {
+    // NOTE: written so that NaN is rejected too.
+    if !(value >= 0.0) {
+        errors.push(FieldError::new(field, "must not be negative"));
+    }
+}
+
+impl Validate for RegionCreate {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        check_name(&mut errors, &self.name);
+
+        errors
+    }
+}
+
+impl Validate for SubregionCreate {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        check_name(&mut errors, &self.name);
+
+        errors
+    }
+}
+
+impl Validate for CelestialBodyCreate {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        check_name(&mut errors, &self.name);
+        check_non_negative(&mut errors, "aphelion", self.aphelion);
+        check_non_negative(&mut errors, "perihelion", self.perihelion);
+        check_non_negative(&mut errors, "orbital_period", self.orbital_period);
+        check_non_negative(&mut errors, "radius", self.radius);
+        check_non_negative(&mut errors, "mass", self.mass);
+        if self.perihelion > self.aphelion {
+            errors.push(FieldError::new(
+                "perihelion",
+                "must not be greater than the aphelion",
+            ));
+        }
+
+        errors
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Extractor
+// -----------------------------------------------------------------------------
+
+/// As `Json`, but a body that fails to deserialize is rejected with
+/// `Error::InvalidJson`, and one that fails `Validate` with `Error::Validation`,
+/// both 422s in the usual error format. One over the route's size limit (see
+/// `limit_body`) is still a 413.
+///
+/// NOTE: this includes a missing `Content-Type: application/json`, which plain
+///       `Json` rejects with a 415.
+pub struct ValidJson<T>(pub T);
+
+#[async_trait]
+impl<T, S, B> FromRequest<S, B> for ValidJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+{
+    type Rejection = Error;
+
+    async fn from_request(req: Request<B>, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|rejection| {
+                if rejection.status() == StatusCode::PAYLOAD_TOO_LARGE {
+                    Error::PayloadTooLarge
+                } else {
+                    Error::InvalidJson(rejection.body_text())
+                }
+            })?;
+
+        let errors = value.validate();
+        if !errors.is_empty() {
+            return Err(Error::Validation(errors));
+        }
+
+        Ok(Self(value))
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils::body_create;
+
+    fn body() -> CelestialBodyCreate {
+        CelestialBodyCreate {
+            aphelion: 152_100_000.0,
+            perihelion: 147_095_000.0,
+            orbital_period: 365.256,
+            radius: 6_371.0,
+            mass: 5.972e24,
+            ..body_create("Earth")
+        }
+    }
+
+    fn fields(errors: Vec<FieldError>) -> Vec<String> {
+        errors.into_iter().map(|err| err.field).collect()
+    }
+
+    #[test]
+    fn test_valid_dtos_pass() {
+        assert!(body().validate().is_empty());
+        let region = RegionCreate {
+            name: "Inner Solar System".to_string(),
+            description: None,
+        };
+        assert!(region.validate().is_empty());
+    }
+
+    #[test]
+    fn test_each_invalid_body_field() {
+        let cases: [(fn(&mut CelestialBodyCreate), &str); 8] = [
+            (|body| body.name = " ".to_string(), "name"),
+            (|body| body.name = "x".repeat(NAME_MAX_LENGTH + 1), "name"),
+            (|body| body.aphelion = -1.0, "aphelion"),
+            (|body| body.perihelion = -1.0, "perihelion"),
+            (|body| body.orbital_period = -1.0, "orbital_period"),
+            (|body| body.radius = -1.0, "radius"),
+            (|body| body.mass = f64::NAN, "mass"),
+            (|body| body.perihelion = body.aphelion + 1.0, "perihelion"),
+        ];
+
+        for (invalidate, field) in cases {
+            let mut invalid = body();
+            invalidate(&mut invalid);
+            assert_eq!(fields(invalid.validate()), [field]);
+        }
+
+        // A name of exactly the maximum length is fine.
+        let mut longest = body();
+        longest.name = "x".repeat(NAME_MAX_LENGTH);
+        assert!(longest.validate().is_empty());
+    }
+
+    #[test]
+    fn test_each_invalid_region_and_subregion_field() {
+        let region = RegionCreate {
+            name: String::new(),
+            description: None,
+        };
+        assert_eq!(fields(region.validate()), ["name"]);
+        let subregion = SubregionCreate {
+            name: "x".repeat(NAME_MAX_LENGTH + 1),
+            description: None,
+            region_id: 1,
+        };
+        assert_eq!(fields(subregion.validate()), ["name"]);
+    }
+
+    #[test]
+    fn test_list_errors_are_reported_by_position() {
+        let mut invalid = body();
+        invalid.name = String::new();
+        invalid.radius = -1.0;
+
+        assert_eq!(
+            fields(vec![body(), invalid].validate()),
+            ["1.name", "1.radius"]
+        );
+    }
+}